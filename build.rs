@@ -0,0 +1,516 @@
+//! Generate the C header and the pkg-config file of the library.
+//!
+//! Every `#[no_mangle] pub extern "C"` function found in the crate's sources is declared in
+//! `sorting.h`, with its doc comment, as are the functions exported by the invocations of the
+//! macros of `FAMILIES`. The sources are parsed with `syn`, and the build fails if an export
+//! cannot be declared.
+//!
+//! Both files are written to the build's output directory, `OUT_DIR`, as `sorting.h` and
+//! `sorting.pc.in`, whose prefix is `@PREFIX@`. The `install-c` binary copies them along with the
+//! libraries (see `src/bin/install-c.rs`).
+
+extern crate proc_macro2;
+extern crate syn;
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{TokenStream, TokenTree};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, FnArg, GenericArgument, Ident, Item, Lit, Meta, Pat, PathArguments,
+          ReturnType, Token, Type};
+
+/// An exported function.
+struct Export {
+    name: String,
+    doc: Vec<String>,
+    params: Vec<(String, String)>,
+    ret: Option<String>,
+}
+
+/// A macro exporting a family of functions, one per type of the elements.
+///
+/// The macro is invoked with the function implementing the exports, and the name and the element
+/// type of every export, those of the integer types first (see `Invocation`). The exports of the
+/// floating point types take a NaN policy as their last parameter.
+struct Family {
+    /// Name of the macro.
+    name: &'static str,
+    /// Parameters of the exports and their C types, `T` standing for the type of the elements.
+    params: &'static [(&'static str, &'static str)],
+    /// Documentation of an export, given its name.
+    doc: fn(&str) -> Vec<String>,
+}
+
+/// Documentation of the `policy` parameter of the exports of floating point values.
+const NAN_POLICY_DOC: &[&str] = &[
+    "NaNs are placed following `policy`, a `sorting_nan_policy`: with",
+    "`SORTING_NAN_POLICY_ERROR`, returns `SORTING_STATUS_INVALID_ARGUMENT` if the array",
+    "contains a NaN and leaves it untouched.",
+];
+
+/// Document the exports of `payload_exports!`.
+fn payload_doc(name: &str) -> Vec<String> {
+    let algorithm = name.trim_start_matches("ffi_").split("_payload_").next().unwrap();
+    vec![format!("Sort the `n` keys at `keys_pointer` in ascending order using {}, moving the \
+                  records of", algorithm),
+         "`size` bytes at `values_pointer` along with them.".to_string()]
+}
+
+/// Document the exports of `select_exports!`.
+fn select_doc(_: &str) -> Vec<String> {
+    vec!["Re-arrange the `n` values at `array_pointer` so that the value at index `k` is the one \
+          which".to_string(),
+         "would be there if they were sorted.".to_string()]
+}
+
+/// Document the exports of `argsort_exports!`.
+fn argsort_doc(_: &str) -> Vec<String> {
+    vec!["Write to `indices` the permutation sorting the `n` values at `array_pointer`, which are \
+          left".to_string(),
+         "untouched. The sort is stable, like NumPy's `argsort(kind='stable')`.".to_string()]
+}
+
+/// The macros exporting families of functions.
+const FAMILIES: &[Family] = &[
+    Family {
+        name: "payload_exports",
+        params: &[("keys_pointer", "T *"), ("n", "size_t"), ("values_pointer", "void *"),
+                  ("size", "size_t")],
+        doc: payload_doc,
+    },
+    Family {
+        name: "select_exports",
+        params: &[("array_pointer", "T *"), ("n", "size_t"), ("k", "size_t")],
+        doc: select_doc,
+    },
+    Family {
+        name: "argsort_exports",
+        params: &[("array_pointer", "const T *"), ("n", "size_t"), ("indices", "size_t *")],
+        doc: argsort_doc,
+    },
+];
+
+/// An export of a family: `name: type`.
+struct FamilyExport {
+    name: Ident,
+    element_type: Type,
+}
+
+impl Parse for FamilyExport {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        Ok(FamilyExport { name, element_type: input.parse()? })
+    }
+}
+
+/// Arguments of the macro of a family:
+///
+/// ```ignore
+/// function;
+/// integers { ffi_name_i8: i8, ... }
+/// floats { ffi_name_f32: f32, ... }
+/// ```
+struct Invocation {
+    integers: Vec<FamilyExport>,
+    floats: Vec<FamilyExport>,
+}
+
+/// Parse the exports listed in the `label { ... }` group of an invocation.
+fn parse_group(input: ParseStream, label: &str) -> syn::Result<Vec<FamilyExport>> {
+    let ident: Ident = input.parse()?;
+    if ident != label {
+        return Err(syn::Error::new(ident.span(), format!("expected `{}`", label)));
+    }
+    let content;
+    syn::braced!(content in input);
+    Ok(Punctuated::<FamilyExport, Token![,]>::parse_terminated(&content)?.into_iter().collect())
+}
+
+impl Parse for Invocation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<syn::Path>()?;
+        input.parse::<Token![;]>()?;
+        let integers = parse_group(input, "integers")?;
+        let floats = parse_group(input, "floats")?;
+        Ok(Invocation { integers, floats })
+    }
+}
+
+/// List the Rust source files, sorted so the header does not depend on the file system.
+fn source_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().path()).collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            source_files(&path, files);
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+/// Convert a Rust FFI type to C, `None` if it is not supported.
+fn c_type(rust_type: &Type) -> Option<String> {
+    match rust_type {
+        Type::Ptr(pointer) => {
+            let pointee = c_type(&pointer.elem)?;
+            if pointer.mutability.is_some() {
+                Some(format!("{} *", pointee))
+            } else if pointee.ends_with('*') {
+                // A pointer to a constant pointer: `const void *const *`.
+                Some(format!("{}const *", pointee))
+            } else {
+                Some(format!("const {} *", pointee))
+            }
+        },
+        Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            let name = segment.ident.to_string();
+            let c = match name.as_str() {
+                "int8_t" | "int16_t" | "int32_t" | "int64_t" |
+                "uint8_t" | "uint16_t" | "uint32_t" | "uint64_t" |
+                "size_t" => name.as_str(),
+                "i8" => "int8_t",
+                "i16" => "int16_t",
+                "i32" => "int32_t",
+                "i64" => "int64_t",
+                "u8" => "uint8_t",
+                "u16" => "uint16_t",
+                "u32" => "uint32_t",
+                "u64" => "uint64_t",
+                "c_int" => "int",
+                "c_float" | "f32" => "float",
+                "c_double" | "f64" => "double",
+                "c_void" => "void",
+                "Comparator" => "sorting_comparator",
+                "SortStatus" => "sorting_status",
+                // A nullable function pointer.
+                "Option" => return match segment.arguments {
+                    PathArguments::AngleBracketed(ref arguments) => match arguments.args.first() {
+                        Some(GenericArgument::Type(function)) => c_type(function),
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                _ => return None,
+            };
+            Some(c.to_string())
+        },
+        _ => None,
+    }
+}
+
+/// Convert the Rust type `rust_type` of the export `name` to C.
+fn export_c_type(rust_type: &Type, name: &str) -> String {
+    c_type(rust_type).unwrap_or_else(|| {
+        panic!("Cannot convert a type of the exported function `{}` to C", name)
+    })
+}
+
+/// Lines of the doc comment of an item.
+fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    attrs.iter().filter_map(|attr| match attr.meta {
+        Meta::NameValue(ref doc) if doc.path.is_ident("doc") => match doc.value {
+            Expr::Lit(ref lit) => match lit.lit {
+                Lit::Str(ref line) => Some(line.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }).collect()
+}
+
+/// Parse an exported function.
+fn parse_function(function: &syn::ItemFn) -> Export {
+    let name = function.sig.ident.to_string();
+    let is_c = function.sig.abi.as_ref()
+                               .and_then(|abi| abi.name.as_ref())
+                               .is_some_and(|abi| abi.value() == "C");
+    if !is_c {
+        panic!("The exported function `{}` is not `extern \"C\"`", name);
+    }
+    let params = function.sig.inputs.iter().map(|param| match *param {
+        FnArg::Typed(ref param) => match *param.pat {
+            Pat::Ident(ref ident) => (ident.ident.to_string(), export_c_type(&param.ty, &name)),
+            _ => panic!("Unsupported parameter pattern in the exported function `{}`", name),
+        },
+        FnArg::Receiver(_) => panic!("The exported function `{}` is a method", name),
+    }).collect();
+    let ret = match function.sig.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ref ret) => Some(export_c_type(ret, &name)),
+    };
+    Export { name, doc: doc_lines(&function.attrs), params, ret }
+}
+
+/// Declare the exports listed by an invocation of the macro of `family`.
+fn parse_family(family: &Family, tokens: TokenStream, exports: &mut Vec<Export>) {
+    let invocation: Invocation = syn::parse2(tokens).unwrap_or_else(|error| {
+        panic!("Cannot parse an invocation of `{}!`: {}", family.name, error)
+    });
+    let listed = invocation.integers.iter().map(|export| (export, false))
+                           .chain(invocation.floats.iter().map(|export| (export, true)));
+    for (export, is_float) in listed {
+        let name = export.name.to_string();
+        let element_type = export_c_type(&export.element_type, &name);
+        let mut params: Vec<(String, String)> = family.params.iter().map(|&(param, c)| {
+            (param.to_string(), c.replace('T', &element_type))
+        }).collect();
+        let mut doc = (family.doc)(&name);
+        if is_float {
+            params.push(("policy".to_string(), "int".to_string()));
+            doc.extend(NAN_POLICY_DOC.iter().map(|line| line.to_string()));
+        }
+        exports.push(Export { name, doc, params, ret: Some("sorting_status".to_string()) });
+    }
+}
+
+/// Number of `no_mangle` identifiers in `tokens`, including in attributes and macros.
+fn count_no_mangle(tokens: TokenStream) -> usize {
+    tokens.into_iter().map(|tree| match tree {
+        TokenTree::Ident(ref ident) if ident == "no_mangle" => 1,
+        TokenTree::Group(group) => count_no_mangle(group.stream()),
+        _ => 0,
+    }).sum()
+}
+
+/// Find the exported functions of `items`: those with a `#[no_mangle]` attribute and those listed
+/// by the invocations of the macros of `FAMILIES`. Returns the number of `no_mangle` identifiers
+/// accounted for, in the attributes of these functions and in the definitions of these macros.
+fn parse_items(items: &[Item], exports: &mut Vec<Export>) -> usize {
+    let mut accounted = 0;
+    for item in items {
+        match *item {
+            Item::Fn(ref function)
+                if function.attrs.iter().any(|attr| attr.path().is_ident("no_mangle")) => {
+                exports.push(parse_function(function));
+                accounted += 1;
+            },
+            Item::Mod(ref module) => {
+                if let Some((_, ref items)) = module.content {
+                    accounted += parse_items(items, exports);
+                }
+            },
+            Item::Macro(ref item) => {
+                // `macro_rules! name { ... }` defines `name`, `name! { ... }` invokes it.
+                let name = match item.ident {
+                    Some(ref name) => name,
+                    None           => &item.mac.path.segments.last().unwrap().ident,
+                };
+                let tokens = item.mac.tokens.clone();
+                match FAMILIES.iter().find(|family| name == family.name) {
+                    Some(_) if item.ident.is_some() => accounted += count_no_mangle(tokens),
+                    Some(family)                    => parse_family(family, tokens, exports),
+                    None                            => {},
+                }
+            },
+            _ => {},
+        }
+    }
+    accounted
+}
+
+/// Find the exported functions of a source file.
+///
+/// # Panics
+///
+/// Panics if an export could not be parsed: every `no_mangle` identifier must either be the
+/// attribute of an exported function, or be in the definition of the macro of a family.
+///
+fn parse_exports(path: &Path) -> Vec<Export> {
+    let source = fs::read_to_string(path).unwrap();
+    let file = syn::parse_file(&source).unwrap_or_else(|error| {
+        panic!("Cannot parse {}: {}", path.display(), error)
+    });
+    let mut exports = Vec::new();
+    let accounted = parse_items(&file.items, &mut exports);
+    if count_no_mangle(source.parse().unwrap()) != accounted {
+        panic!("{} contains an exported function which could not be parsed", path.display());
+    }
+    exports
+}
+/// Document the exports sorting an array of numbers, which do not have a doc comment.
+fn default_doc(export: &Export) -> Vec<String> {
+    let (algorithm, number_type) = export.name.trim_start_matches("ffi_").rsplit_once('_').unwrap();
+    let algorithm = match algorithm {
+        "sort" => "Rust's standard library sort",
+        algorithm => algorithm,
+    };
+    let mut doc = vec![format!("Sort the `n` values at `array_pointer` in ascending order using {}.",
+                               algorithm)];
+    if number_type.starts_with('f') {
+        doc.extend(NAN_POLICY_DOC.iter().map(|line| line.to_string()));
+    }
+    doc
+}
+
+/// Convert `CamelCase` to `CAMEL_CASE`.
+fn upper_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            result.push('_');
+        }
+        result.push(c.to_ascii_uppercase());
+    }
+    result
+}
+
+/// Declare the `name` enum of the Rust source file `path` as `c_name`, with its doc comments.
+fn write_enum<W: Write>(header: &mut W, path: &str, name: &str, c_name: &str) {
+    let file = syn::parse_file(&fs::read_to_string(path).unwrap()).unwrap();
+    let item = file.items.iter().filter_map(|item| match *item {
+        Item::Enum(ref item) if item.ident == name => Some(item),
+        _ => None,
+    }).next().unwrap_or_else(|| panic!("Cannot find the `{}` enum in {}", name, path));
+
+    writeln!(header, "/** {} */", doc_lines(&item.attrs).join(" ")).unwrap();
+    writeln!(header, "typedef enum {} {{", c_name).unwrap();
+    for variant in &item.variants {
+        // Doc comments spanning several lines are joined.
+        writeln!(header, "    /** {} */", doc_lines(&variant.attrs).join(" ")).unwrap();
+        let value = match variant.discriminant {
+            Some((_, Expr::Lit(ref lit))) => match lit.lit {
+                Lit::Int(ref value) => value.to_string(),
+                _ => panic!("The `{}::{}` discriminant is not an integer", name, variant.ident),
+            },
+            _ => panic!("`{}::{}` has no literal discriminant", name, variant.ident),
+        };
+        writeln!(header, "    {}_{} = {},", c_name.to_uppercase(),
+                 upper_snake_case(&variant.ident.to_string()), value).unwrap();
+    }
+    writeln!(header, "}} {};\n", c_name).unwrap();
+}
+
+/// Beginning of the header, up to the declarations.
+const HEADER_START: &str = "\
+#ifndef SORTING_H
+#define SORTING_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern \"C\" {
+#endif
+";
+
+/// Declaration of the comparator type of the generic functions.
+const COMPARATOR: &str = "\
+/** Comparator of the generic functions: returns a negative, zero or positive integer if `a` is
+ *  respectively smaller than, equal to or larger than `b`. `ctx` is the pointer given to the
+ *  sorting function.
+ */
+typedef int (*sorting_comparator)(const void *a, const void *b, void *ctx);
+";
+
+/// End of the header.
+const HEADER_END: &str = "\
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SORTING_H */";
+
+/// Declare an exported function, with its documentation.
+fn write_export<W: Write>(header: &mut W, export: &Export) {
+    let doc = if export.doc.is_empty() { default_doc(export) } else { export.doc.clone() };
+    if doc.len() == 1 {
+        writeln!(header, "/** {} */", doc[0]).unwrap();
+    } else {
+        writeln!(header, "/** {}", doc.join("\n *  ").replace(" *  \n", " *\n")).unwrap();
+        writeln!(header, " */").unwrap();
+    }
+
+    let params: Vec<String> = export.params.iter().map(|(name, c)| {
+        if c.ends_with('*') { format!("{}{}", c, name) } else { format!("{} {}", c, name) }
+    }).collect();
+    let start = format!("{} {}(", export.ret.as_ref().map_or("void", |r| r.as_str()), export.name);
+    let mut declaration = format!("{}{});", start, params.join(", "));
+    if declaration.len() > 100 {
+        let separator = format!(",\n{}", " ".repeat(start.len()));
+        declaration = format!("{}{});", start, params.join(&separator));
+    }
+    writeln!(header, "{}\n", declaration).unwrap();
+}
+
+fn write_header<W: Write>(header: &mut W, files: &[PathBuf]) {
+    writeln!(header, "/* sorting.h: C interface of the sorting library, version {}.",
+             env::var("CARGO_PKG_VERSION").unwrap()).unwrap();
+    writeln!(header, " *\n * Generated by build.rs, do not edit.\n */\n").unwrap();
+    writeln!(header, "{}", HEADER_START).unwrap();
+    write_enum(header, "src/ffi.rs", "SortStatus", "sorting_status");
+    write_enum(header, "src/ffi.rs", "ColumnType", "sorting_column_type");
+    write_enum(header, "src/floats.rs", "NanPolicy", "sorting_nan_policy");
+    writeln!(header, "{}", COMPARATOR).unwrap();
+
+    for file in files {
+        let exports = parse_exports(file);
+        if exports.is_empty() {
+            continue;
+        }
+        writeln!(header, "/* {} */\n", file.display()).unwrap();
+        for export in &exports {
+            write_export(header, export);
+        }
+    }
+
+    writeln!(header, "{}", HEADER_END).unwrap();
+}
+
+/// Write the pkg-config file, the installation prefix being `@PREFIX@`.
+/// Native libraries the static library needs on the target, those `rustc --print
+/// native-static-libs` lists for a `staticlib` depending only on the standard library.
+fn native_static_libs() -> &'static str {
+    let os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+    match (os.as_str(), target_env.as_str()) {
+        ("linux", "musl") => "-lc",
+        ("linux", _) => "-lgcc_s -lutil -lrt -lpthread -lm -ldl -lc",
+        ("android", _) => "-ldl -llog -lunwind -lm -lc",
+        ("macos", _) | ("ios", _) => "-lSystem -lc -lm",
+        ("freebsd", _) => "-lexecinfo -lpthread -lgcc_s -lc -lm -lrt -lutil",
+        ("netbsd", _) | ("openbsd", _) => "-lpthread -lgcc_s -lc -lm -lrt -lutil",
+        ("windows", "msvc") => "kernel32.lib advapi32.lib ntdll.lib userenv.lib \
+             ws2_32.lib dbghelp.lib /defaultlib:msvcrt",
+        ("windows", _) => "-lkernel32 -ladvapi32 -lntdll -luserenv -lws2_32 -ldbghelp",
+        _ => "",
+    }
+}
+
+fn write_pkg_config<W: Write>(pc: &mut W) {
+    writeln!(pc, "prefix=@PREFIX@").unwrap();
+    writeln!(pc, "libdir=${{prefix}}/lib").unwrap();
+    writeln!(pc, "includedir=${{prefix}}/include\n").unwrap();
+    writeln!(pc, "Name: sorting").unwrap();
+    writeln!(pc, "Description: {}", env::var("CARGO_PKG_DESCRIPTION").unwrap()).unwrap();
+    writeln!(pc, "URL: {}", env::var("CARGO_PKG_HOMEPAGE").unwrap()).unwrap();
+    writeln!(pc, "Version: {}", env::var("CARGO_PKG_VERSION").unwrap()).unwrap();
+    writeln!(pc, "Libs: -L${{libdir}} -lsorting").unwrap();
+    writeln!(pc, "Libs.private: {}", native_static_libs()).unwrap();
+    writeln!(pc, "Cflags: -I${{includedir}}").unwrap();
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src");
+
+    let mut files = Vec::new();
+    source_files(Path::new("src"), &mut files);
+
+    let mut header = Vec::new();
+    write_header(&mut header, &files);
+
+    let mut pc = Vec::new();
+    write_pkg_config(&mut pc);
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("sorting.h"), &header).unwrap();
+    fs::write(out_dir.join("sorting.pc.in"), &pc).unwrap();
+}
@@ -0,0 +1,107 @@
+//! Helpers shared by the fuzz targets.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::mem;
+
+use sorting::floats;
+use sorting::floats::NanPolicy;
+
+/// Numeric types the fuzz targets sort, built from the fuzzer's bytes.
+pub trait Element: Copy + PartialOrd + Debug {
+    /// Build a value from `mem::size_of::<Self>()` little endian bytes.
+    fn from_bytes(bytes: &[u8]) -> Self;
+
+    /// Bits of the value, to compare values exactly (including NaNs and signed zeros).
+    fn bits(&self) -> u64;
+
+    /// Is the value a NaN?
+    fn is_nan(&self) -> bool;
+
+    /// Total order used to verify the output: the natural order for integers, IEEE 754 total
+    /// order with NaNs placed following `policy` for floating point values.
+    fn compare_with(a: &Self, b: &Self, policy: NanPolicy) -> Ordering;
+
+    /// Total order used to verify the output, NaNs being placed last (as the FFI exports sort
+    /// them with `NanPolicy::Last`).
+    fn compare(a: &Self, b: &Self) -> Ordering {
+        Self::compare_with(a, b, NanPolicy::Last)
+    }
+}
+
+macro_rules! impl_element_integer {
+    ($($t:ty),*) => {$(
+        impl Element for $t {
+            fn from_bytes(bytes: &[u8]) -> $t {
+                let mut array = [0; mem::size_of::<$t>()];
+                array.copy_from_slice(bytes);
+                <$t>::from_le_bytes(array)
+            }
+
+            fn bits(&self) -> u64 {
+                *self as u64
+            }
+
+            fn is_nan(&self) -> bool {
+                false
+            }
+
+            fn compare_with(a: &$t, b: &$t, _: NanPolicy) -> Ordering {
+                a.cmp(b)
+            }
+        }
+    )*}
+}
+
+impl_element_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_element_float {
+    ($($t:ty),*) => {$(
+        impl Element for $t {
+            fn from_bytes(bytes: &[u8]) -> $t {
+                let mut array = [0; mem::size_of::<$t>()];
+                array.copy_from_slice(bytes);
+                <$t>::from_le_bytes(array)
+            }
+
+            fn bits(&self) -> u64 {
+                self.to_bits() as u64
+            }
+
+            fn is_nan(&self) -> bool {
+                <$t>::is_nan(*self)
+            }
+
+            fn compare_with(a: &$t, b: &$t, policy: NanPolicy) -> Ordering {
+                floats::compare(a, b, policy)
+            }
+        }
+    )*}
+}
+
+impl_element_float!(f32, f64);
+
+/// Reinterpret `data` as values of type `T`, ignoring the trailing bytes.
+pub fn values<T: Element>(data: &[u8]) -> Vec<T> {
+    data.chunks_exact(mem::size_of::<T>()).map(T::from_bytes).collect()
+}
+
+/// Is `output` the same multiset of values as `input`?
+pub fn is_permutation<T: Element>(input: &[T], output: &[T]) -> bool {
+    let mut input_bits: Vec<u64> = input.iter().map(Element::bits).collect();
+    let mut output_bits: Vec<u64> = output.iter().map(Element::bits).collect();
+    input_bits.sort_unstable();
+    output_bits.sort_unstable();
+    input_bits == output_bits
+}
+
+/// Verify `output` is `input` sorted following `compare`.
+pub fn verify_sorted_by<T, F>(name: &str, input: &[T], output: &[T], mut compare: F)
+    where T: Element,
+          F: FnMut(&T, &T) -> Ordering
+{
+    assert!(output.windows(2).all(|w| compare(&w[0], &w[1]) != Ordering::Greater),
+            "{}: not sorted: {:?} gave {:?}", name, input, output);
+    assert!(is_permutation(input, output),
+            "{}: not a permutation: {:?} gave {:?}", name, input, output);
+}
@@ -0,0 +1,688 @@
+//! Call the C interface with arbitrary values.
+//!
+//! The first byte chooses the numeric type (or records sorted by the `ffi_<name>_generic()`
+//! functions, or key columns sorted by `ffi_lexsort()`), the following ones are reinterpreted as
+//! values of that type. Every `ffi_*` export of the type is called: the sorts, with and without a
+//! payload, the selection, the argsort, and the median and the percentile of floating point
+//! values. Those of floating point values are called with every NaN policy code and an invalid
+//! one.
+//!
+//! Every export must return `SortStatus::Ok` and leave a sorted permutation of the values (or the
+//! selected value at its index, the sorting permutation, the percentile), or return
+//! `SortStatus::InvalidArgument` and leave its arguments untouched when an argument is invalid or
+//! the values contain a NaN with `NanPolicy::Error`.
+
+#![no_main]
+
+mod common;
+
+use std::cmp::Ordering;
+use std::f64;
+use std::mem;
+use std::slice;
+
+use libfuzzer_sys::fuzz_target;
+
+use sorting::efficientsorts::{heap, intro, merge, quick, radix, select, tim};
+use sorting::ffi::{ColumnType, Comparator, SortStatus};
+use sorting::floats::NanPolicy;
+use sorting::lexsort::ffi_lexsort;
+use sorting::permutation;
+use sorting::simplesorts::{bubble, insertion, selection};
+
+use common::Element;
+
+/// An exported function sorting an array of `T`.
+type Export<T> = extern "C" fn(*mut T, libc::size_t) -> SortStatus;
+
+/// An exported function sorting an array of floating point values `T` following a NaN policy.
+type FloatExport<T> = extern "C" fn(*mut T, libc::size_t, libc::c_int) -> SortStatus;
+
+/// An exported function sorting records with a C comparator.
+type GenericExport = extern "C" fn(*mut libc::c_void, libc::size_t, libc::size_t,
+                                   Option<Comparator>, *mut libc::c_void) -> SortStatus;
+
+/// An exported function sorting keys of type `T` along with records.
+type PayloadExport<T> = extern "C" fn(*mut T, libc::size_t, *mut libc::c_void, libc::size_t)
+                                      -> SortStatus;
+
+/// An exported function sorting floating point keys `T` along with records following a NaN
+/// policy.
+type FloatPayloadExport<T> = extern "C" fn(*mut T, libc::size_t, *mut libc::c_void, libc::size_t,
+                                           libc::c_int) -> SortStatus;
+
+/// An exported function selecting the `k`-th smallest value of an array of `T`.
+type SelectExport<T> = extern "C" fn(*mut T, libc::size_t, libc::size_t) -> SortStatus;
+
+/// An exported function selecting the `k`-th smallest value of an array of floating point values
+/// `T` following a NaN policy.
+type FloatSelectExport<T> = extern "C" fn(*mut T, libc::size_t, libc::size_t, libc::c_int)
+                                          -> SortStatus;
+
+/// An exported function computing the permutation sorting an array of `T`.
+type ArgsortExport<T> = extern "C" fn(*const T, libc::size_t, *mut libc::size_t) -> SortStatus;
+
+/// An exported function computing the permutation sorting an array of floating point values `T`
+/// following a NaN policy.
+type FloatArgsortExport<T> = extern "C" fn(*const T, libc::size_t, *mut libc::size_t,
+                                           libc::c_int) -> SortStatus;
+
+/// An exported function computing the median of an array of floating point values `T`.
+type MedianExport<T> = extern "C" fn(*mut T, libc::size_t, *mut libc::c_double) -> SortStatus;
+
+/// An exported function computing a percentile of an array of floating point values `T`.
+type PercentileExport<T> = extern "C" fn(*mut T, libc::size_t, libc::c_double,
+                                         *mut libc::c_double) -> SortStatus;
+
+/// NaN policy codes the exports of floating point values are called with: those of the four
+/// policies, and an invalid one.
+const POLICY_CODES: [libc::c_int; 5] = [NanPolicy::First as libc::c_int,
+                                        NanPolicy::Last as libc::c_int,
+                                        NanPolicy::TotalOrder as libc::c_int,
+                                        NanPolicy::Error as libc::c_int,
+                                        4];
+
+/// Code the verification of the exports of integers, which take no NaN policy, is done with.
+const INTEGER_POLICY_CODE: libc::c_int = NanPolicy::Last as libc::c_int;
+
+/// Comparison of two elements, by index, in the bytes of a column.
+type CompareElements = fn(&[u8], usize, usize) -> Ordering;
+
+/// Column types of `ffi_lexsort()`, with the size and the comparison of their elements.
+const COLUMN_TYPES: [(ColumnType, usize, CompareElements); 10] = [
+    (ColumnType::I8, mem::size_of::<i8>(), compare_elements::<i8>),
+    (ColumnType::I16, mem::size_of::<i16>(), compare_elements::<i16>),
+    (ColumnType::I32, mem::size_of::<i32>(), compare_elements::<i32>),
+    (ColumnType::I64, mem::size_of::<i64>(), compare_elements::<i64>),
+    (ColumnType::U8, mem::size_of::<u8>(), compare_elements::<u8>),
+    (ColumnType::U16, mem::size_of::<u16>(), compare_elements::<u16>),
+    (ColumnType::U32, mem::size_of::<u32>(), compare_elements::<u32>),
+    (ColumnType::U64, mem::size_of::<u64>(), compare_elements::<u64>),
+    (ColumnType::F32, mem::size_of::<f32>(), compare_elements::<f32>),
+    (ColumnType::F64, mem::size_of::<f64>(), compare_elements::<f64>),
+];
+
+fuzz_target!(|data: &[u8]| {
+    let (&selector, data) = match data.split_first() {
+        Some(split) => split,
+        None        => return,
+    };
+    match selector % 12 {
+        0  => {
+            check::<i8>(data, &[("sort", sorting::ffi_sort_i8),
+                                ("bubblesort", bubble::ffi_bubblesort_i8),
+                                ("insertionsort", insertion::ffi_insertionsort_i8),
+                                ("selectionsort", selection::ffi_selectionsort_i8),
+                                ("quicksort", quick::ffi_quicksort_i8),
+                                ("mergesort", merge::ffi_mergesort_i8),
+                                ("heapsort", heap::ffi_heapsort_i8),
+                                ("introsort", intro::ffi_introsort_i8),
+                                ("timsort", tim::ffi_timsort_i8),
+                                ("radixsort", radix::ffi_radixsort_i8)]);
+            check_payload::<i8>(data, &[("quicksort", quick::ffi_quicksort_payload_i8),
+                                        ("mergesort", merge::ffi_mergesort_payload_i8),
+                                        ("heapsort", heap::ffi_heapsort_payload_i8)]);
+            check_select::<i8>(data, select::ffi_select_nth_i8);
+            check_argsort::<i8>(data, permutation::ffi_argsort_i8);
+        },
+        1  => {
+            check::<i16>(data, &[("sort", sorting::ffi_sort_i16),
+                                 ("bubblesort", bubble::ffi_bubblesort_i16),
+                                 ("insertionsort", insertion::ffi_insertionsort_i16),
+                                 ("selectionsort", selection::ffi_selectionsort_i16),
+                                 ("quicksort", quick::ffi_quicksort_i16),
+                                 ("mergesort", merge::ffi_mergesort_i16),
+                                 ("heapsort", heap::ffi_heapsort_i16),
+                                 ("introsort", intro::ffi_introsort_i16),
+                                 ("timsort", tim::ffi_timsort_i16),
+                                 ("radixsort", radix::ffi_radixsort_i16)]);
+            check_payload::<i16>(data, &[("quicksort", quick::ffi_quicksort_payload_i16),
+                                         ("mergesort", merge::ffi_mergesort_payload_i16),
+                                         ("heapsort", heap::ffi_heapsort_payload_i16)]);
+            check_select::<i16>(data, select::ffi_select_nth_i16);
+            check_argsort::<i16>(data, permutation::ffi_argsort_i16);
+        },
+        2  => {
+            check::<i32>(data, &[("sort", sorting::ffi_sort_i32),
+                                 ("bubblesort", bubble::ffi_bubblesort_i32),
+                                 ("insertionsort", insertion::ffi_insertionsort_i32),
+                                 ("selectionsort", selection::ffi_selectionsort_i32),
+                                 ("quicksort", quick::ffi_quicksort_i32),
+                                 ("mergesort", merge::ffi_mergesort_i32),
+                                 ("heapsort", heap::ffi_heapsort_i32),
+                                 ("introsort", intro::ffi_introsort_i32),
+                                 ("timsort", tim::ffi_timsort_i32),
+                                 ("radixsort", radix::ffi_radixsort_i32)]);
+            check_payload::<i32>(data, &[("quicksort", quick::ffi_quicksort_payload_i32),
+                                         ("mergesort", merge::ffi_mergesort_payload_i32),
+                                         ("heapsort", heap::ffi_heapsort_payload_i32)]);
+            check_select::<i32>(data, select::ffi_select_nth_i32);
+            check_argsort::<i32>(data, permutation::ffi_argsort_i32);
+        },
+        3  => {
+            check::<i64>(data, &[("sort", sorting::ffi_sort_i64),
+                                 ("bubblesort", bubble::ffi_bubblesort_i64),
+                                 ("insertionsort", insertion::ffi_insertionsort_i64),
+                                 ("selectionsort", selection::ffi_selectionsort_i64),
+                                 ("quicksort", quick::ffi_quicksort_i64),
+                                 ("mergesort", merge::ffi_mergesort_i64),
+                                 ("heapsort", heap::ffi_heapsort_i64),
+                                 ("introsort", intro::ffi_introsort_i64),
+                                 ("timsort", tim::ffi_timsort_i64),
+                                 ("radixsort", radix::ffi_radixsort_i64)]);
+            check_payload::<i64>(data, &[("quicksort", quick::ffi_quicksort_payload_i64),
+                                         ("mergesort", merge::ffi_mergesort_payload_i64),
+                                         ("heapsort", heap::ffi_heapsort_payload_i64)]);
+            check_select::<i64>(data, select::ffi_select_nth_i64);
+            check_argsort::<i64>(data, permutation::ffi_argsort_i64);
+        },
+        4  => {
+            check::<u8>(data, &[("sort", sorting::ffi_sort_u8),
+                                ("bubblesort", bubble::ffi_bubblesort_u8),
+                                ("insertionsort", insertion::ffi_insertionsort_u8),
+                                ("selectionsort", selection::ffi_selectionsort_u8),
+                                ("quicksort", quick::ffi_quicksort_u8),
+                                ("mergesort", merge::ffi_mergesort_u8),
+                                ("heapsort", heap::ffi_heapsort_u8),
+                                ("introsort", intro::ffi_introsort_u8),
+                                ("timsort", tim::ffi_timsort_u8),
+                                ("radixsort", radix::ffi_radixsort_u8)]);
+            check_payload::<u8>(data, &[("quicksort", quick::ffi_quicksort_payload_u8),
+                                        ("mergesort", merge::ffi_mergesort_payload_u8),
+                                        ("heapsort", heap::ffi_heapsort_payload_u8)]);
+            check_select::<u8>(data, select::ffi_select_nth_u8);
+            check_argsort::<u8>(data, permutation::ffi_argsort_u8);
+        },
+        5  => {
+            check::<u16>(data, &[("sort", sorting::ffi_sort_u16),
+                                 ("bubblesort", bubble::ffi_bubblesort_u16),
+                                 ("insertionsort", insertion::ffi_insertionsort_u16),
+                                 ("selectionsort", selection::ffi_selectionsort_u16),
+                                 ("quicksort", quick::ffi_quicksort_u16),
+                                 ("mergesort", merge::ffi_mergesort_u16),
+                                 ("heapsort", heap::ffi_heapsort_u16),
+                                 ("introsort", intro::ffi_introsort_u16),
+                                 ("timsort", tim::ffi_timsort_u16),
+                                 ("radixsort", radix::ffi_radixsort_u16)]);
+            check_payload::<u16>(data, &[("quicksort", quick::ffi_quicksort_payload_u16),
+                                         ("mergesort", merge::ffi_mergesort_payload_u16),
+                                         ("heapsort", heap::ffi_heapsort_payload_u16)]);
+            check_select::<u16>(data, select::ffi_select_nth_u16);
+            check_argsort::<u16>(data, permutation::ffi_argsort_u16);
+        },
+        6  => {
+            check::<u32>(data, &[("sort", sorting::ffi_sort_u32),
+                                 ("bubblesort", bubble::ffi_bubblesort_u32),
+                                 ("insertionsort", insertion::ffi_insertionsort_u32),
+                                 ("selectionsort", selection::ffi_selectionsort_u32),
+                                 ("quicksort", quick::ffi_quicksort_u32),
+                                 ("mergesort", merge::ffi_mergesort_u32),
+                                 ("heapsort", heap::ffi_heapsort_u32),
+                                 ("introsort", intro::ffi_introsort_u32),
+                                 ("timsort", tim::ffi_timsort_u32),
+                                 ("radixsort", radix::ffi_radixsort_u32)]);
+            check_payload::<u32>(data, &[("quicksort", quick::ffi_quicksort_payload_u32),
+                                         ("mergesort", merge::ffi_mergesort_payload_u32),
+                                         ("heapsort", heap::ffi_heapsort_payload_u32)]);
+            check_select::<u32>(data, select::ffi_select_nth_u32);
+            check_argsort::<u32>(data, permutation::ffi_argsort_u32);
+        },
+        7  => {
+            check::<u64>(data, &[("sort", sorting::ffi_sort_u64),
+                                 ("bubblesort", bubble::ffi_bubblesort_u64),
+                                 ("insertionsort", insertion::ffi_insertionsort_u64),
+                                 ("selectionsort", selection::ffi_selectionsort_u64),
+                                 ("quicksort", quick::ffi_quicksort_u64),
+                                 ("mergesort", merge::ffi_mergesort_u64),
+                                 ("heapsort", heap::ffi_heapsort_u64),
+                                 ("introsort", intro::ffi_introsort_u64),
+                                 ("timsort", tim::ffi_timsort_u64),
+                                 ("radixsort", radix::ffi_radixsort_u64)]);
+            check_payload::<u64>(data, &[("quicksort", quick::ffi_quicksort_payload_u64),
+                                         ("mergesort", merge::ffi_mergesort_payload_u64),
+                                         ("heapsort", heap::ffi_heapsort_payload_u64)]);
+            check_select::<u64>(data, select::ffi_select_nth_u64);
+            check_argsort::<u64>(data, permutation::ffi_argsort_u64);
+        },
+        8  => {
+            check_floats::<f32>(data, &[("sort", sorting::ffi_sort_f32),
+                                        ("bubblesort", bubble::ffi_bubblesort_f32),
+                                        ("insertionsort", insertion::ffi_insertionsort_f32),
+                                        ("selectionsort", selection::ffi_selectionsort_f32),
+                                        ("quicksort", quick::ffi_quicksort_f32),
+                                        ("mergesort", merge::ffi_mergesort_f32),
+                                        ("heapsort", heap::ffi_heapsort_f32),
+                                        ("introsort", intro::ffi_introsort_f32),
+                                        ("timsort", tim::ffi_timsort_f32)]);
+            check_payload_floats::<f32>(data, &[("quicksort", quick::ffi_quicksort_payload_f32),
+                                                ("mergesort", merge::ffi_mergesort_payload_f32),
+                                                ("heapsort", heap::ffi_heapsort_payload_f32)]);
+            check_select_floats::<f32>(data, select::ffi_select_nth_f32);
+            check_argsort_floats::<f32>(data, permutation::ffi_argsort_f32);
+            check_percentile::<f32>(data, select::ffi_median_f32, select::ffi_percentile_f32);
+        },
+        9  => {
+            check_floats::<f64>(data, &[("sort", sorting::ffi_sort_f64),
+                                        ("bubblesort", bubble::ffi_bubblesort_f64),
+                                        ("insertionsort", insertion::ffi_insertionsort_f64),
+                                        ("selectionsort", selection::ffi_selectionsort_f64),
+                                        ("quicksort", quick::ffi_quicksort_f64),
+                                        ("mergesort", merge::ffi_mergesort_f64),
+                                        ("heapsort", heap::ffi_heapsort_f64),
+                                        ("introsort", intro::ffi_introsort_f64),
+                                        ("timsort", tim::ffi_timsort_f64)]);
+            check_payload_floats::<f64>(data, &[("quicksort", quick::ffi_quicksort_payload_f64),
+                                                ("mergesort", merge::ffi_mergesort_payload_f64),
+                                                ("heapsort", heap::ffi_heapsort_payload_f64)]);
+            check_select_floats::<f64>(data, select::ffi_select_nth_f64);
+            check_argsort_floats::<f64>(data, permutation::ffi_argsort_f64);
+            check_percentile::<f64>(data, select::ffi_median_f64, select::ffi_percentile_f64);
+        },
+        10 => check_generic(data),
+        _  => check_lexsort(data),
+    }
+});
+
+/// Policy an export must follow when called with the NaN policy `code` on `input`, `None` if it
+/// must instead return `SortStatus::InvalidArgument` and leave its arguments untouched: when the
+/// code is invalid, or when `input` contains a NaN with `NanPolicy::Error`.
+fn expected_policy<T: Element>(input: &[T], code: libc::c_int) -> Option<NanPolicy> {
+    let policy = match code {
+        0 => NanPolicy::First,
+        1 => NanPolicy::Last,
+        2 => NanPolicy::TotalOrder,
+        3 => NanPolicy::Error,
+        _ => return None,
+    };
+    if policy == NanPolicy::Error && input.iter().any(Element::is_nan) {
+        None
+    } else {
+        Some(policy)
+    }
+}
+
+/// Are `a` and `b` the same values, bit for bit?
+fn is_same<T: Element>(a: &[T], b: &[T]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.bits() == y.bits())
+}
+
+/// Verify `indices` is the permutation stably sorting `indices.len()` elements compared by index
+/// with `compare`.
+fn verify_stable_permutation<F>(name: &str, indices: &[libc::size_t], mut compare: F)
+    where F: FnMut(usize, usize) -> Ordering
+{
+    let mut sorted = indices.to_vec();
+    sorted.sort_unstable();
+    assert!(sorted.iter().enumerate().all(|(i, &index)| index as usize == i),
+            "{}: not a permutation: {:?}", name, indices);
+    assert!(indices.windows(2).all(|w| {
+                match compare(w[0] as usize, w[1] as usize) {
+                    Ordering::Less    => true,
+                    Ordering::Equal   => w[0] < w[1],
+                    Ordering::Greater => false,
+                }
+            }),
+            "{}: not a stable sort: {:?}", name, indices);
+}
+
+/// Sort the values with every export of `exports`.
+fn check<T: Element>(data: &[u8], exports: &[(&str, Export<T>)]) {
+    let input: Vec<T> = common::values(data);
+    for &(name, export) in exports {
+        verify_sort(name, &input, INTEGER_POLICY_CODE, |output| {
+            export(output.as_mut_ptr(), output.len() as libc::size_t)
+        });
+    }
+}
+
+/// Sort the values with every export of `exports` and every NaN policy code.
+fn check_floats<T: Element>(data: &[u8], exports: &[(&str, FloatExport<T>)]) {
+    let input: Vec<T> = common::values(data);
+    for &(name, export) in exports {
+        for &code in &POLICY_CODES {
+            verify_sort(name, &input, code, |output| {
+                export(output.as_mut_ptr(), output.len() as libc::size_t, code)
+            });
+        }
+    }
+}
+
+/// Verify `sort` sorts a copy of `input` as an export called with the NaN policy `code` must.
+fn verify_sort<T, F>(name: &str, input: &[T], code: libc::c_int, sort: F)
+    where T: Element,
+          F: FnOnce(&mut [T]) -> SortStatus
+{
+    let mut output = input.to_vec();
+    let status = sort(&mut output);
+    let name = format!("{} (policy {})", name, code);
+    match expected_policy(input, code) {
+        Some(policy) => {
+            assert_eq!(status, SortStatus::Ok, "{}", name);
+            common::verify_sorted_by(&name, input, &output, |a, b| T::compare_with(a, b, policy));
+        },
+        None         => {
+            assert_eq!(status, SortStatus::InvalidArgument, "{}", name);
+            assert!(is_same(input, &output), "{}: modified", name);
+        },
+    }
+}
+
+/// Sort the values along with their indices, as records, with every export of `exports`.
+fn check_payload<T: Element>(data: &[u8], exports: &[(&str, PayloadExport<T>)]) {
+    let input: Vec<T> = common::values(data);
+    for &(name, export) in exports {
+        verify_payload(name, &input, INTEGER_POLICY_CODE, |keys, values| {
+            export(keys.as_mut_ptr(), keys.len() as libc::size_t,
+                   values.as_mut_ptr() as *mut libc::c_void,
+                   mem::size_of::<usize>() as libc::size_t)
+        });
+    }
+}
+
+/// Sort the values along with their indices, as records, with every export of `exports` and
+/// every NaN policy code.
+fn check_payload_floats<T: Element>(data: &[u8], exports: &[(&str, FloatPayloadExport<T>)]) {
+    let input: Vec<T> = common::values(data);
+    for &(name, export) in exports {
+        for &code in &POLICY_CODES {
+            verify_payload(name, &input, code, |keys, values| {
+                export(keys.as_mut_ptr(), keys.len() as libc::size_t,
+                       values.as_mut_ptr() as *mut libc::c_void,
+                       mem::size_of::<usize>() as libc::size_t, code)
+            });
+        }
+    }
+}
+
+/// Verify `sort` sorts a copy of `input` along with the indices of the keys as an export called
+/// with the NaN policy `code` must.
+fn verify_payload<T, F>(name: &str, input: &[T], code: libc::c_int, sort: F)
+    where T: Element,
+          F: FnOnce(&mut [T], &mut [usize]) -> SortStatus
+{
+    let mut keys = input.to_vec();
+    let mut values: Vec<usize> = (0..input.len()).collect();
+    let status = sort(&mut keys, &mut values);
+    let name = format!("{} with payload (policy {})", name, code);
+    match expected_policy(input, code) {
+        Some(policy) => {
+            assert_eq!(status, SortStatus::Ok, "{}", name);
+            common::verify_sorted_by(&name, input, &keys, |a, b| T::compare_with(a, b, policy));
+            let mut indices = values.clone();
+            indices.sort_unstable();
+            assert!(indices.iter().enumerate().all(|(i, &index)| index == i) &&
+                    keys.iter().zip(&values).all(|(key, &index)| key.bits() == input[index].bits()),
+                    "{}: records not moved along with the keys: {:?}", name, values);
+        },
+        None         => {
+            assert_eq!(status, SortStatus::InvalidArgument, "{}", name);
+            assert!(is_same(input, &keys) && values.iter().enumerate().all(|(i, &v)| v == i),
+                    "{}: modified", name);
+        },
+    }
+}
+
+/// Select values of the array with `export`.
+fn check_select<T: Element>(data: &[u8], export: SelectExport<T>) {
+    let input: Vec<T> = common::values(data);
+    verify_select(&input, INTEGER_POLICY_CODE, |output, k| {
+        export(output.as_mut_ptr(), output.len() as libc::size_t, k as libc::size_t)
+    });
+}
+
+/// Select values of the array with `export` and every NaN policy code.
+fn check_select_floats<T: Element>(data: &[u8], export: FloatSelectExport<T>) {
+    let input: Vec<T> = common::values(data);
+    for &code in &POLICY_CODES {
+        verify_select(&input, code, |output, k| {
+            export(output.as_mut_ptr(), output.len() as libc::size_t, k as libc::size_t, code)
+        });
+    }
+}
+
+/// Verify `select` re-arranges a copy of `input` around the first, middle and last indices `k`
+/// (and fails with an index out of range) as an export called with the NaN policy `code` must.
+fn verify_select<T, F>(input: &[T], code: libc::c_int, mut select: F)
+    where T: Element,
+          F: FnMut(&mut [T], usize) -> SortStatus
+{
+    let n = input.len();
+    let policy = expected_policy(input, code);
+    let mut sorted = input.to_vec();
+    if let Some(policy) = policy {
+        sorted.sort_by(|a, b| T::compare_with(a, b, policy));
+    }
+    for &k in &[0, n / 2, n.saturating_sub(1), n] {
+        let mut output = input.to_vec();
+        let status = select(&mut output, k);
+        let name = format!("select_nth (k {}, policy {})", k, code);
+        match policy {
+            Some(policy) if k < n => {
+                let compare = |a, b| T::compare_with(a, b, policy);
+                assert_eq!(status, SortStatus::Ok, "{}", name);
+                assert!(common::is_permutation(input, &output), "{}: not a permutation", name);
+                assert!(compare(&output[k], &sorted[k]) == Ordering::Equal &&
+                        output[..k].iter().all(|x| compare(x, &output[k]) != Ordering::Greater) &&
+                        output[k+1..].iter().all(|x| compare(x, &output[k]) != Ordering::Less),
+                        "{}: {:?} gave {:?}", name, input, output);
+            },
+            _                     => {
+                assert_eq!(status, SortStatus::InvalidArgument, "{}", name);
+                assert!(is_same(input, &output), "{}: modified", name);
+            },
+        }
+    }
+}
+
+/// Compute the permutation sorting the values with `export`.
+fn check_argsort<T: Element>(data: &[u8], export: ArgsortExport<T>) {
+    let input: Vec<T> = common::values(data);
+    verify_argsort(&input, INTEGER_POLICY_CODE, |indices| {
+        export(input.as_ptr(), input.len() as libc::size_t, indices.as_mut_ptr())
+    });
+}
+
+/// Compute the permutation sorting the values with `export` and every NaN policy code.
+fn check_argsort_floats<T: Element>(data: &[u8], export: FloatArgsortExport<T>) {
+    let input: Vec<T> = common::values(data);
+    for &code in &POLICY_CODES {
+        verify_argsort(&input, code, |indices| {
+            export(input.as_ptr(), input.len() as libc::size_t, indices.as_mut_ptr(), code)
+        });
+    }
+}
+
+/// Verify `argsort` writes the permutation stably sorting `input` as an export called with the
+/// NaN policy `code` must.
+fn verify_argsort<T, F>(input: &[T], code: libc::c_int, argsort: F)
+    where T: Element,
+          F: FnOnce(&mut [libc::size_t]) -> SortStatus
+{
+    let untouched = libc::size_t::MAX;
+    let mut indices = vec![untouched; input.len()];
+    let status = argsort(&mut indices);
+    let name = format!("argsort (policy {})", code);
+    match expected_policy(input, code) {
+        Some(policy) => {
+            assert_eq!(status, SortStatus::Ok, "{}", name);
+            verify_stable_permutation(&name, &indices, |i, j| {
+                T::compare_with(&input[i], &input[j], policy)
+            });
+        },
+        None         => {
+            assert_eq!(status, SortStatus::InvalidArgument, "{}", name);
+            assert!(indices.iter().all(|&index| index == untouched), "{}: modified", name);
+        },
+    }
+}
+
+/// Compute the median and percentiles of the values (and fail with percentiles out of range)
+/// with `median` and `percentile`.
+fn check_percentile<T>(data: &[u8], median: MedianExport<T>, percentile: PercentileExport<T>)
+    where T: Element + Into<f64>
+{
+    let input: Vec<T> = common::values(data);
+    verify_percentile("median", &input, 50.0, |output, result| {
+        median(output.as_mut_ptr(), output.len() as libc::size_t, result)
+    });
+    for &p in &[0.0, 25.0, 62.5, 100.0, -1.0, 101.0, f64::NAN] {
+        verify_percentile("percentile", &input, p, |output, result| {
+            percentile(output.as_mut_ptr(), output.len() as libc::size_t, p, result)
+        });
+    }
+}
+
+/// Verify `compute` computes the `p`-th percentile of a copy of `input`, re-arranging it: NaN if
+/// it is empty or contains a NaN, otherwise between the values around the percentile.
+fn verify_percentile<T, F>(name: &str, input: &[T], p: f64, compute: F)
+    where T: Element + Into<f64>,
+          F: FnOnce(&mut [T], *mut libc::c_double) -> SortStatus
+{
+    let mut output = input.to_vec();
+    let mut result = 0.0;
+    let status = compute(&mut output, &mut result);
+    let name = format!("{} (p {})", name, p);
+    if !(0.0..=100.0).contains(&p) {
+        assert_eq!(status, SortStatus::InvalidArgument, "{}", name);
+        return;
+    }
+    assert_eq!(status, SortStatus::Ok, "{}", name);
+    assert!(common::is_permutation(input, &output), "{}: not a permutation", name);
+    if input.is_empty() || input.iter().any(Element::is_nan) {
+        assert!(result.is_nan(), "{}: {:?} gave {}", name, input, result);
+        return;
+    }
+    let mut sorted: Vec<f64> = input.iter().map(|&x| x.into()).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let index = p / 100.0 * (sorted.len() - 1) as f64;
+    let (below, above) = (sorted[index.floor() as usize], sorted[index.ceil() as usize]);
+    // The interpolation overflows between values of large magnitudes, and may be rounded past
+    // them.
+    let slack = (above - below) * 4.0 * f64::EPSILON;
+    if slack.is_finite() {
+        assert!(below - slack <= result && result <= above + slack,
+                "{}: {:?} gave {}, not between {} and {}", name, input, result, below, above);
+    }
+}
+
+/// Compare the elements `i` and `j` of type `T` of a column, in the order of `ffi_lexsort()`.
+fn compare_elements<T: Element>(bytes: &[u8], i: usize, j: usize) -> Ordering {
+    let size = mem::size_of::<T>();
+    T::compare(&T::from_bytes(&bytes[i * size..(i + 1) * size]),
+               &T::from_bytes(&bytes[j * size..(j + 1) * size]))
+}
+
+/// Sort the rows of key columns with `ffi_lexsort()`: the first byte gives the number of columns
+/// (1 to 4), each of the following ones the type of a column (or an invalid type) and its
+/// direction, and the next ones the elements of the columns, one column after the other.
+fn check_lexsort(data: &[u8]) {
+    let (&ncolumns, data) = match data.split_first() {
+        Some(split) => split,
+        None        => return,
+    };
+    let ncolumns = (ncolumns % 4) as usize + 1;
+    if data.len() < ncolumns {
+        return;
+    }
+    let (header, data) = data.split_at(ncolumns);
+    let columns: Vec<_> = header.iter().map(|&byte| {
+        (COLUMN_TYPES.get(byte as usize % (COLUMN_TYPES.len() + 1)), byte >= 128)
+    }).collect();
+    // The elements of a column of an invalid type are one byte long.
+    let row_size: usize = columns.iter().map(|&(column_type, _)| {
+        column_type.map_or(1, |&(_, size, _)| size)
+    }).sum();
+    let n = data.len() / row_size;
+
+    let mut types = Vec::with_capacity(ncolumns);
+    let mut descending = Vec::with_capacity(ncolumns);
+    let mut bytes = Vec::with_capacity(ncolumns);
+    let mut offset = 0;
+    for &(column_type, is_descending) in &columns {
+        let size = column_type.map_or(1, |&(_, size, _)| size);
+        types.push(column_type.map_or(COLUMN_TYPES.len() as libc::c_int,
+                                      |&(column_type, _, _)| column_type as libc::c_int));
+        descending.push(is_descending as libc::c_int);
+        bytes.push(&data[offset..offset + n * size]);
+        offset += n * size;
+    }
+    // Copied to `u64` buffers for the elements to be aligned.
+    let buffers: Vec<Vec<u64>> = bytes.iter().map(|bytes| {
+        bytes.chunks(mem::size_of::<u64>()).map(|chunk| {
+            let mut word = [0; mem::size_of::<u64>()];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_ne_bytes(word)
+        }).collect()
+    }).collect();
+    let pointers: Vec<*const libc::c_void> = buffers.iter()
+                                                    .map(|buffer| buffer.as_ptr() as *const _)
+                                                    .collect();
+
+    let untouched = libc::size_t::MAX;
+    let mut indices = vec![untouched; n];
+    let status = ffi_lexsort(pointers.as_ptr(), types.as_ptr(), descending.as_ptr(),
+                             ncolumns as libc::size_t, n as libc::size_t, indices.as_mut_ptr());
+    let name = format!("lexsort {:?}", types);
+    if n == 0 {
+        assert_eq!(status, SortStatus::Ok, "{}", name);
+    } else if columns.iter().any(|&(column_type, _)| column_type.is_none()) {
+        assert_eq!(status, SortStatus::InvalidArgument, "{}", name);
+        assert!(indices.iter().all(|&index| index == untouched), "{}: modified", name);
+    } else {
+        assert_eq!(status, SortStatus::Ok, "{}", name);
+        verify_stable_permutation(&name, &indices, |i, j| {
+            columns.iter().zip(&bytes).map(|(&(column_type, is_descending), bytes)| {
+                let &(_, _, compare) = column_type.unwrap();
+                let ordering = compare(bytes, i, j);
+                if is_descending { ordering.reverse() } else { ordering }
+            }).find(|&ordering| ordering != Ordering::Equal).unwrap_or(Ordering::Equal)
+        });
+    }
+}
+
+/// C comparator of records of `*ctx` bytes, in lexicographic order.
+extern "C" fn compare_records(a: *const libc::c_void, b: *const libc::c_void,
+                              ctx: *mut libc::c_void) -> libc::c_int {
+    let (a, b) = unsafe {
+        let size = *(ctx as *const usize);
+        (slice::from_raw_parts(a as *const u8, size), slice::from_raw_parts(b as *const u8, size))
+    };
+    match a.cmp(b) {
+        Ordering::Less    => -1,
+        Ordering::Equal   => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+/// Sort records with every generic export: the first byte gives the size of the records (1 to
+/// 16 bytes), the following ones their content.
+fn check_generic(data: &[u8]) {
+    let (&size, data) = match data.split_first() {
+        Some(split) => split,
+        None        => return,
+    };
+    let mut size = (size % 16) as usize + 1;
+    let input: Vec<&[u8]> = data.chunks_exact(size).collect();
+    let mut expected = input.clone();
+    expected.sort();
+
+    let exports: [(&str, GenericExport); 8] = [
+        ("bubblesort", bubble::ffi_bubblesort_generic),
+        ("insertionsort", insertion::ffi_insertionsort_generic),
+        ("selectionsort", selection::ffi_selectionsort_generic),
+        ("quicksort", quick::ffi_quicksort_generic),
+        ("mergesort", merge::ffi_mergesort_generic),
+        ("heapsort", heap::ffi_heapsort_generic),
+        ("introsort", intro::ffi_introsort_generic),
+        ("timsort", tim::ffi_timsort_generic),
+    ];
+    for &(name, export) in &exports {
+        let mut records: Vec<u8> = input.concat();
+        let status = export(records.as_mut_ptr() as *mut libc::c_void,
+                            input.len() as libc::size_t, size as libc::size_t,
+                            Some(compare_records), &mut size as *mut usize as *mut libc::c_void);
+        assert_eq!(status, SortStatus::Ok, "{}", name);
+        let output: Vec<&[u8]> = records.chunks_exact(size).collect();
+        assert_eq!(output, expected, "{}", name);
+    }
+}
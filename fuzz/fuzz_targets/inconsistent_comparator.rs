@@ -0,0 +1,47 @@
+//! Sort with a comparator which is not a total order.
+//!
+//! The first two bytes give the number of elements, every following byte the result of a
+//! comparison (cycling through them): the results are thus inconsistent (`a < b` and `b < a`,
+//! non-transitive...). The algorithms must neither panic, index out of bounds nor loop forever,
+//! and the elements must be kept: quicksort's partitioning and heap sort's `sift_down()` must
+//! stay within the vector whatever the comparator says.
+
+#![no_main]
+
+use std::cmp::Ordering;
+
+use libfuzzer_sys::fuzz_target;
+
+use sorting::registry;
+use sorting::sorter::Sorter;
+
+/// Largest number of elements sorted, so the quadratic algorithms stay fast.
+const MAX_LEN: usize = 512;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    let n = (data[0] as usize | (data[1] as usize) << 8) % (MAX_LEN + 1);
+    let results = &data[2..];
+
+    for &algorithm in registry::all() {
+        let mut to_sort: Vec<usize> = (0..n).collect();
+        let mut next = 0;
+        algorithm.sort_by(&mut to_sort, |_, _| {
+            if results.is_empty() {
+                return Ordering::Equal;
+            }
+            let result = results[next % results.len()];
+            next += 1;
+            match result % 3 {
+                0 => Ordering::Less,
+                1 => Ordering::Equal,
+                _ => Ordering::Greater,
+            }
+        });
+
+        to_sort.sort_unstable();
+        assert!(to_sort.iter().cloned().eq(0..n), "{}: not a permutation", algorithm.name());
+    }
+});
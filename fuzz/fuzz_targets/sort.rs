@@ -0,0 +1,76 @@
+//! Sort arbitrary values with every algorithm.
+//!
+//! The first byte chooses the numeric type, the following ones are reinterpreted as values of
+//! that type. Every algorithm of the registry (and radix sort, for integers) must give a sorted
+//! permutation of the values, with `sort()` and `sort_by()`.
+
+#![no_main]
+
+mod common;
+
+use std::cmp::Ordering;
+
+use libfuzzer_sys::fuzz_target;
+
+use sorting::efficientsorts::radix::{self, RadixKey};
+use sorting::registry;
+use sorting::sorter::Sorter;
+
+use common::Element;
+
+fuzz_target!(|data: &[u8]| {
+    let (&selector, data) = match data.split_first() {
+        Some(split) => split,
+        None        => return,
+    };
+    match selector % 12 {
+        0  => { check::<i8>(data); check_radix::<i8>(data) },
+        1  => { check::<i16>(data); check_radix::<i16>(data) },
+        2  => { check::<i32>(data); check_radix::<i32>(data) },
+        3  => { check::<i64>(data); check_radix::<i64>(data) },
+        4  => { check::<isize>(data); check_radix::<isize>(data) },
+        5  => { check::<u8>(data); check_radix::<u8>(data) },
+        6  => { check::<u16>(data); check_radix::<u16>(data) },
+        7  => { check::<u32>(data); check_radix::<u32>(data) },
+        8  => { check::<u64>(data); check_radix::<u64>(data) },
+        9  => { check::<usize>(data); check_radix::<usize>(data) },
+        10 => check::<f32>(data),
+        _  => check::<f64>(data),
+    }
+});
+
+/// Sort the values with every algorithm of the registry.
+fn check<T: Element>(data: &[u8]) {
+    let input: Vec<T> = common::values(data);
+    // NaNs are unordered: `sort()` must then only keep the values.
+    let ordered = !input.iter().any(Element::is_nan);
+
+    for &algorithm in registry::all() {
+        let mut output = input.clone();
+        algorithm.sort_by(&mut output, T::compare);
+        common::verify_sorted_by(algorithm.name(), &input, &output, T::compare);
+
+        let mut output = input.clone();
+        algorithm.sort(&mut output);
+        if ordered {
+            common::verify_sorted_by(algorithm.name(), &input, &output,
+                                     |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        } else {
+            assert!(common::is_permutation(&input, &output), "{}: not a permutation",
+                    algorithm.name());
+        }
+    }
+}
+
+/// Sort the values with both radix sorts.
+fn check_radix<T: Element + RadixKey>(data: &[u8]) {
+    let input: Vec<T> = common::values(data);
+
+    let mut output = input.clone();
+    radix::sort(&mut output);
+    common::verify_sorted_by("radixsort", &input, &output, T::compare);
+
+    let mut output = input.clone();
+    radix::sort_msd(&mut output);
+    common::verify_sorted_by("radixsort (MSD)", &input, &output, T::compare);
+}
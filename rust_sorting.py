@@ -23,7 +23,54 @@ libfile = files[0]
 
 rustlib = ctypes.CDLL(libfile)
 
-def sort(array):
+
+# Status returned by the library's functions (see the `ffi` module's `SortStatus`).
+
+class SortError(Exception):
+    pass
+
+class NullPointerError(SortError):
+    pass
+
+class InvalidLengthError(SortError):
+    pass
+
+class ComparatorError(SortError):
+    pass
+
+class RustPanicError(SortError):
+    pass
+
+class InvalidArgumentError(SortError):
+    pass
+
+STATUS_ERRORS = {
+    1: (NullPointerError,     "null pointer"),
+    2: (InvalidLengthError,   "invalid length"),
+    3: (ComparatorError,      "null comparator"),
+    4: (RustPanicError,       "panic while sorting"),
+    5: (InvalidArgumentError, "argument out of range"),
+}
+
+# Where NaNs end up when sorting floating point values (see the `floats` module's `NanPolicy`):
+# "error" raises an InvalidArgumentError if the array contains a NaN, leaving it untouched.
+NAN_POLICIES = {
+    "first":       0,
+    "last":        1,
+    "total_order": 2,
+    "error":       3,
+}
+
+def call(rust_sort, *args):
+    """Call one of the library's functions and raise the exception corresponding to its status."""
+
+    rust_sort.restype = ctypes.c_int
+    status = rust_sort(*args)
+    if status != 0:
+        error, message = STATUS_ERRORS.get(status, (SortError, "unknown status"))
+        raise error("%s: %s (status %d)" % (rust_sort.__name__, message, status))
+
+def sort(array, policy="last"):
 
     ptr = ctypes.c_void_p(array.ctypes.data)
     n   = len(array)
@@ -46,19 +93,21 @@ def sort(array):
     elif array.dtype == np.uint64:
         rust_sort = rustlib.ffi_sort_u64
 
-    # Rust's vec.sort() can't be used on floating points!
-    # elif array.dtype == np.float32:
-    #     rust_sort = rustlib.ffi_sort_f32
-    # elif array.dtype == np.float64:
-    #     rust_sort = rustlib.ffi_sort_f64
+    elif array.dtype == np.float32:
+        rust_sort = rustlib.ffi_sort_f32
+    elif array.dtype == np.float64:
+        rust_sort = rustlib.ffi_sort_f64
 
     else:
         raise NotImplementedError
 
-    rust_sort(ptr, n)
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
 
 
-def insertionsort(array):
+def insertionsort(array, policy="last"):
 
     ptr = ctypes.c_void_p(array.ctypes.data)
     n   = len(array)
@@ -89,10 +138,13 @@ def insertionsort(array):
     else:
          raise NotImplementedError
 
-    rust_sort(ptr, n)
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
 
 
-def selectionsort(array):
+def selectionsort(array, policy="last"):
 
     ptr = ctypes.c_void_p(array.ctypes.data)
     n   = len(array)
@@ -123,10 +175,13 @@ def selectionsort(array):
     else:
          raise NotImplementedError
 
-    rust_sort(ptr, n)
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
 
 
-def bubblesort(array):
+def bubblesort(array, policy="last"):
 
     ptr = ctypes.c_void_p(array.ctypes.data)
     n   = len(array)
@@ -157,10 +212,13 @@ def bubblesort(array):
     else:
          raise NotImplementedError
 
-    rust_sort(ptr, n)
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
 
 
-def quicksort(array):
+def quicksort(array, policy="last"):
 
     ptr = ctypes.c_void_p(array.ctypes.data)
     n   = len(array)
@@ -191,10 +249,13 @@ def quicksort(array):
     else:
          raise NotImplementedError
 
-    rust_sort(ptr, n)
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
 
 
-def mergesort(array):
+def mergesort(array, policy="last"):
 
     ptr = ctypes.c_void_p(array.ctypes.data)
     n   = len(array)
@@ -225,10 +286,13 @@ def mergesort(array):
     else:
          raise NotImplementedError
 
-    rust_sort(ptr, n)
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
 
 
-def heapsort(array):
+def heapsort(array, policy="last"):
 
     ptr = ctypes.c_void_p(array.ctypes.data)
     n   = len(array)
@@ -259,4 +323,348 @@ def heapsort(array):
     else:
          raise NotImplementedError
 
-    rust_sort(ptr, n)
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
+
+
+def introsort(array, policy="last"):
+
+    ptr = ctypes.c_void_p(array.ctypes.data)
+    n   = len(array)
+
+    if array.dtype == np.int8:
+        rust_sort = rustlib.ffi_introsort_i8
+    elif array.dtype == np.int16:
+        rust_sort = rustlib.ffi_introsort_i16
+    elif array.dtype == np.int32:
+        rust_sort = rustlib.ffi_introsort_i32
+    elif array.dtype == np.int64:
+        rust_sort = rustlib.ffi_introsort_i64
+
+    elif array.dtype == np.uint8:
+        rust_sort = rustlib.ffi_introsort_u8
+    elif array.dtype == np.uint16:
+        rust_sort = rustlib.ffi_introsort_u16
+    elif array.dtype == np.uint32:
+        rust_sort = rustlib.ffi_introsort_u32
+    elif array.dtype == np.uint64:
+        rust_sort = rustlib.ffi_introsort_u64
+
+    elif array.dtype == np.float32:
+        rust_sort = rustlib.ffi_introsort_f32
+    elif array.dtype == np.float64:
+        rust_sort = rustlib.ffi_introsort_f64
+
+    else:
+         raise NotImplementedError
+
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
+
+
+def timsort(array, policy="last"):
+
+    ptr = ctypes.c_void_p(array.ctypes.data)
+    n   = len(array)
+
+    if array.dtype == np.int8:
+        rust_sort = rustlib.ffi_timsort_i8
+    elif array.dtype == np.int16:
+        rust_sort = rustlib.ffi_timsort_i16
+    elif array.dtype == np.int32:
+        rust_sort = rustlib.ffi_timsort_i32
+    elif array.dtype == np.int64:
+        rust_sort = rustlib.ffi_timsort_i64
+
+    elif array.dtype == np.uint8:
+        rust_sort = rustlib.ffi_timsort_u8
+    elif array.dtype == np.uint16:
+        rust_sort = rustlib.ffi_timsort_u16
+    elif array.dtype == np.uint32:
+        rust_sort = rustlib.ffi_timsort_u32
+    elif array.dtype == np.uint64:
+        rust_sort = rustlib.ffi_timsort_u64
+
+    elif array.dtype == np.float32:
+        rust_sort = rustlib.ffi_timsort_f32
+    elif array.dtype == np.float64:
+        rust_sort = rustlib.ffi_timsort_f64
+
+    else:
+         raise NotImplementedError
+
+    if array.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n))
+
+
+def radixsort(array):
+
+    ptr = ctypes.c_void_p(array.ctypes.data)
+    n   = len(array)
+
+    if array.dtype == np.int8:
+        rust_sort = rustlib.ffi_radixsort_i8
+    elif array.dtype == np.int16:
+        rust_sort = rustlib.ffi_radixsort_i16
+    elif array.dtype == np.int32:
+        rust_sort = rustlib.ffi_radixsort_i32
+    elif array.dtype == np.int64:
+        rust_sort = rustlib.ffi_radixsort_i64
+
+    elif array.dtype == np.uint8:
+        rust_sort = rustlib.ffi_radixsort_u8
+    elif array.dtype == np.uint16:
+        rust_sort = rustlib.ffi_radixsort_u16
+    elif array.dtype == np.uint32:
+        rust_sort = rustlib.ffi_radixsort_u32
+    elif array.dtype == np.uint64:
+        rust_sort = rustlib.ffi_radixsort_u64
+
+    # Radix sort only handles integers.
+
+    else:
+         raise NotImplementedError
+
+    call(rust_sort, ptr, ctypes.c_size_t(n))
+
+
+def sort_with_payload(keys, values, algorithm="quicksort", policy="last"):
+    """Sort the keys array in place and re-arrange the values array (of any dtype, for example a
+    structured array) along with it, using "quicksort", "mergesort" or "heapsort". NaN keys are
+    placed following policy (see NAN_POLICIES)."""
+
+    if len(values) != len(keys):
+        raise ValueError("keys and values differ in length")
+
+    ptr    = ctypes.c_void_p(keys.ctypes.data)
+    n      = len(keys)
+    vptr   = ctypes.c_void_p(values.ctypes.data)
+    size   = values.dtype.itemsize
+
+    if keys.dtype == np.int8:
+        suffix = "i8"
+    elif keys.dtype == np.int16:
+        suffix = "i16"
+    elif keys.dtype == np.int32:
+        suffix = "i32"
+    elif keys.dtype == np.int64:
+        suffix = "i64"
+
+    elif keys.dtype == np.uint8:
+        suffix = "u8"
+    elif keys.dtype == np.uint16:
+        suffix = "u16"
+    elif keys.dtype == np.uint32:
+        suffix = "u32"
+    elif keys.dtype == np.uint64:
+        suffix = "u64"
+
+    elif keys.dtype == np.float32:
+        suffix = "f32"
+    elif keys.dtype == np.float64:
+        suffix = "f64"
+
+    else:
+        raise NotImplementedError
+
+    rust_sort = getattr(rustlib, "ffi_" + algorithm + "_payload_" + suffix)
+    if keys.dtype.kind == "f":
+        call(rust_sort, ptr, ctypes.c_size_t(n), vptr, ctypes.c_size_t(size),
+             ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_sort, ptr, ctypes.c_size_t(n), vptr, ctypes.c_size_t(size))
+
+
+def argsort(array, policy="last"):
+    """The indices sorting the array, like numpy.argsort(kind="stable"). The array is left
+    untouched. NaNs are placed following policy (see NAN_POLICIES)."""
+
+    ptr     = ctypes.c_void_p(array.ctypes.data)
+    n       = len(array)
+    indices = np.empty(n, dtype=np.uintp)
+
+    if array.dtype == np.int8:
+        rust_argsort = rustlib.ffi_argsort_i8
+    elif array.dtype == np.int16:
+        rust_argsort = rustlib.ffi_argsort_i16
+    elif array.dtype == np.int32:
+        rust_argsort = rustlib.ffi_argsort_i32
+    elif array.dtype == np.int64:
+        rust_argsort = rustlib.ffi_argsort_i64
+
+    elif array.dtype == np.uint8:
+        rust_argsort = rustlib.ffi_argsort_u8
+    elif array.dtype == np.uint16:
+        rust_argsort = rustlib.ffi_argsort_u16
+    elif array.dtype == np.uint32:
+        rust_argsort = rustlib.ffi_argsort_u32
+    elif array.dtype == np.uint64:
+        rust_argsort = rustlib.ffi_argsort_u64
+
+    elif array.dtype == np.float32:
+        rust_argsort = rustlib.ffi_argsort_f32
+    elif array.dtype == np.float64:
+        rust_argsort = rustlib.ffi_argsort_f64
+
+    else:
+        raise NotImplementedError
+
+    if array.dtype.kind == "f":
+        call(rust_argsort, ptr, ctypes.c_size_t(n), ctypes.c_void_p(indices.ctypes.data),
+             ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_argsort, ptr, ctypes.c_size_t(n), ctypes.c_void_p(indices.ctypes.data))
+    return indices
+
+
+# Codes of the sorting_column_type enum.
+COLUMN_TYPES = {
+    np.dtype(np.int8):    0,
+    np.dtype(np.int16):   1,
+    np.dtype(np.int32):   2,
+    np.dtype(np.int64):   3,
+    np.dtype(np.uint8):   4,
+    np.dtype(np.uint16):  5,
+    np.dtype(np.uint32):  6,
+    np.dtype(np.uint64):  7,
+    np.dtype(np.float32): 8,
+    np.dtype(np.float64): 9,
+}
+
+def lexsort(keys, descending=None):
+    """The indices sorting the rows of several key columns, like numpy.lexsort(): the LAST key is
+    the primary one. The columns may have different dtypes. descending is an optional sequence of
+    booleans, one per key, sorting the matching column in descending order."""
+
+    # The Rust function takes the primary key first.
+    keys = [np.ascontiguousarray(key) for key in reversed(keys)]
+    if descending is None:
+        descending = [False] * len(keys)
+    descending = list(reversed(descending))
+    if len(descending) != len(keys):
+        raise ValueError("keys and descending differ in length")
+    if len(keys) == 0:
+        raise InvalidArgumentError("no key columns to sort")
+    n = len(keys[0])
+    if any(len(key) != n for key in keys):
+        raise ValueError("the key columns differ in length")
+
+    try:
+        types = [COLUMN_TYPES[key.dtype] for key in keys]
+    except KeyError:
+        raise NotImplementedError
+
+    ncolumns = len(keys)
+    columns  = (ctypes.c_void_p * ncolumns)(*[key.ctypes.data for key in keys])
+    types    = (ctypes.c_int * ncolumns)(*types)
+    flags    = (ctypes.c_int * ncolumns)(*[int(bool(d)) for d in descending])
+    indices  = np.empty(n, dtype=np.uintp)
+
+    call(rustlib.ffi_lexsort, columns, types, flags, ctypes.c_size_t(ncolumns),
+         ctypes.c_size_t(n), ctypes.c_void_p(indices.ctypes.data))
+    return indices
+
+
+def select_nth(array, k, policy="last"):
+    """Re-arrange the array so that array[k] is the value which would be there if it was sorted,
+    like numpy.partition(), and return that value. NaNs are placed following policy (see
+    NAN_POLICIES)."""
+
+    ptr = ctypes.c_void_p(array.ctypes.data)
+    n   = len(array)
+
+    if array.dtype == np.int8:
+        rust_select = rustlib.ffi_select_nth_i8
+    elif array.dtype == np.int16:
+        rust_select = rustlib.ffi_select_nth_i16
+    elif array.dtype == np.int32:
+        rust_select = rustlib.ffi_select_nth_i32
+    elif array.dtype == np.int64:
+        rust_select = rustlib.ffi_select_nth_i64
+
+    elif array.dtype == np.uint8:
+        rust_select = rustlib.ffi_select_nth_u8
+    elif array.dtype == np.uint16:
+        rust_select = rustlib.ffi_select_nth_u16
+    elif array.dtype == np.uint32:
+        rust_select = rustlib.ffi_select_nth_u32
+    elif array.dtype == np.uint64:
+        rust_select = rustlib.ffi_select_nth_u64
+
+    elif array.dtype == np.float32:
+        rust_select = rustlib.ffi_select_nth_f32
+    elif array.dtype == np.float64:
+        rust_select = rustlib.ffi_select_nth_f64
+
+    else:
+        raise NotImplementedError
+
+    if array.dtype.kind == "f":
+        call(rust_select, ptr, ctypes.c_size_t(n), ctypes.c_size_t(k),
+             ctypes.c_int(NAN_POLICIES[policy]))
+    else:
+        call(rust_select, ptr, ctypes.c_size_t(n), ctypes.c_size_t(k))
+    return array[k]
+
+
+def percentile(array, p):
+    """The p-th percentile (0 <= p <= 100) of a float array, linearly interpolated like
+    numpy.percentile(). The array is re-arranged."""
+
+    ptr    = ctypes.c_void_p(array.ctypes.data)
+    n      = len(array)
+    result = ctypes.c_double()
+
+    if array.dtype == np.float32:
+        rust_percentile = rustlib.ffi_percentile_f32
+    elif array.dtype == np.float64:
+        rust_percentile = rustlib.ffi_percentile_f64
+
+    else:
+        raise NotImplementedError
+
+    call(rust_percentile, ptr, ctypes.c_size_t(n), ctypes.c_double(p), ctypes.byref(result))
+    return result.value
+
+
+def median(array):
+    """The median of a float array, like numpy.median(). The array is re-arranged."""
+
+    ptr    = ctypes.c_void_p(array.ctypes.data)
+    n      = len(array)
+    result = ctypes.c_double()
+
+    if array.dtype == np.float32:
+        rust_median = rustlib.ffi_median_f32
+    elif array.dtype == np.float64:
+        rust_median = rustlib.ffi_median_f64
+
+    else:
+        raise NotImplementedError
+
+    call(rust_median, ptr, ctypes.c_size_t(n), ctypes.byref(result))
+    return result.value
+
+
+# C comparator: int compar(const void *a, const void *b, void *ctx)
+COMPARATOR = ctypes.CFUNCTYPE(ctypes.c_int, ctypes.c_void_p, ctypes.c_void_p, ctypes.c_void_p)
+
+def sort_generic(array, compar, ctx=None, algorithm="quicksort"):
+    """Sort any array of fixed-size records (e.g. a structured array) using a
+    C comparator (a COMPARATOR instance) and one of the library's algorithms."""
+
+    ptr   = ctypes.c_void_p(array.ctypes.data)
+    n     = len(array)
+    size  = array.dtype.itemsize
+
+    rust_sort = getattr(rustlib, "ffi_" + algorithm + "_generic")
+    rust_sort.argtypes = [ctypes.c_void_p, ctypes.c_size_t, ctypes.c_size_t,
+                          COMPARATOR, ctypes.c_void_p]
+
+    call(rust_sort, ptr, n, size, compar, ctx)
@@ -0,0 +1,138 @@
+//! Render animations of the sorting algorithms as SVG images.
+//!
+//! Every algorithm sorts a small vector while its operations are recorded (see the `trace`
+//! module). The recording is rendered as an animated SVG, `figures/<name>.svg` (see the `svg`
+//! module), or as a series of SVG frames, `figures/<name>/frame-<k>.svg`, with `--frames`.
+//!
+//! ```ignore
+//! cargo run --bin animate [--n N] [--pattern P] [--seed S] [--frames] [--output DIR] [NAME...]
+//! ```
+//!
+//! The algorithms are chosen by name (see `Sorter::name()`), by default bubble sort, insertion
+//! sort, selection sort, quicksort, merge sort and heap sort. The vector has 32 elements following
+//! the "random" pattern (see `patterns::Pattern`).
+
+extern crate sorting;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+use sorting::patterns::{self, Pattern};
+use sorting::registry::{self, Algorithm};
+use sorting::sorter::Sorter;
+use sorting::svg;
+use sorting::trace;
+
+/// Algorithms animated by default.
+const DEFAULT_NAMES: [&str; 6] = ["bubblesort", "insertionsort", "selectionsort", "quicksort",
+                                  "mergesort", "heapsort"];
+
+/// Options, from the command line.
+struct Options {
+    n: usize,
+    pattern: Pattern,
+    seed: u64,
+    frames: bool,
+    output: PathBuf,
+    algorithms: Vec<Algorithm>,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: animate [--n N] [--pattern P] [--seed S] [--frames] [--output DIR] [NAME...]");
+    eprintln!();
+    let names: Vec<&str> = registry::all().iter().map(|algorithm| algorithm.name()).collect();
+    eprintln!("Algorithms: {}", names.join(", "));
+    let pattern_names: Vec<String> = patterns::all().iter().map(|p| p.to_string()).collect();
+    eprintln!("Patterns: {}", pattern_names.join(", "));
+    process::exit(1);
+}
+
+/// Parse the value following an option.
+fn option_value<T: FromStr>(value: Option<String>) -> T {
+    value.and_then(|value| value.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        n: 32,
+        pattern: Pattern::Random,
+        seed: 0,
+        frames: false,
+        output: PathBuf::from("figures"),
+        algorithms: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--n"       => options.n = option_value(args.next()),
+            "--pattern" => options.pattern = option_value(args.next()),
+            "--seed"    => options.seed = option_value(args.next()),
+            "--frames"  => options.frames = true,
+            "--output"  => options.output = option_value(args.next()),
+            "-h" | "--help" => usage(),
+            name => match registry::find(name) {
+                Some(algorithm) => options.algorithms.push(algorithm),
+                None => {
+                    eprintln!("Unknown algorithm: {}", name);
+                    usage();
+                },
+            },
+        }
+    }
+    if options.algorithms.is_empty() {
+        options.algorithms = DEFAULT_NAMES.iter().map(|name| registry::find(name).unwrap())
+                                                 .collect();
+    }
+    if options.n == 0 {
+        usage();
+    }
+    options
+}
+
+/// Record `algorithm` sorting `initial` and write its animation (or frames) in `output`.
+fn animate(algorithm: Algorithm, initial: &[i32], frames: bool, output: &Path)
+           -> io::Result<PathBuf> {
+    let mut to_sort = initial.to_vec();
+    let events = trace::record(&algorithm, &mut to_sort, |a, b| a.cmp(b));
+    let frames_svg = svg::frames(initial, &events);
+    let options = svg::Options::default();
+
+    if frames {
+        let directory = output.join(algorithm.name());
+        fs::create_dir_all(&directory)?;
+        for (k, frame) in frames_svg.iter().enumerate() {
+            fs::write(directory.join(format!("frame-{:05}.svg", k)),
+                      svg::render_frame(frame, &options))?;
+        }
+        Ok(directory)
+    } else {
+        let filename = output.join(format!("{}.svg", algorithm.name()));
+        fs::write(&filename, svg::render_animation(&frames_svg, &options))?;
+        Ok(filename)
+    }
+}
+
+fn main() {
+    let options = parse_options();
+    let initial: Vec<i32> = patterns::generate(options.pattern, options.n, options.seed);
+
+    if let Err(error) = fs::create_dir_all(&options.output) {
+        eprintln!("Cannot create {}: {}", options.output.display(), error);
+        process::exit(1);
+    }
+
+    for &algorithm in &options.algorithms {
+        match animate(algorithm, &initial, options.frames, &options.output) {
+            Ok(path) => println!("{}: {}", algorithm, path.display()),
+            Err(error) => {
+                eprintln!("Cannot write the animation of {}: {}", algorithm, error);
+                process::exit(1);
+            },
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Benchmark the sorting algorithms.
+//!
+//! Every algorithm sorts vectors of `i32` values of sizes N = 1.5^k, k = 0..29, each size being
+//! repeated 10 times. The timings are written to `benchmark/<name>.txt`, one line per N, in the
+//! format used by `benchmark.py` to plot them.
+//!
+//! ```ignore
+//! cargo run --release --bin benchmark [--repeat R] [--max-n N] [--pattern P] [--output DIR] [NAME...]
+//! ```
+//!
+//! The algorithms are chosen by name (see `Sorter::name()`): "sort" (Rust's standard library
+//! sort), the algorithms of the `registry` and "radixsort". By default, all are benchmarked.
+//!
+//! The input is generated following a pattern (see `patterns::Pattern`), by default random values
+//! among 10 distinct ones ("few-unique:10"), as the original benchmarks did. Every repetition uses
+//! a different seed.
+
+extern crate sorting;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::time::Instant;
+
+use sorting::efficientsorts::radix;
+use sorting::patterns::{self, Pattern};
+use sorting::registry;
+use sorting::sorter::Sorter;
+
+/// Number of sizes: N = 1.5^k, k = 0..NB_SIZES (before removing duplicates).
+const NB_SIZES: i32 = 30;
+
+/// Benchmark options, from the command line.
+struct Options {
+    repeat: usize,
+    max_n: usize,
+    pattern: Pattern,
+    output: PathBuf,
+    names: Vec<String>,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: benchmark [--repeat R] [--max-n N] [--pattern P] [--output DIR] [NAME...]");
+    eprintln!();
+    eprintln!("Algorithms: {}", algorithm_names().join(", "));
+    let pattern_names: Vec<String> = patterns::all().iter().map(|p| p.to_string()).collect();
+    eprintln!("Patterns: {}", pattern_names.join(", "));
+    process::exit(1);
+}
+
+/// Names of all the algorithms, in benchmark order.
+fn algorithm_names() -> Vec<&'static str> {
+    let mut names = vec!["sort"];
+    names.extend(registry::all().iter().map(|algorithm| algorithm.name()));
+    names.push("radixsort");
+    names
+}
+
+/// Parse the value following an option.
+fn option_value<T: FromStr>(value: Option<String>) -> T {
+    value.and_then(|value| value.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        repeat: 10,
+        max_n: usize::MAX,
+        pattern: Pattern::FewUnique(10),
+        output: PathBuf::from("benchmark"),
+        names: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--repeat"  => options.repeat = option_value(args.next()),
+            "--max-n"   => options.max_n = option_value(args.next()),
+            "--pattern" => options.pattern = option_value(args.next()),
+            "--output"  => options.output = option_value(args.next()),
+            "-h" | "--help" => usage(),
+            name => {
+                if !algorithm_names().contains(&name) {
+                    eprintln!("Unknown algorithm: {}", name);
+                    usage();
+                }
+                options.names.push(name.to_string());
+            },
+        }
+    }
+    if options.names.is_empty() {
+        options.names = algorithm_names().iter().map(|name| name.to_string()).collect();
+    }
+    if options.repeat == 0 {
+        usage();
+    }
+    options
+}
+
+/// Vector sizes N = 1.5^k, without duplicates.
+fn sizes(max_n: usize) -> Vec<usize> {
+    let mut sizes: Vec<usize> = (0..NB_SIZES).map(|k| 1.5f64.powi(k) as usize)
+                                             .filter(|&n| n <= max_n)
+                                             .collect();
+    sizes.dedup();
+    sizes
+}
+
+/// Sort `to_sort` with the algorithm called `name`.
+fn sort_with(name: &str, to_sort: &mut [i32]) {
+    match name {
+        "sort"      => to_sort.sort(),
+        "radixsort" => radix::sort(to_sort),
+        name        => registry::find(name).unwrap().sort(to_sort),
+    }
+}
+
+/// Format like C's `%13.7e`: `1.2345678e-04`.
+fn scientific(x: f64) -> String {
+    let formatted = format!("{:.7e}", x);
+    let (mantissa, exponent) = formatted.split_at(formatted.find('e').unwrap());
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    format!("{:>13}", format!("{}e{}{:02}", mantissa, if exponent < 0 { '-' } else { '+' },
+                              exponent.abs()))
+}
+
+/// Time `repeat` sorts of vectors of every size, in seconds.
+fn run_benchmark(name: &str, sizes: &[usize], repeat: usize, pattern: Pattern) -> Vec<Vec<f64>> {
+    println!("{} ({})", name, pattern);
+
+    sizes.iter().enumerate().map(|(ni, &n)| {
+        print!("Ni: {:2}   N: {:6}...", ni, n);
+        io::stdout().flush().unwrap();
+
+        let timings: Vec<f64> = (0..repeat).map(|r| {
+            let mut to_sort: Vec<i32> = patterns::generate(pattern, n, r as u64);
+            let start = Instant::now();
+            sort_with(name, &mut to_sort);
+            let duration = start.elapsed();
+            duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9
+        }).collect();
+
+        let mean = timings.iter().sum::<f64>() / repeat as f64;
+        let variance = timings.iter().map(|t| (t - mean) * (t - mean)).sum::<f64>() / repeat as f64;
+        let std = variance.sqrt();
+        println!(" timing: {} +- {} ({:.1} %) s for {} repeats",
+                 scientific(mean).trim(), scientific(std).trim(), std / mean * 100.0, repeat);
+        timings
+    }).collect()
+}
+
+/// Write the timings in the format of NumPy's `savetxt()`, as `benchmark.py` used to.
+fn write_benchmark(filename: &Path, sizes: &[usize], timings: &[Vec<f64>]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(filename)?);
+
+    let mut header = String::from("#      N");
+    for r in 0..timings[0].len() {
+        header.push_str(&format!(",   Run #{:<2} [s]", r + 1));
+    }
+    writeln!(file, "{}", header)?;
+
+    for (n, run_timings) in sizes.iter().zip(timings) {
+        write!(file, "{:8}", n)?;
+        for &timing in run_timings {
+            write!(file, ", {}", scientific(timing))?;
+        }
+        writeln!(file)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = parse_options();
+    let sizes = sizes(options.max_n);
+    if sizes.is_empty() {
+        usage();
+    }
+
+    if let Err(error) = fs::create_dir_all(&options.output) {
+        eprintln!("Cannot create {}: {}", options.output.display(), error);
+        process::exit(1);
+    }
+
+    for name in &options.names {
+        let timings = run_benchmark(name, &sizes, options.repeat, options.pattern);
+        let filename = options.output.join(format!("{}.txt", name));
+        if let Err(error) = write_benchmark(&filename, &sizes, &timings) {
+            eprintln!("Cannot write {}: {}", filename.display(), error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Install the C interface of the library.
+//!
+//! The header and the pkg-config file generated by the build script are copied, along with the
+//! static and shared libraries, under a prefix:
+//!
+//! ```ignore
+//! cargo run --release --bin install-c -- PREFIX
+//! ```
+//!
+//! giving `PREFIX/include/sorting.h`, `PREFIX/lib/libsorting.a` (and the shared library) and
+//! `PREFIX/lib/pkgconfig/sorting.pc`. The libraries are those Cargo placed next to the executable,
+//! built with the same profile and target.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Header generated by the build script.
+const HEADER: &str = include_str!(concat!(env!("OUT_DIR"), "/sorting.h"));
+
+/// pkg-config file generated by the build script, whose prefix is `@PREFIX@`.
+const PKG_CONFIG: &str = include_str!(concat!(env!("OUT_DIR"), "/sorting.pc.in"));
+
+fn usage() -> ! {
+    eprintln!("Usage: install-c PREFIX");
+    process::exit(1);
+}
+
+/// File names of the static and shared libraries.
+fn library_names() -> [String; 2] {
+    let static_name = if cfg!(target_env = "msvc") { "sorting.lib" } else { "libsorting.a" };
+    [static_name.to_string(),
+     format!("{}sorting{}", env::consts::DLL_PREFIX, env::consts::DLL_SUFFIX)]
+}
+
+/// Install the files under `prefix`, returning the paths written.
+fn install(prefix: &Path) -> io::Result<Vec<PathBuf>> {
+    let include_dir = prefix.join("include");
+    let lib_dir = prefix.join("lib");
+    let pkg_config_dir = lib_dir.join("pkgconfig");
+    fs::create_dir_all(&include_dir)?;
+    fs::create_dir_all(&pkg_config_dir)?;
+    let prefix = fs::canonicalize(prefix)?;
+
+    let mut installed = Vec::new();
+    let header = include_dir.join("sorting.h");
+    fs::write(&header, HEADER)?;
+    installed.push(header);
+
+    // Cargo places the libraries next to the executables.
+    let exe = env::current_exe()?;
+    let artifacts_dir = exe.parent().unwrap();
+    for name in library_names().iter() {
+        let library = artifacts_dir.join(name);
+        if library.exists() {
+            fs::copy(&library, lib_dir.join(name))?;
+            installed.push(lib_dir.join(name));
+        }
+    }
+    if installed.len() == 1 {
+        return Err(io::Error::new(io::ErrorKind::NotFound,
+                                  format!("no library found in {}", artifacts_dir.display())));
+    }
+
+    let pkg_config = pkg_config_dir.join("sorting.pc");
+    fs::write(&pkg_config, PKG_CONFIG.replace("@PREFIX@", &prefix.display().to_string()))?;
+    installed.push(pkg_config);
+    Ok(installed)
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let prefix = match (args.next(), args.next()) {
+        (Some(prefix), None) => PathBuf::from(prefix),
+        _                    => usage(),
+    };
+    match install(&prefix) {
+        Ok(installed) => {
+            for path in installed {
+                println!("{}", path.display());
+            }
+        },
+        Err(error) => {
+            eprintln!("Cannot install to {}: {}", prefix.display(), error);
+            process::exit(1);
+        },
+    }
+}
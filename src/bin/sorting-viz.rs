@@ -0,0 +1,185 @@
+//! Watch a sorting algorithm run in a terminal.
+//!
+//! The algorithm sorts a vector while its operations are recorded (see the `trace` module), using
+//! the implementation of the `simplesorts` or `efficientsorts` module. The recording is then
+//! replayed as a bar chart drawn with ANSI escape codes, one operation at a time: the bars compared
+//! are yellow, the bars swapped or written red and the bars at their final location green. The
+//! part of the vector being sorted by the current recursive call is underlined by a `-` line.
+//!
+//! ```ignore
+//! cargo run --bin sorting-viz -- [--n N] [--pattern P] [--seed S] [--delay MS] [--height H] [NAME]
+//! ```
+//!
+//! The algorithm is chosen by name (see `Sorter::name()`), quicksort by default. The vector has
+//! 32 elements following the "random" pattern (see `patterns::Pattern`) and every operation is
+//! shown for 30 ms.
+
+extern crate sorting;
+
+use std::env;
+use std::io::{self, Write};
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use sorting::patterns::{self, Pattern};
+use sorting::registry::{self, Algorithm};
+use sorting::sorter::Sorter;
+use sorting::svg::{self, Frame, Highlight};
+use sorting::trace;
+
+/// Background colors of the bars.
+const ANSI_DEFAULT: &str = "\x1b[44m";
+const ANSI_COMPARE: &str = "\x1b[43m";
+const ANSI_MOVE: &str = "\x1b[41m";
+const ANSI_DONE: &str = "\x1b[42m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Clear the screen and hide the cursor.
+const ANSI_START: &str = "\x1b[2J\x1b[?25l";
+/// Move the cursor to the top left corner.
+const ANSI_HOME: &str = "\x1b[H";
+/// Show the cursor again.
+const ANSI_END: &str = "\x1b[?25h";
+
+/// Options, from the command line.
+struct Options {
+    n: usize,
+    pattern: Pattern,
+    seed: u64,
+    delay: u64,
+    height: usize,
+    algorithm: Algorithm,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: sorting-viz [--n N] [--pattern P] [--seed S] [--delay MS] [--height H] [NAME]");
+    eprintln!();
+    let names: Vec<&str> = registry::all().iter().map(|algorithm| algorithm.name()).collect();
+    eprintln!("Algorithms: {}", names.join(", "));
+    let pattern_names: Vec<String> = patterns::all().iter().map(|p| p.to_string()).collect();
+    eprintln!("Patterns: {}", pattern_names.join(", "));
+    process::exit(1);
+}
+
+/// Parse the value following an option.
+fn option_value<T: FromStr>(value: Option<String>) -> T {
+    value.and_then(|value| value.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        n: 32,
+        pattern: Pattern::Random,
+        seed: 0,
+        delay: 30,
+        height: 16,
+        algorithm: Algorithm::Quick,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--n"       => options.n = option_value(args.next()),
+            "--pattern" => options.pattern = option_value(args.next()),
+            "--seed"    => options.seed = option_value(args.next()),
+            "--delay"   => options.delay = option_value(args.next()),
+            "--height"  => options.height = option_value(args.next()),
+            "-h" | "--help" => usage(),
+            name => match registry::find(name) {
+                Some(algorithm) => options.algorithm = algorithm,
+                None => {
+                    eprintln!("Unknown algorithm: {}", name);
+                    usage();
+                },
+            },
+        }
+    }
+    if options.n == 0 || options.height == 0 {
+        usage();
+    }
+    options
+}
+
+/// Running totals of the operations shown.
+#[derive(Default)]
+struct Counters {
+    comparisons: usize,
+    swaps: usize,
+    writes: usize,
+}
+
+/// Color of the bar at index `i` in `frame`.
+fn color(frame: &Frame, i: usize) -> &'static str {
+    match frame.highlight {
+        Highlight::Compare(a, b) if i == a || i == b => ANSI_COMPARE,
+        Highlight::Swap(a, b) if i == a || i == b    => ANSI_MOVE,
+        Highlight::Write(a) if i == a                => ANSI_MOVE,
+        _ if frame.done[i]                           => ANSI_DONE,
+        _                                            => ANSI_DEFAULT,
+    }
+}
+
+/// Draw `frame` as a bar chart of `height` rows, followed by the counters.
+fn draw(frame: &Frame, height: usize, title: &str, counters: &Counters, progress: (usize, usize))
+        -> String {
+    let n = frame.ranks.len();
+    // Every bar is at least one row high, the largest ones `height` rows.
+    let rows: Vec<usize> = frame.ranks.iter().map(|&rank| (rank + 1) * height / n).map(|r| r.max(1))
+                                .collect();
+
+    let mut screen = String::from(ANSI_HOME);
+    screen.push_str(title);
+    screen.push_str("\x1b[K\n");
+    for row in (1..height + 1).rev() {
+        for (i, &bar) in rows.iter().enumerate() {
+            if bar >= row {
+                screen.push_str(color(frame, i));
+                screen.push_str("  ");
+                screen.push_str(ANSI_RESET);
+            } else {
+                screen.push_str("  ");
+            }
+        }
+        screen.push('\n');
+    }
+    for i in 0..n {
+        let inside = frame.range.is_some_and(|(start, end)| start <= i && i < end);
+        screen.push_str(if inside { "--" } else { "  " });
+    }
+    screen.push('\n');
+    screen.push_str(&format!("comparisons: {:6}   swaps: {:6}   writes: {:6}   step: {}/{}\x1b[K\n",
+                             counters.comparisons, counters.swaps, counters.writes,
+                             progress.0, progress.1));
+    screen
+}
+
+fn main() {
+    let options = parse_options();
+    let initial: Vec<i32> = patterns::generate(options.pattern, options.n, options.seed);
+
+    let mut to_sort = initial.clone();
+    let events = trace::record(&options.algorithm, &mut to_sort, |a, b| a.cmp(b));
+    let frames = svg::frames(&initial, &events);
+    let title = format!("{} ({}, N = {})", options.algorithm, options.pattern, options.n);
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut counters = Counters::default();
+    write!(stdout, "{}", ANSI_START).unwrap();
+    for (f, frame) in frames.iter().enumerate() {
+        match frame.highlight {
+            Highlight::Compare(..) => counters.comparisons += 1,
+            Highlight::Swap(..)    => counters.swaps += 1,
+            Highlight::Write(..)   => counters.writes += 1,
+            Highlight::None        => {},
+        }
+        let screen = draw(frame, options.height, &title, &counters, (f, frames.len() - 1));
+        if stdout.write_all(screen.as_bytes()).and_then(|_| stdout.flush()).is_err() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(options.delay));
+    }
+    write!(stdout, "{}", ANSI_END).unwrap();
+}
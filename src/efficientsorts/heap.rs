@@ -6,7 +6,13 @@
 
 extern crate libc;
 
-use std::slice;
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use permutation::{Payload, SwapPayload};
+use sorter::{Heap, Sorter};
 
 
 /// Heap sort
@@ -28,6 +34,12 @@ use std::slice;
 /// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
 /// compiler knows how to compare the elements and sort them.
 ///
+/// The implementation is not 'stable': building the heap and moving its root to the end of the
+/// vector change the relative order of items with equal values.
+///
+/// Sifting elements down the heap swaps them with their children, so a panicking comparison leaves
+/// the vector as a permutation of its initial elements.
+///
 /// The design comes from the Wikipedia page on heap sort.
 ///
 /// # Examples
@@ -39,18 +51,197 @@ use std::slice;
 /// ```
 ///
 pub fn sort<T: PartialOrd>(input: &mut [T]) {
-    let n = input.len();
+    sort_by(input, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
 
-    if n > 2 {
-        heapify(input);
+/// Heap sort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::heap::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(input: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(input, compare, &mut Unobserved);
+}
 
-        let mut end = n - 1;
-        while end > 0 {
-            input.swap(end, 0);
-            end -= 1;
-            sift_down(input, 0, end);
-        }
-    }
+/// Heap sort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison and swap (see the
+/// `instrument` module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::efficientsorts::heap::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(counts.allocations, 0);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(input: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    heap_sort(input, 0, &mut compare, observer);
+}
+
+/// Heap sort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::efficientsorts::heap::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(input: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(input, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Heap sort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::efficientsorts::heap::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(input: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(input, policy)?;
+    sort_by(input, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Heap sort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// The algorithm is not stable: the indices of equal elements end up in any order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::efficientsorts::heap::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(input: &[T]) -> Vec<usize> {
+    Heap.argsort(input)
+}
+
+/// Heap sort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::efficientsorts::heap::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(input: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Heap.argsort_by(input, compare)
+}
+
+/// Heap sort of keys, along with a payload.
+///
+/// # Details
+///
+/// Sort `keys` and apply the same permutation to `payload`: one or more companion slices, the
+/// element at index `i` of which is associated with key `i` (see `permutation::Payload`).
+///
+/// Heap sort only swaps elements, and every swap of two keys is mirrored on the payload (see
+/// `instrument::Observer`): no memory is allocated. If the comparison panics, the keys and the
+/// payload are left permuted alike.
+///
+/// # Panics
+///
+/// Panics if the payload's length differs from the number of keys.
+///
+/// # Examples
+///
+/// ```
+/// let mut keys: Vec<u32> = vec![3, 1, 2];
+/// let mut values: Vec<&str> = vec!["c", "a", "b"];
+/// sorting::efficientsorts::heap::sort_with_payload(&mut keys, &mut values);
+/// assert_eq!(keys, vec![1, 2, 3]);
+/// assert_eq!(values, vec!["a", "b", "c"]);
+/// ```
+///
+pub fn sort_with_payload<K, P>(keys: &mut [K], payload: &mut P)
+    where K: PartialOrd,
+          P: Payload + ?Sized
+{
+    sort_with_payload_by(keys, payload, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Heap sort of keys, along with a payload, using a comparator function.
+///
+/// # Panics
+///
+/// Panics if the payload's length differs from the number of keys.
+///
+/// # Examples
+///
+/// ```
+/// let mut keys: Vec<u32> = vec![3, 1, 2];
+/// let mut values: Vec<&str> = vec!["c", "a", "b"];
+/// sorting::efficientsorts::heap::sort_with_payload_by(&mut keys, &mut values, |a, b| b.cmp(a));
+/// assert_eq!(values, vec!["c", "b", "a"]);
+/// ```
+///
+pub fn sort_with_payload_by<K, P, F>(keys: &mut [K], payload: &mut P, compare: F)
+    where P: Payload + ?Sized,
+          F: FnMut(&K, &K) -> Ordering
+{
+    assert_eq!(keys.len(), payload.len(), "the payload and the keys differ in length");
+    sort_by_observed(keys, compare, &mut SwapPayload(payload));
 }
 
 fn node_parent_id(i: usize) -> usize {
@@ -66,7 +257,47 @@ fn node_child_left(i: usize) -> usize {
 //     2 * i + 2
 // }
 
-fn heapify<T: PartialOrd>(input: &mut [T]) {
+/// Heap sort of `input`, located at index `offset` of the vector seen by `observer`.
+pub(crate) fn heap_sort<T, F, O>(input: &mut [T], offset: usize, compare: &mut F,
+                                 observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = input.len();
+
+    if n > 2 {
+        heapify(input, offset, compare, observer);
+        sort_heap(input, offset, compare, observer);
+    } else if n == 2 {
+        observer.compare(offset, offset+1);
+        if compare(&input[0], &input[1]) == Ordering::Greater {
+            input.swap(0, 1);
+            observer.swap(offset, offset+1);
+        }
+    }
+}
+
+/// Sort `input`, a non-empty max-heap, by moving its root to the end repeatedly.
+pub(crate) fn sort_heap<T, F, O>(input: &mut [T], offset: usize, compare: &mut F,
+                                 observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let mut end = input.len() - 1;
+    while end > 0 {
+        input.swap(end, 0);
+        observer.swap(offset+end, offset);
+        end -= 1;
+        sift_down(input, offset, 0, end, compare, observer);
+    }
+}
+
+/// Re-arrange a non-empty vector into a max-heap.
+pub(crate) fn heapify<T, F, O>(input: &mut [T], offset: usize, compare: &mut F,
+                               observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
     let n = input.len();
     let end = n - 1;
 
@@ -74,13 +305,19 @@ fn heapify<T: PartialOrd>(input: &mut [T]) {
     let mut start: usize = node_parent_id(n-1);
 
     loop {
-        sift_down(input, start, end);
+        sift_down(input, offset, start, end, compare, observer);
         if start == 0 { break; }
         start -= 1;
     }
 }
 
-fn sift_down<T: PartialOrd>(input: &mut [T], start: usize, end: usize) {
+/// Move the element at index `start` down the max-heap ending at index `end` (included) until it
+/// is not smaller than its children.
+pub(crate) fn sift_down<T, F, O>(input: &mut [T], offset: usize, start: usize, end: usize,
+                                 compare: &mut F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
     let mut i = start;
 
     // Starting at the "start" element, loop "down" the vector and swap elements that are
@@ -89,17 +326,22 @@ fn sift_down<T: PartialOrd>(input: &mut [T], start: usize, end: usize) {
         let child = node_child_left(i);
         let mut swap = i;
 
-        if input[swap] < input[child] {
+        observer.compare(offset+swap, offset+child);
+        if compare(&input[swap], &input[child]) == Ordering::Less {
             swap = child;
         }
-        if child+1 <= end && input[swap] < input[child+1] {
-            swap = child + 1;
+        if child < end {
+            observer.compare(offset+swap, offset+child+1);
+            if compare(&input[swap], &input[child+1]) == Ordering::Less {
+                swap = child + 1;
+            }
         }
 
         if swap == i {
             return;
         } else {
             input.swap(i, swap);
+            observer.swap(offset+i, offset+swap);
             i = swap;
         }
     }
@@ -107,95 +349,82 @@ fn sift_down<T: PartialOrd>(input: &mut [T], start: usize, end: usize) {
 
 
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_i8(array_pointer: *const libc::int8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_i16(array_pointer: *const libc::int16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_i32(array_pointer: *const libc::int32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_i64(array_pointer: *const libc::int64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_u8(array_pointer: *const libc::uint8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_u16(array_pointer: *const libc::uint16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_u32(array_pointer: *const libc::uint32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_u64(array_pointer: *const libc::uint64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_f32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                   policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
 }
 #[no_mangle]
-pub extern "C" fn ffi_heapsort_f64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_heapsort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                   policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
+#[no_mangle]
+pub extern "C" fn ffi_heapsort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                       size: libc::size_t, compar: Option<ffi::Comparator>,
+                                       ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Heap, base, nmemb, size, compar, ctx)
+}
+
+payload_exports! {
+    sort_with_payload_by;
+    integers {
+        ffi_heapsort_payload_i8: i8,
+        ffi_heapsort_payload_i16: i16,
+        ffi_heapsort_payload_i32: i32,
+        ffi_heapsort_payload_i64: i64,
+        ffi_heapsort_payload_u8: u8,
+        ffi_heapsort_payload_u16: u16,
+        ffi_heapsort_payload_u32: u32,
+        ffi_heapsort_payload_u64: u64,
+    }
+    floats {
+        ffi_heapsort_payload_f32: f32,
+        ffi_heapsort_payload_f64: f64,
+    }
 }
@@ -0,0 +1,307 @@
+//! Introsort algorithm.
+//!
+//! The `efficient` module contains the efficient sorting algorithm "Introsort".
+//!
+//! Source: https://en.wikipedia.org/wiki/Introsort
+
+extern crate libc;
+
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use sorter::{Intro, Sorter};
+use efficientsorts::{heap, quick};
+use simplesorts::insertion;
+
+/// Partitions of at most this number of elements are sorted using insertion sort.
+const INSERTION_THRESHOLD: usize = 16;
+
+/// Introsort
+///
+/// # Details
+///
+/// Introsort ("introspective sort") is a quicksort that keeps track of its recursion depth. When
+/// the depth exceeds a limit proportional to log N, the partitioning is considered to be going
+/// badly and the current partition is sorted using heap sort instead. Small partitions are sorted
+/// using insertion sort.
+///
+/// The partitioning is the median-of-three one from `quick`, the fallback is `heap::sort_by()`
+/// and small partitions use `insertion::sort_by()`.
+///
+/// # Scaling
+///
+/// Introsort has the O(N log N) average case complexity of quicksort. Quicksort degrades to
+/// O(N^2) (and to a recursion depth of O(N)) on inputs where the chosen pivots are always close
+/// to the smallest or largest element, for example when all elements are equal or on inputs
+/// crafted against the median-of-three choice. Introsort bounds the recursion depth to
+/// 2 log2(N), after which heap sort guarantees O(N log N), making the worst case O(N log N).
+///
+/// The space complexity is O(log N) due to the bounded recursion.
+///
+/// # Optimizations
+///
+/// Insertion sort is used for partitions of at most 16 elements.
+///
+/// # Notes
+///
+/// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
+/// compiler knows how to compare the elements and sort them.
+///
+/// The implementation is not 'stable', as quicksort and heap sort are not.
+///
+/// Quicksort, heap sort and insertion sort only swap elements: if the comparison panics, the
+/// vector is left as a permutation of its initial elements.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::intro::sort(&mut data);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// ```
+///
+pub fn sort<T: PartialOrd>(array: &mut [T]) {
+    sort_by(array, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Introsort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::intro::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(array: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(array, compare, &mut Unobserved);
+}
+
+/// Introsort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison and swap, for the
+/// allocation of the pivot candidates, after every partitioning step and when entering and leaving
+/// every recursive call (see the `instrument` module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::efficientsorts::intro::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// // Small vectors are sorted using insertion sort.
+/// assert_eq!(counts.allocations, 0);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(array: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let depth_limit = 2 * log2(array.len());
+    intro_sort(array, 0, depth_limit, &mut compare, observer);
+}
+
+/// Introsort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::efficientsorts::intro::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(array: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(array, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Introsort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::efficientsorts::intro::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(array: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(array, policy)?;
+    sort_by(array, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Introsort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// The algorithm is not stable: the indices of equal elements end up in any order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::efficientsorts::intro::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(array: &[T]) -> Vec<usize> {
+    Intro.argsort(array)
+}
+
+/// Introsort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::efficientsorts::intro::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(array: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Intro.argsort_by(array, compare)
+}
+
+/// Floor of the base 2 logarithm of `n` (zero for `n` <= 1).
+fn log2(n: usize) -> usize {
+    let mut log = 0;
+    let mut n = n;
+    while n > 1 {
+        n /= 2;
+        log += 1;
+    }
+    log
+}
+
+// "offset" is the index of "array" in the vector seen by the observer.
+fn intro_sort<T, F, O>(array: &mut [T], offset: usize, depth_limit: usize, compare: &mut F,
+                       observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = array.len();
+    observer.enter(offset, offset+n);
+
+    if n <= INSERTION_THRESHOLD {
+        insertion::insertion_sort(array, offset, compare, observer);
+    } else if depth_limit == 0 {
+        // Too many bad pivots: stop recursing and fall back to heap sort's guaranteed
+        // O(N log N) for this partition.
+        heap::heap_sort(array, offset, compare, observer);
+    } else {
+        let pivot = quick::partition(array, offset, compare, observer);
+
+        // The pivot is at its final location: it does not need to be part of any partition.
+        intro_sort(&mut array[0..pivot], offset, depth_limit - 1, compare, observer);
+        intro_sort(&mut array[pivot+1..n], offset+pivot+1, depth_limit - 1, compare, observer);
+    }
+
+    observer.exit(offset, offset+n);
+}
+
+
+
+#[no_mangle]
+pub extern "C" fn ffi_introsort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_introsort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_introsort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_introsort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+
+
+#[no_mangle]
+pub extern "C" fn ffi_introsort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_introsort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_introsort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_introsort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+
+#[no_mangle]
+pub extern "C" fn ffi_introsort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                    policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+#[no_mangle]
+pub extern "C" fn ffi_introsort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                    policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
+#[no_mangle]
+pub extern "C" fn ffi_introsort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                        size: libc::size_t, compar: Option<ffi::Comparator>,
+                                        ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Intro, base, nmemb, size, compar, ctx)
+}
@@ -6,8 +6,14 @@
 
 extern crate libc;
 
-use std::slice;
 use std::ptr;
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use permutation::{self, Payload};
+use sorter::{Merge, Sorter};
 
 /// Mergesort
 ///
@@ -26,24 +32,28 @@ use std::ptr;
 /// and N steps per level to merge these subvectors.
 ///
 /// Merge sort cannot merge in-place without shifting elements often in the merge process. To
-/// prevent this shifting, a temporary vector is allocated (and freed) at every level of the
-/// recursion. This temporary vector has the same size as the vector that is split in two at every
-/// level. During the last merging step the allocation is the same size as the initial vector to
-/// sort, hence the space complexity is O(N).
+/// prevent this shifting, the left half of the two subvectors to merge is moved to a temporary
+/// buffer and the merged elements are written back into the vector. The buffer is allocated once
+/// with half the size of the initial vector and reused at every level of the recursion, hence the
+/// space complexity is O(N).
 ///
 /// # Optimizations
 ///
-/// An unsafe block is used to copy the content of the temporary vector back into the function
-/// argument through `ptr::copy_nonoverlapping()`. Without this, one would have to loop over
-/// every elements which would be highly inefficient.
+/// Elements are moved (not cloned) between the vector and the temporary buffer using
+/// `ptr::copy_nonoverlapping()` inside unsafe blocks. Without this, one would have to clone every
+/// elements which would be inefficient (and impossible for types not implementing `Clone`).
 ///
 /// # Notes
 ///
 /// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
 /// compiler knows how to compare the elements and sort them.
 ///
-/// Also, the trait `Clone` must also be implemented for `T` to be able to copy the vector elements
-/// into the temporary vector.
+/// The implementation is 'stable' as it does preserve the relative order of items with
+/// equal values.
+///
+/// If the comparison panics, the elements still in the temporary buffer are moved back into the
+/// vector before unwinding: the vector is left as a (partially sorted) permutation of its
+/// initial elements and every element is dropped exactly once.
 ///
 /// # Examples
 ///
@@ -53,79 +63,321 @@ use std::ptr;
 /// assert_eq!(vec![1, 2, 3, 4, 5], data);
 /// ```
 ///
-pub fn sort<T: PartialOrd+Clone>(input: &mut [T]) {
+pub fn sort<T: PartialOrd>(input: &mut [T]) {
+    sort_by(input, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Mergesort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::merge::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(input: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(input, compare, &mut Unobserved);
+}
+
+/// Mergesort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison and swap, for the
+/// allocation of the temporary buffer, for every element moved to or from it and when entering and
+/// leaving every recursive call (see the `instrument` module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::efficientsorts::merge::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(counts.allocations, 1);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(input: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = input.len();
+
+    if n > 1 {
+        // Allocate the temporary buffer once. It will only ever hold the left half of the
+        // subvectors to merge, the largest being the left half of the initial vector.
+        // Its length stays zero so dropping it never drops the elements moved through it.
+        let mut buffer: Vec<T> = Vec::with_capacity(n / 2);
+        observer.allocate(n / 2);
+        merge_sort(input, 0, buffer.as_mut_ptr(), &mut compare, observer);
+    }
+}
+
+/// Mergesort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::efficientsorts::merge::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(input: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(input, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Mergesort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::efficientsorts::merge::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(input: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(input, policy)?;
+    sort_by(input, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Merge sort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// As the algorithm is stable, the indices of equal elements stay in increasing order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::efficientsorts::merge::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(input: &[T]) -> Vec<usize> {
+    Merge.argsort(input)
+}
+
+/// Merge sort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::efficientsorts::merge::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(input: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Merge.argsort_by(input, compare)
+}
+
+/// Merge sort of keys, along with a payload.
+///
+/// # Details
+///
+/// Sort `keys` and apply the same permutation to `payload`: one or more companion slices, the
+/// element at index `i` of which is associated with key `i` (see `permutation::Payload`).
+///
+/// Merge sort moves elements through a temporary buffer, which cannot be mirrored on the payload:
+/// the indices of the keys are sorted instead (see `argsort_by()`), then the permutation is
+/// applied to the keys and to the payload by swapping elements along its cycles. The result is
+/// stable. If the comparison panics, the keys and the payload are left untouched.
+///
+/// # Panics
+///
+/// Panics if the payload's length differs from the number of keys.
+///
+/// # Examples
+///
+/// ```
+/// let mut keys: Vec<u32> = vec![3, 1, 2];
+/// let mut values: Vec<&str> = vec!["c", "a", "b"];
+/// sorting::efficientsorts::merge::sort_with_payload(&mut keys, &mut values);
+/// assert_eq!(keys, vec![1, 2, 3]);
+/// assert_eq!(values, vec!["a", "b", "c"]);
+/// ```
+///
+pub fn sort_with_payload<K, P>(keys: &mut [K], payload: &mut P)
+    where K: PartialOrd,
+          P: Payload + ?Sized
+{
+    sort_with_payload_by(keys, payload, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Merge sort of keys, along with a payload, using a comparator function.
+///
+/// # Panics
+///
+/// Panics if the payload's length differs from the number of keys.
+///
+/// # Examples
+///
+/// ```
+/// let mut keys: Vec<u32> = vec![3, 1, 2];
+/// let mut values: Vec<&str> = vec!["c", "a", "b"];
+/// sorting::efficientsorts::merge::sort_with_payload_by(&mut keys, &mut values, |a, b| b.cmp(a));
+/// assert_eq!(values, vec!["c", "b", "a"]);
+/// ```
+///
+pub fn sort_with_payload_by<K, P, F>(keys: &mut [K], payload: &mut P, compare: F)
+    where P: Payload + ?Sized,
+          F: FnMut(&K, &K) -> Ordering
+{
+    assert_eq!(keys.len(), payload.len(), "the payload and the keys differ in length");
+    let indices = argsort_by(keys, compare);
+    permutation::apply_with_payload(keys, payload, &indices);
+}
+
+// The comparator is taken by mutable reference so the recursion does not create a new closure
+// type (and thus a new instantiation of the function) at every level. "offset" is the index of
+// "input" in the vector seen by the observer.
+fn merge_sort<T, F, O>(input: &mut [T], offset: usize, buffer: *mut T, compare: &mut F,
+                       observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
     let n = input.len();
+    observer.enter(offset, offset+n);
 
     if n <= 1 {
         // Nothing to do
     } else if n == 2 {
         // Manually sort the two elements
-        if input.first() > input.last() {
+        observer.compare(offset+1, offset);
+        if compare(&input[1], &input[0]) == Ordering::Less {
             input.swap(0, 1);
+            observer.swap(offset, offset+1);
         }
     } else {
         // Perform a merge sort
-        assert!(n > 2);
+        let n2 = n / 2;
 
-        // Allocate a vector that will hold the sorted "input" values temporarily.
-        // This prevents having to move elements inside the "input" vector directly.
-        // This makes the implementation O(N) for space complexity.
-        let mut tmp: Vec<T> = Vec::with_capacity(n);
-
-        // New code block as to enforce "split_left" and "split_right"'s lifetime, ending the
-        // mutable borrow on input. This is required to copy the elements of "tmp" back
-        // into "input".
+        // Recursively call the function on slices of the vector.
         {
-            let n2 = n / 2;
-            let (mut split_left, mut split_right) = input.split_at_mut(n2);
-
-            // Recursively call the function on slices of the vector.
-            sort(&mut split_left);
-            sort(&mut split_right);
-
-            // Keep peekable iterators into left and right slices.
-            // NOTE: We need a peekable iterator as we must not iterate over each slice at every
-            //       loop iteration.
-            let mut iter_left  = split_left.iter().peekable();
-            let mut iter_right = split_right.iter().peekable();
-
-            // Loop over the size of the "input" vector. We'll copy the smallest element of
-            // either the left or right slice into "tmp".
-            for _ in 0..n {
-                // If there is still elements in both the left and right slice, take the
-                // smallest of the two jump to the next element of the peekable iterator
-                // of that particular slice.
-                if !iter_left.peek().is_none() && !iter_right.peek().is_none() {
-                    // Should we take an element from the left slice?
-                    let take_left: bool = iter_left.peek() < iter_right.peek();
-                    if take_left {
-                        // If so, get the element and push() it at the end of the "tmp" vector.
-                        // Use next() to advance that slice's iterator.
-                        tmp.push(iter_left.next().cloned().unwrap());
-                    } else {
-                        // If not, insert the element from the right slice instead.
-                        tmp.push(iter_right.next().cloned().unwrap());
-                    }
-                } else if iter_left.peek().is_none() {
-                    // Left slice is now purged: insert into "tmp" elements from the right slice,
-                    // advancing the iterator.
-                    debug_assert!(iter_left.peek().is_none());
-                    debug_assert!(!iter_right.peek().is_none());
-                    tmp.push(iter_right.next().cloned().unwrap());
-                } else {
-                    // Right slice is now purged: insert into "tmp" elements from the left slice,
-                    // advancing the iterator.
-                    debug_assert!(!iter_left.peek().is_none());
-                    debug_assert!(iter_right.peek().is_none());
-                    tmp.push(iter_left.next().cloned().unwrap());
-                }
+            let (split_left, split_right) = input.split_at_mut(n2);
+            merge_sort(split_left, offset, buffer, compare, observer);
+            merge_sort(split_right, offset+n2, buffer, compare, observer);
+        }
+
+        merge(input, offset, n2, buffer, compare, observer);
+    }
+
+    observer.exit(offset, offset+n);
+}
+
+/// Merge the two sorted subvectors `input[..mid]` and `input[mid..]`.
+///
+/// The left subvector is moved into `buffer` (which must have room for `mid` elements), leaving
+/// a "hole" at the beginning of `input`. The smallest of the front elements of the buffer and of
+/// the right subvector is then moved into the hole, one at a time.
+///
+/// The hole always has exactly the size of what remains in the buffer: every element taken from
+/// the right subvector frees its own slot. The right subvector's elements thus never get
+/// overwritten before being read and, when the buffer is exhausted, the remaining right elements
+/// are already at their proper location.
+fn merge<T, F, O>(input: &mut [T], offset: usize, mid: usize, buffer: *mut T, compare: &mut F,
+                  observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = input.len();
+    let array = input.as_mut_ptr();
+
+    unsafe {
+        ptr::copy_nonoverlapping(array, buffer, mid);
+        observer.move_to_buffer(offset, mid);
+
+        // From here on, the guard owns the elements in "buffer[left..left_end]" and knows where
+        // they belong in "input". If "compare" panics, its destructor moves them back into the
+        // hole so the slice stays a valid permutation.
+        let mut hole = MergeHole {
+            buffer,
+            left: 0,
+            left_end: mid,
+            array,
+            dest: 0,
+        };
+        let mut right = mid;
+
+        while hole.left < hole.left_end && right < n {
+            // Take from the right subvector only if strictly smaller: on equality, the left
+            // element (which came first) is kept first, making the merge stable.
+            // The buffer's elements were moved from the beginning of "input".
+            observer.compare(offset + right, offset + hole.left);
+            if compare(&*array.add(right), &*buffer.add(hole.left)) == Ordering::Less {
+                ptr::copy_nonoverlapping(array.add(right), array.add(hole.dest), 1);
+                right += 1;
+            } else {
+                ptr::copy_nonoverlapping(buffer.add(hole.left), array.add(hole.dest), 1);
+                hole.left += 1;
             }
+            observer.write(offset + hole.dest, &*array.add(hole.dest));
+            hole.dest += 1;
+        }
+
+        // Dropping "hole" moves what is left of the buffer into its final location.
+        for k in 0..hole.left_end - hole.left {
+            observer.write(offset + hole.dest + k, &*buffer.add(hole.left + k));
         }
+    }
+}
+
+/// Elements of the left subvector that are still in the temporary buffer during a merge.
+struct MergeHole<T> {
+    buffer: *mut T,
+    left: usize,
+    left_end: usize,
+    array: *mut T,
+    dest: usize,
+}
 
-        // Copy content of "tmp" back into "input" vector.
-        assert_eq!(tmp.len(), n);
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
         unsafe {
-            ptr::copy_nonoverlapping(tmp.as_ptr(), input.as_mut_ptr(), n);
+            ptr::copy_nonoverlapping(self.buffer.add(self.left),
+                                     self.array.add(self.dest),
+                                     self.left_end - self.left);
         }
     }
 }
@@ -133,95 +385,82 @@ pub fn sort<T: PartialOrd+Clone>(input: &mut [T]) {
 
 
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_i8(array_pointer: *const libc::int8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_i16(array_pointer: *const libc::int16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_i32(array_pointer: *const libc::int32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_i64(array_pointer: *const libc::int64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_u8(array_pointer: *const libc::uint8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_u16(array_pointer: *const libc::uint16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_u32(array_pointer: *const libc::uint32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_u64(array_pointer: *const libc::uint64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_f32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                    policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+#[no_mangle]
+pub extern "C" fn ffi_mergesort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                    policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
 }
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
 #[no_mangle]
-pub extern "C" fn ffi_mergesort_f64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_mergesort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                        size: libc::size_t, compar: Option<ffi::Comparator>,
+                                        ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Merge, base, nmemb, size, compar, ctx)
+}
+
+payload_exports! {
+    sort_with_payload_by;
+    integers {
+        ffi_mergesort_payload_i8: i8,
+        ffi_mergesort_payload_i16: i16,
+        ffi_mergesort_payload_i32: i32,
+        ffi_mergesort_payload_i64: i64,
+        ffi_mergesort_payload_u8: u8,
+        ffi_mergesort_payload_u16: u16,
+        ffi_mergesort_payload_u32: u32,
+        ffi_mergesort_payload_u64: u64,
+    }
+    floats {
+        ffi_mergesort_payload_f32: f32,
+        ffi_mergesort_payload_f64: f64,
+    }
 }
@@ -2,6 +2,51 @@
 //!
 //! The `efficientsorts` module contains efficient sorting algorithms.
 
+/// Export the functions sorting keys along with records, `ffi_<name>_payload_<type>()` (see the
+/// `ffi` module), using `$sort_with_payload_by`, a module's `sort_with_payload_by()`.
+///
+/// Every export is given by its name and the type of its keys. The exports sorting floating point
+/// keys take a NaN `policy` (see `ffi::sort_floats_with_payload_array()`). build.rs declares the
+/// exports listed by the invocation in the C header.
+macro_rules! payload_exports {
+    ($sort_with_payload_by:path;
+     integers { $($integer:ident: $integer_type:ty),* $(,)? }
+     floats { $($float:ident: $float_type:ty),* $(,)? }) => {
+        $(
+            #[no_mangle]
+            pub extern "C" fn $integer(keys_pointer: *mut $integer_type, n: $crate::libc::size_t,
+                                       values_pointer: *mut $crate::libc::c_void,
+                                       size: $crate::libc::size_t) -> $crate::ffi::SortStatus {
+                $crate::ffi::sort_with_payload_array(keys_pointer, n, values_pointer, size,
+                                                     |keys, values| {
+                    $sort_with_payload_by(keys, values, |a: &$integer_type, b| a.cmp(b))
+                })
+            }
+        )*
+        $(
+            #[no_mangle]
+            pub extern "C" fn $float(keys_pointer: *mut $float_type, n: $crate::libc::size_t,
+                                     values_pointer: *mut $crate::libc::c_void,
+                                     size: $crate::libc::size_t, policy: $crate::libc::c_int)
+                                     -> $crate::ffi::SortStatus {
+                $crate::ffi::sort_floats_with_payload_array(keys_pointer, n, values_pointer, size,
+                                                            policy, |keys, values, policy| {
+                    $crate::floats::check(keys, policy)?;
+                    $sort_with_payload_by(keys, values, |a, b| {
+                        $crate::floats::compare(a, b, policy)
+                    });
+                    Ok(())
+                })
+            }
+        )*
+    }
+}
+
 pub mod quick;
 pub mod merge;
 pub mod heap;
+pub mod intro;
+pub mod tim;
+pub mod radix;
+pub mod select;
+pub mod partial;
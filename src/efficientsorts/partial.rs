@@ -0,0 +1,201 @@
+//! Partial sort.
+//!
+//! The `partial` module sorts only the `k` smallest elements of a vector, or of an iterator,
+//! using a bounded heap.
+//!
+//! Source: https://en.wikipedia.org/wiki/Partial_sorting
+
+use std::cmp::Ordering;
+
+use efficientsorts::heap;
+use instrument::Unobserved;
+
+/// Partial sort
+///
+/// # Details
+///
+/// Move the `k` smallest elements of the vector to its first `k` positions, in sorted order. The
+/// other elements are left after them, in an unspecified order.
+///
+/// The first `k` elements are re-arranged into a max-heap (see the `heap` module), its root being
+/// the largest of the smallest elements found so far. Every following element smaller than the
+/// root takes its place and is sifted down the heap. The heap is finally sorted like in heap sort.
+///
+/// If `k` is larger than the vector's length, the whole vector is sorted.
+///
+/// # Scaling
+///
+/// The complexity is O(N log k): every element is compared to the root of the heap, of `k`
+/// elements, and only the smaller ones are sifted down. No memory is allocated.
+///
+/// # Notes
+///
+/// The type T of the vector elements _must_ implement the `PartialOrd` trait so the compiler knows
+/// how to compare them.
+///
+/// The implementation is not 'stable': like heap sort, building and sorting the heap change the
+/// relative order of items with equal values.
+///
+/// Elements are only swapped: a panicking comparison leaves the vector as a permutation of its
+/// initial elements.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::partial::partial_sort(&mut data, 2);
+/// assert_eq!(data[..2].to_vec(), vec![1, 2]);
+/// ```
+///
+pub fn partial_sort<T: PartialOrd>(array: &mut [T], k: usize) {
+    partial_sort_by(array, k, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Partial sort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `partial_sort()` but the ordering of the elements is given by the `compare`
+/// closure instead of the `PartialOrd` trait: a reversed comparator gives the `k` largest
+/// elements.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::partial::partial_sort_by(&mut data, 2, |a, b| b.cmp(a));
+/// assert_eq!(data[..2].to_vec(), vec![5, 4]);
+/// ```
+///
+pub fn partial_sort_by<T, F>(array: &mut [T], k: usize, mut compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    let n = array.len();
+    let k = k.min(n);
+    if k == 0 {
+        return;
+    }
+
+    heap::heapify(&mut array[..k], 0, &mut compare, &mut Unobserved);
+    for i in k..n {
+        if compare(&array[i], &array[0]) == Ordering::Less {
+            array.swap(0, i);
+            heap::sift_down(&mut array[..k], 0, 0, k - 1, &mut compare, &mut Unobserved);
+        }
+    }
+    heap::sort_heap(&mut array[..k], 0, &mut compare, &mut Unobserved);
+}
+
+/// Partial sort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::efficientsorts::partial::partial_sort_by_key(&mut data, 1, |&(_, age)| age);
+/// assert_eq!(data[0], ("a", 1));
+/// ```
+///
+pub fn partial_sort_by_key<T, K, F>(array: &mut [T], k: usize, mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    partial_sort_by(array, k, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Top-k extraction
+///
+/// # Details
+///
+/// Returns the `k` smallest elements yielded by `iter`, in sorted order (fewer if `iter` yields
+/// fewer elements).
+///
+/// Same algorithm as `partial_sort()`, the heap being a vector of at most `k` elements: the
+/// elements which are not among the smallest ones are dropped as soon as they are yielded, so a
+/// stream is never collected in memory.
+///
+/// # Scaling
+///
+/// The complexity is O(N log k) and the space complexity O(k).
+///
+/// # Examples
+///
+/// ```
+/// let smallest = sorting::efficientsorts::partial::top_k((0..1000).map(|x| (x * 7) % 1000), 3);
+/// assert_eq!(smallest, vec![0, 1, 2]);
+/// ```
+///
+pub fn top_k<I>(iter: I, k: usize) -> Vec<I::Item>
+    where I: IntoIterator,
+          I::Item: PartialOrd
+{
+    top_k_by(iter, k, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
+/// Top-k extraction, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `top_k()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait: a reversed comparator gives the `k` largest elements, in
+/// descending order.
+///
+/// # Examples
+///
+/// ```
+/// let scores = vec![12, 55, 3, 78, 41];
+/// let podium = sorting::efficientsorts::partial::top_k_by(scores, 3, |a, b| b.cmp(a));
+/// assert_eq!(podium, vec![78, 55, 41]);
+/// ```
+///
+pub fn top_k_by<I, F>(iter: I, k: usize, mut compare: F) -> Vec<I::Item>
+    where I: IntoIterator,
+          F: FnMut(&I::Item, &I::Item) -> Ordering
+{
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut iter = iter.into_iter();
+    let mut heap: Vec<I::Item> = iter.by_ref().take(k).collect();
+    if heap.len() < k {
+        // The iterator is exhausted: all its elements are kept.
+        heap::heap_sort(&mut heap, 0, &mut compare, &mut Unobserved);
+        return heap;
+    }
+
+    heap::heapify(&mut heap, 0, &mut compare, &mut Unobserved);
+    for element in iter {
+        if compare(&element, &heap[0]) == Ordering::Less {
+            heap[0] = element;
+            heap::sift_down(&mut heap, 0, 0, k - 1, &mut compare, &mut Unobserved);
+        }
+    }
+    heap::sort_heap(&mut heap, 0, &mut compare, &mut Unobserved);
+    heap
+}
+
+/// Top-k extraction, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let words = "the quick brown fox jumps over the lazy dog".split(' ');
+/// let shortest = sorting::efficientsorts::partial::top_k_by_key(words, 2, |word| word.len());
+/// assert!(shortest.iter().all(|word| word.len() == 3));
+/// ```
+///
+pub fn top_k_by_key<I, K, F>(iter: I, k: usize, mut key: F) -> Vec<I::Item>
+    where I: IntoIterator,
+          K: PartialOrd,
+          F: FnMut(&I::Item) -> K
+{
+    top_k_by(iter, k, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal))
+}
@@ -6,7 +6,13 @@
 
 extern crate libc;
 
-use std::slice;
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use permutation::{Payload, SwapPayload};
+use sorter::{Quick, Sorter};
 
 /// Quicksort
 ///
@@ -26,11 +32,21 @@ use std::slice;
 ///
 /// Use median as pivot.
 ///
+/// The pivot, at its final location once the vector is partitioned, is excluded from both
+/// recursive calls. Every call thus sorts fewer elements, even with a comparator which is not a
+/// total order.
+///
 /// # Notes
 ///
 /// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
 /// compiler knows how to compare the elements and sort them.
 ///
+/// The implementation is not 'stable': partitioning swaps elements across the vector, which
+/// changes the relative order of items with equal values.
+///
+/// Partitioning only swaps elements: if the comparison panics, the vector is left as a permutation
+/// of its initial elements.
+///
 /// # Examples
 ///
 /// ```
@@ -40,7 +56,209 @@ use std::slice;
 /// ```
 ///
 pub fn sort<T: PartialOrd>(array: &mut [T]) {
+    sort_by(array, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Quicksort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::quick::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(array: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(array, compare, &mut Unobserved);
+}
+
+/// Quicksort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison and swap, for the
+/// allocation of the pivot candidates, after every partitioning step and when entering and leaving
+/// every recursive call (see the `instrument` module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::efficientsorts::quick::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// // The pivot candidates are allocated at every partitioning step.
+/// assert_eq!(counts.allocations, 2);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(array: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    quick_sort(array, 0, &mut compare, observer);
+}
+
+/// Quicksort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::efficientsorts::quick::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(array: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(array, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Quicksort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::efficientsorts::quick::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(array: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(array, policy)?;
+    sort_by(array, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Quicksort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// The algorithm is not stable: the indices of equal elements end up in any order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::efficientsorts::quick::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(array: &[T]) -> Vec<usize> {
+    Quick.argsort(array)
+}
+
+/// Quicksort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::efficientsorts::quick::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(array: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Quick.argsort_by(array, compare)
+}
+
+/// Quicksort of keys, along with a payload.
+///
+/// # Details
+///
+/// Sort `keys` and apply the same permutation to `payload`: one or more companion slices, the
+/// element at index `i` of which is associated with key `i` (see `permutation::Payload`).
+///
+/// Every swap of two keys is mirrored on the payload (see `instrument::Observer`): no memory is
+/// allocated. If the comparison panics, the keys and the payload are left permuted alike.
+///
+/// # Panics
+///
+/// Panics if the payload's length differs from the number of keys.
+///
+/// # Examples
+///
+/// ```
+/// let mut keys: Vec<u32> = vec![3, 1, 2];
+/// let mut values: Vec<&str> = vec!["c", "a", "b"];
+/// sorting::efficientsorts::quick::sort_with_payload(&mut keys, &mut values);
+/// assert_eq!(keys, vec![1, 2, 3]);
+/// assert_eq!(values, vec!["a", "b", "c"]);
+/// ```
+///
+pub fn sort_with_payload<K, P>(keys: &mut [K], payload: &mut P)
+    where K: PartialOrd,
+          P: Payload + ?Sized
+{
+    sort_with_payload_by(keys, payload, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Quicksort of keys, along with a payload, using a comparator function.
+///
+/// # Panics
+///
+/// Panics if the payload's length differs from the number of keys.
+///
+/// # Examples
+///
+/// ```
+/// let mut keys: Vec<u32> = vec![3, 1, 2];
+/// let mut values: Vec<&str> = vec!["c", "a", "b"];
+/// sorting::efficientsorts::quick::sort_with_payload_by(&mut keys, &mut values, |a, b| b.cmp(a));
+/// assert_eq!(values, vec!["c", "b", "a"]);
+/// ```
+///
+pub fn sort_with_payload_by<K, P, F>(keys: &mut [K], payload: &mut P, compare: F)
+    where P: Payload + ?Sized,
+          F: FnMut(&K, &K) -> Ordering
+{
+    assert_eq!(keys.len(), payload.len(), "the payload and the keys differ in length");
+    sort_by_observed(keys, compare, &mut SwapPayload(payload));
+}
+
+// The comparator is taken by mutable reference so the recursion does not create a new closure
+// type (and thus a new instantiation of the function) at every level. "offset" is the index of
+// "array" in the vector seen by the observer.
+fn quick_sort<T, F, O>(array: &mut [T], offset: usize, compare: &mut F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
     let n = array.len();
+    observer.enter(offset, offset+n);
 
     // NOTE: Insertion could be used for "small" number of elements as an optimization. It is not
     //       used here as to show exactly how quicksort works.
@@ -48,157 +266,199 @@ pub fn sort<T: PartialOrd>(array: &mut [T]) {
     if n <= 1 {
         // Don't do anything
     } else if n == 2 {
-        if array.first() > array.last() {
+        observer.compare(offset, offset+1);
+        if compare(&array[0], &array[1]) == Ordering::Greater {
             array.swap(0, 1);
+            observer.swap(offset, offset+1);
         }
     } else {
-        // Choose the pivot element: Select the median between the first, middle and last element.
-        let mut pivot = {
-            let mut piv_choices: Vec<usize> = vec![0, n/2, n-1];
-            // Find maximum value between the first, middle and last element.
-            let mut max_val_i: usize = 0;
-            for i in 1..3 {
-                if array[piv_choices[max_val_i]] < array[piv_choices[i]] {
-                    max_val_i = i;
-                }
-            }
-            // Remove that index from the choice of pivot.
-            piv_choices.remove(max_val_i);
-            // The pivot will be the maximum of the remaining two (the median of initial
-            // piv_choices vector).
-            assert_eq!(piv_choices.len(), 2);
-            if array[piv_choices[0]] > array[piv_choices[1]] {
-                piv_choices[0]
-            } else {
-                piv_choices[1]
-            }
-        };
+        let pivot = partition(array, offset, compare, observer);
+
+        // Recursively call the function on slices of the vector. The pivot is at its final
+        // location: it is not part of any slice, so both are smaller than the vector even if
+        // the comparator is inconsistent.
+        quick_sort(&mut array[0..pivot], offset, compare, observer);
+        quick_sort(&mut array[pivot+1..n], offset+pivot+1, compare, observer);
+    }
 
-        // Place pivot at i=0 and loop over the remaining of the vector.
+    observer.exit(offset, offset+n);
+}
+
+/// Partition a vector of at least three elements around a median-of-three pivot.
+///
+/// On return, every element before the returned index is smaller than the pivot (now located at
+/// that index) and no element after it is smaller. The operations are reported to `observer`,
+/// `array` being located at index `offset`.
+pub(crate) fn partition<T, F, O>(array: &mut [T], offset: usize, compare: &mut F,
+                                 observer: &mut O) -> usize
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let pivot = median_of_three(array, offset, compare, observer);
+    partition_around(array, offset, pivot, compare, observer)
+}
+
+/// Index of the median of the first, middle and last elements of a vector of at least three
+/// elements.
+fn median_of_three<T, F, O>(array: &[T], offset: usize, compare: &mut F, observer: &mut O)
+                            -> usize
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = array.len();
+    debug_assert!(n > 2);
+
+    // Choose the pivot element: Select the median between the first, middle and last element.
+    let mut piv_choices: Vec<usize> = vec![0, n/2, n-1];
+    observer.allocate(piv_choices.len());
+    // Find maximum value between the first, middle and last element.
+    let mut max_val_i: usize = 0;
+    for i in 1..3 {
+        observer.compare(offset+piv_choices[max_val_i], offset+piv_choices[i]);
+        if compare(&array[piv_choices[max_val_i]], &array[piv_choices[i]]) == Ordering::Less {
+            max_val_i = i;
+        }
+    }
+    // Remove that index from the choice of pivot.
+    piv_choices.remove(max_val_i);
+    // The pivot will be the maximum of the remaining two (the median of initial
+    // piv_choices vector).
+    assert_eq!(piv_choices.len(), 2);
+    observer.compare(offset+piv_choices[0], offset+piv_choices[1]);
+    if compare(&array[piv_choices[0]], &array[piv_choices[1]]) == Ordering::Greater {
+        piv_choices[0]
+    } else {
+        piv_choices[1]
+    }
+}
+
+/// Partition a non-empty vector around the element at index `pivot`.
+///
+/// Same as `partition()`, the pivot being chosen by the caller.
+pub(crate) fn partition_around<T, F, O>(array: &mut [T], offset: usize, mut pivot: usize,
+                                        compare: &mut F, observer: &mut O) -> usize
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = array.len();
+    debug_assert!(pivot < n);
+
+    // Place pivot at i=0 and loop over the remaining of the vector.
+    if pivot != 0 {
         array.swap(pivot, 0);
-        pivot = 0;
-
-        for i in 1..n {
-            // The loop element is smaller than the pivot. Shuffle things around to place that
-            // element before the pivot.
-            if array[pivot] > array[i] {
-                // First, let's swap the element and the pivot.
-                array.swap(pivot, i);
-                // Two cases are possible here:
-                //     1) The element was the one next to the pivot in the vector. As such, the
-                //        new pivot's location is the index "i".
-                //     2) The element swaped was further down the vector. If we just swap the pivot
-                //        and that element, we are sending the pivot _after_ larger elements,
-                //        breaking the ordering! Additionally to the first swap, the pivot (now
-                //        being at index "i" after the first swap) is again swapped with the
-                //        element that was next to it before (index "pivot+1").
-                if i == pivot+1 {
-                    pivot = i;
-                } else {
-                    array.swap(i, pivot+1);
-                    pivot = pivot+1;
-                }
+        observer.swap(offset+pivot, offset);
+    }
+    pivot = 0;
+
+    for i in 1..n {
+        // The loop element is smaller than the pivot. Shuffle things around to place that
+        // element before the pivot.
+        observer.compare(offset+pivot, offset+i);
+        if compare(&array[pivot], &array[i]) == Ordering::Greater {
+            // First, let's swap the element and the pivot.
+            array.swap(pivot, i);
+            observer.swap(offset+pivot, offset+i);
+            // Two cases are possible here:
+            //     1) The element was the one next to the pivot in the vector. As such, the
+            //        new pivot's location is the index "i".
+            //     2) The element swaped was further down the vector. If we just swap the pivot
+            //        and that element, we are sending the pivot _after_ larger elements,
+            //        breaking the ordering! Additionally to the first swap, the pivot (now
+            //        being at index "i" after the first swap) is again swapped with the
+            //        element that was next to it before (index "pivot+1").
+            if i == pivot+1 {
+                pivot = i;
+            } else {
+                array.swap(i, pivot+1);
+                observer.swap(offset+i, offset+pivot+1);
+                pivot += 1;
             }
         }
-
-        // Recursively call the function on slices of the vector.
-        sort(&mut array[0..pivot+1]);
-        sort(&mut array[pivot+1..n]);
     }
+
+    observer.partition(offset, offset+pivot, offset+n);
+    pivot
 }
 
 
 
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_i8(array_pointer: *const libc::int8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_i16(array_pointer: *const libc::int16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_i32(array_pointer: *const libc::int32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_i64(array_pointer: *const libc::int64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_u8(array_pointer: *const libc::uint8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_u16(array_pointer: *const libc::uint16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_u32(array_pointer: *const libc::uint32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_u64(array_pointer: *const libc::uint64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_f32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                    policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+#[no_mangle]
+pub extern "C" fn ffi_quicksort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                    policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
 }
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
 #[no_mangle]
-pub extern "C" fn ffi_quicksort_f64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_quicksort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                        size: libc::size_t, compar: Option<ffi::Comparator>,
+                                        ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Quick, base, nmemb, size, compar, ctx)
+}
+
+payload_exports! {
+    sort_with_payload_by;
+    integers {
+        ffi_quicksort_payload_i8: i8,
+        ffi_quicksort_payload_i16: i16,
+        ffi_quicksort_payload_i32: i32,
+        ffi_quicksort_payload_i64: i64,
+        ffi_quicksort_payload_u8: u8,
+        ffi_quicksort_payload_u16: u16,
+        ffi_quicksort_payload_u32: u32,
+        ffi_quicksort_payload_u64: u64,
+    }
+    floats {
+        ffi_quicksort_payload_f32: f32,
+        ffi_quicksort_payload_f64: f64,
+    }
 }
@@ -0,0 +1,361 @@
+//! Radix sort algorithm.
+//!
+//! The `efficient` module contains the efficient sorting algorithm "Radix sort", for integers.
+//!
+//! Source: https://en.wikipedia.org/wiki/Radix_sort
+
+extern crate libc;
+
+use std::cmp::Ordering;
+
+use ffi;
+use instrument::{Observer, Unobserved};
+use simplesorts::insertion;
+
+/// Number of possible values of a digit (one byte).
+const RADIX: usize = 256;
+
+/// Buckets of at most this number of elements are sorted using insertion sort in `sort_msd()`.
+const INSERTION_THRESHOLD: usize = 32;
+
+/// Integer types that can be sorted by radix sort.
+///
+/// Radix sort does not compare elements: it looks at their digits. Here a digit is one byte of the
+/// `key()`, an unsigned integer ordered the same way as the values.
+///
+/// For unsigned types, the key is the value itself. For signed types (two's complement), the sign
+/// bit is flipped: negative values then become smaller unsigned integers than positive ones
+/// (`i8::MIN` maps to 0, -1 to 127, 0 to 128 and `i8::MAX` to 255).
+pub trait RadixKey: Copy + PartialOrd {
+    /// Number of bytes (digits) of the key.
+    const BYTES: usize;
+
+    /// Unsigned key with the same ordering as the value.
+    fn key(&self) -> u64;
+
+    /// Byte (digit) `i` of the key, 0 being the least significant.
+    fn digit(&self, i: usize) -> usize {
+        ((self.key() >> (8 * i)) & 0xff) as usize
+    }
+}
+
+macro_rules! impl_radix_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = ::std::mem::size_of::<$t>();
+            fn key(&self) -> u64 {
+                *self as u64
+            }
+        }
+    )*}
+}
+
+macro_rules! impl_radix_key_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = ::std::mem::size_of::<$t>();
+            fn key(&self) -> u64 {
+                // Flip the sign bit.
+                ((*self as $u) ^ (1 << (8 * Self::BYTES - 1))) as u64
+            }
+        }
+    )*}
+}
+
+impl_radix_key_unsigned!(u8, u16, u32, u64, usize);
+impl_radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize);
+
+/// Radix sort (LSD)
+///
+/// # Details
+///
+/// Least significant digit (LSD) radix sort sorts the integers one byte at a time, starting with
+/// the least significant one. Every pass is a counting sort: the number of elements having each
+/// possible byte value is counted, which gives where each group of elements starts in the
+/// output. The elements are then moved, in order, to their group.
+///
+/// As each pass is stable, after the last pass (the most significant byte) the elements are
+/// sorted by their whole value.
+///
+/// # Scaling
+///
+/// Radix sort does not compare elements and is thus not bound by the O(N log N) limit of
+/// comparison sorts. Every pass is O(N) and the number of passes is the number of bytes of the
+/// type (at most 8), hence the O(N) complexity, whatever the data.
+///
+/// A temporary vector of the same size as the input is needed to move the elements into during
+/// each pass, hence the space complexity is O(N).
+///
+/// # Optimizations
+///
+/// A pass is skipped if all the elements have the same value for that byte.
+///
+/// # Notes
+///
+/// The type T of the vector elements to sort _must_ implement the `RadixKey` trait, which is
+/// implemented for all the primitive integer types.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, -2, 3, 1, 5];
+/// sorting::efficientsorts::radix::sort(&mut data);
+/// assert_eq!(vec![-2, 1, 3, 4, 5], data);
+/// ```
+///
+pub fn sort<T: RadixKey>(input: &mut [T]) {
+    sort_observed(input, &mut Unobserved);
+}
+
+/// Radix sort (LSD), reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort()`, calling `observer` for the temporary vector and the elements moved
+/// (see the `instrument` module). Radix sort does not compare elements: a pass moving the
+/// elements to the temporary vector is reported as moving the whole vector to a buffer, and a
+/// pass moving them back as a write of every element.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<u8> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::efficientsorts::radix::sort_observed(&mut data, &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!((counts.comparisons, counts.moves, counts.allocations), (0, 10, 1));
+/// ```
+///
+pub fn sort_observed<T: RadixKey, O: Observer<T>>(input: &mut [T], observer: &mut O) {
+    let n = input.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut buffer: Vec<T> = input.to_vec();
+    observer.allocate(n);
+    // Elements are moved back and forth between "input" and "buffer". Keep track of where the
+    // latest pass left them.
+    let mut in_buffer = false;
+
+    for digit in 0..T::BYTES {
+        let (from, to) = if in_buffer {
+            (&mut buffer[..], &mut input[..])
+        } else {
+            (&mut input[..], &mut buffer[..])
+        };
+
+        // Count the number of elements for each value of the digit.
+        let mut counts = [0usize; RADIX];
+        for x in from.iter() {
+            counts[x.digit(digit)] += 1;
+        }
+
+        // Optimization: all elements are in the same bucket, the pass would not change anything.
+        if counts.contains(&n) {
+            continue;
+        }
+
+        // Convert the counts into the starting offset of each bucket.
+        let mut offsets = [0usize; RADIX];
+        for d in 1..RADIX {
+            offsets[d] = offsets[d-1] + counts[d-1];
+        }
+
+        // Move every element to the next free location of its bucket, in order (stable).
+        for x in from.iter() {
+            let d = x.digit(digit);
+            to[offsets[d]] = *x;
+            if in_buffer {
+                observer.write(offsets[d], x);
+            }
+            offsets[d] += 1;
+        }
+        if !in_buffer {
+            observer.move_to_buffer(0, n);
+        }
+
+        in_buffer = !in_buffer;
+    }
+
+    if in_buffer {
+        input.copy_from_slice(&buffer);
+        for (i, x) in input.iter().enumerate() {
+            observer.write(i, x);
+        }
+    }
+}
+
+/// Radix sort (MSD, in-place)
+///
+/// # Details
+///
+/// Most significant digit (MSD) radix sort starts with the most significant byte. The elements
+/// are distributed into 256 buckets according to that byte, then every bucket is sorted
+/// recursively on the next byte.
+///
+/// This implementation is the "American flag sort" variant: the distribution is done in place.
+/// After counting the number of elements for each byte value, the elements are swapped directly
+/// into their bucket, following the permutation cycles.
+///
+/// # Scaling
+///
+/// Every level of the recursion is O(N) and there are at most as many levels as bytes in the type
+/// (at most 8), hence the O(N) complexity. Contrary to `sort()`, no temporary vector is needed
+/// and the space complexity is O(1) (the recursion depth and the counts per level are bounded).
+///
+/// # Optimizations
+///
+/// Buckets of at most 32 elements are sorted using insertion sort.
+///
+/// # Notes
+///
+/// The type T of the vector elements to sort _must_ implement the `RadixKey` trait, which is
+/// implemented for all the primitive integer types.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, -2, 3, 1, 5];
+/// sorting::efficientsorts::radix::sort_msd(&mut data);
+/// assert_eq!(vec![-2, 1, 3, 4, 5], data);
+/// ```
+///
+pub fn sort_msd<T: RadixKey>(input: &mut [T]) {
+    sort_msd_observed(input, &mut Unobserved);
+}
+
+/// Radix sort (MSD, in-place), reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_msd()`, calling `observer` for every swap and recursive call, and for
+/// the comparisons and swaps of the insertion sort of the small buckets (see the `instrument`
+/// module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<u16> = (0..100).rev().collect();
+/// let mut counts = Counts::default();
+/// sorting::efficientsorts::radix::sort_msd_observed(&mut data, &mut counts);
+/// assert_eq!((0..100).collect::<Vec<u16>>(), data);
+/// assert_eq!(counts.allocations, 0);
+/// ```
+///
+pub fn sort_msd_observed<T: RadixKey, O: Observer<T>>(input: &mut [T], observer: &mut O) {
+    if input.len() > 1 {
+        american_flag_sort(input, 0, T::BYTES - 1, observer);
+    }
+}
+
+/// American flag sort of `input` on `digit` and the less significant ones, `input` being located
+/// at index `offset` of the vector seen by `observer`.
+fn american_flag_sort<T, O>(input: &mut [T], offset: usize, digit: usize, observer: &mut O)
+    where T: RadixKey,
+          O: Observer<T>
+{
+    let n = input.len();
+
+    observer.enter(offset, offset+n);
+    if n <= INSERTION_THRESHOLD {
+        insertion::insertion_sort(input, offset,
+                                  &mut |a: &T, b: &T| a.partial_cmp(b).unwrap_or(Ordering::Equal),
+                                  observer);
+        observer.exit(offset, offset+n);
+        return;
+    }
+
+    // Count the number of elements for each value of the digit.
+    let mut counts = [0usize; RADIX];
+    for x in input.iter() {
+        counts[x.digit(digit)] += 1;
+    }
+
+    // Starting ("heads") and ending ("tails") offsets of each bucket.
+    let mut heads = [0usize; RADIX];
+    let mut tails = [0usize; RADIX];
+    let mut end = 0;
+    for d in 0..RADIX {
+        heads[d] = end;
+        end += counts[d];
+        tails[d] = end;
+    }
+
+    // Place every element in its bucket. The element at the head of bucket "d" is swapped into
+    // its own bucket until an element belonging to "d" comes back; "heads[d]" then advances.
+    for d in 0..RADIX {
+        while heads[d] < tails[d] {
+            let x_digit = input[heads[d]].digit(digit);
+            if x_digit == d {
+                heads[d] += 1;
+            } else {
+                input.swap(heads[d], heads[x_digit]);
+                observer.swap(offset+heads[d], offset+heads[x_digit]);
+                heads[x_digit] += 1;
+            }
+        }
+    }
+
+    // Recursively sort every bucket on the next digit.
+    if digit > 0 {
+        let mut start = 0;
+        for &end in tails.iter() {
+            if end - start > 1 {
+                american_flag_sort(&mut input[start..end], offset+start, digit - 1, observer);
+            }
+            start = end;
+        }
+    }
+    observer.exit(offset, offset+n);
+}
+
+
+
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+
+
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                   -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_radixsort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
@@ -0,0 +1,385 @@
+//! Selection algorithm "Introselect".
+//!
+//! The `select` module finds the element which would be at a given index once the vector is
+//! sorted, without sorting it, and uses it to compute medians and percentiles.
+//!
+//! Source: https://en.wikipedia.org/wiki/Introselect
+
+extern crate libc;
+
+use std::cmp::Ordering;
+use std::f64;
+
+use floats::{self, Float, NanPolicy};
+use ffi;
+use instrument::Unobserved;
+use efficientsorts::quick;
+
+/// Number of partitioning steps using a median-of-three pivot before falling back to the median
+/// of medians.
+const MEDIAN_OF_THREE_STEPS: usize = 16;
+
+/// Size of the groups whose medians are gathered by the median of medians.
+const GROUP_SIZE: usize = 5;
+
+/// Introselect
+///
+/// # Details
+///
+/// Re-arrange the vector so that the element at index `k` is the one which would be there if the
+/// vector was sorted: no element before it is larger and no element after it is smaller. Returns
+/// a reference to that element.
+///
+/// Like quicksort, the vector is partitioned around a median-of-three pivot (see the `quick`
+/// module), but only the part containing index `k` is partitioned again ("quickselect"). Elements
+/// equal to the pivot are gathered next to it, so that vectors with many equal values are not
+/// partitioned one element at a time.
+///
+/// # Scaling
+///
+/// The average case complexity is O(N). A median-of-three pivot can still be one of the smallest
+/// (or largest) elements at every step, making quickselect O(N^2). After 16 partitioning steps,
+/// the pivot is thus chosen as the median of the medians of groups of five elements instead
+/// ("introselect"), which guarantees that at least 30% of the elements are discarded at every
+/// step: the worst case complexity is O(N).
+///
+/// # Notes
+///
+/// The type T of the vector elements _must_ implement the `PartialOrd` trait so the compiler knows
+/// how to compare them.
+///
+/// The implementation is not 'stable': the elements only need to be partially ordered, and equal
+/// items end up in any order.
+///
+/// Partitioning only swaps elements: if the comparison panics, the vector is left as a permutation
+/// of its initial elements.
+///
+/// # Panics
+///
+/// Panics if `k` is not smaller than the vector's length.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(*sorting::efficientsorts::select::select_nth_unstable(&mut data, 1), 2);
+/// assert!(data[..1].iter().all(|&x| x <= 2) && data[2..].iter().all(|&x| x >= 2));
+/// ```
+///
+pub fn select_nth_unstable<T: PartialOrd>(array: &mut [T], k: usize) -> &mut T {
+    select_nth_unstable_by(array, k, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
+/// Introselect, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `select_nth_unstable()` but the ordering of the elements is given by the
+/// `compare` closure instead of the `PartialOrd` trait.
+///
+/// # Panics
+///
+/// Panics if `k` is not smaller than the vector's length.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let second_largest = sorting::efficientsorts::select::select_nth_unstable_by(&mut data, 1,
+///                                                                           |a, b| b.cmp(a));
+/// assert_eq!(*second_largest, 4);
+/// ```
+///
+pub fn select_nth_unstable_by<T, F>(array: &mut [T], k: usize, mut compare: F) -> &mut T
+    where F: FnMut(&T, &T) -> Ordering
+{
+    let n = array.len();
+    assert!(k < n, "index {} is out of range for a vector of length {}", k, n);
+    intro_select(array, k, &mut compare);
+    &mut array[k]
+}
+
+/// Introselect, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Panics
+///
+/// Panics if `k` is not smaller than the vector's length.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// let youngest = sorting::efficientsorts::select::select_nth_unstable_by_key(&mut data, 0,
+///                                                                            |&(_, age)| age);
+/// assert_eq!(*youngest, ("a", 1));
+/// ```
+///
+pub fn select_nth_unstable_by_key<T, K, F>(array: &mut [T], k: usize, mut key: F) -> &mut T
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    select_nth_unstable_by(array, k, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal))
+}
+
+/// Median of floating point values.
+///
+/// # Details
+///
+/// Same as `percentile(array, 50.0)`: the middle value, or the mean of the two middle values if
+/// the vector has an even length.
+///
+/// # Notes
+///
+/// The vector is re-arranged, as by `select_nth_unstable()`.
+///
+/// Returns NaN if the vector is empty or contains a NaN, as NumPy does.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<f64> = vec![4.0, 2.0, 3.0, 1.0];
+/// assert_eq!(sorting::efficientsorts::select::median(&mut data), 2.5);
+/// ```
+///
+pub fn median<T: Float + Into<f64>>(array: &mut [T]) -> f64 {
+    percentile(array, 50.0)
+}
+
+/// Percentile of floating point values.
+///
+/// # Details
+///
+/// Returns the value below which `p` percent of the values are found. When it falls between two
+/// values, it is linearly interpolated: the percentile of `N` values is located at index
+/// `p / 100 * (N - 1)` of the sorted vector, the default method of NumPy's `percentile()`.
+///
+/// The value at the integer part of that index is found using `select_nth_unstable()`; the next
+/// one, needed for the interpolation, is then the smallest of the elements after it.
+///
+/// # Notes
+///
+/// The vector is re-arranged, as by `select_nth_unstable()`.
+///
+/// Returns NaN if the vector is empty or contains a NaN, as NumPy does.
+///
+/// # Panics
+///
+/// Panics if `p` is not between 0 and 100.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<f64> = vec![5.0, 1.0, 4.0, 2.0, 3.0];
+/// assert_eq!(sorting::efficientsorts::select::percentile(&mut data, 25.0), 2.0);
+/// assert_eq!(sorting::efficientsorts::select::percentile(&mut data, 62.5), 3.5);
+/// ```
+///
+pub fn percentile<T: Float + Into<f64>>(array: &mut [T], p: f64) -> f64 {
+    assert!((0.0..=100.0).contains(&p), "percentile {} is not between 0 and 100", p);
+    if array.is_empty() || array.iter().any(|x| x.is_nan()) {
+        return f64::NAN;
+    }
+
+    let index = p / 100.0 * (array.len() - 1) as f64;
+    let k = index.floor() as usize;
+    let fraction = index - k as f64;
+
+    let below: f64 = (*select_nth_unstable_by(array, k, |a, b| {
+        floats::compare(a, b, NanPolicy::TotalOrder)
+    })).into();
+    if fraction == 0.0 {
+        return below;
+    }
+    // The elements after index k are not smaller: the next value is the smallest of them.
+    let above: f64 = array[k+1..].iter().cloned()
+                                 .min_by(|a, b| floats::compare(a, b, NanPolicy::TotalOrder))
+                                 .unwrap().into();
+    below + (above - below) * fraction
+}
+
+// The comparator is taken by mutable reference so the median of medians, which selects among
+// the medians recursively, does not create a new closure type at every level.
+fn intro_select<T, F>(array: &mut [T], k: usize, compare: &mut F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    // Only the range [start, end) of the vector, which contains index k, is left to partition.
+    let mut start = 0;
+    let mut end = array.len();
+    let mut steps = 0;
+
+    while end - start > 2 {
+        let part = &mut array[start..end];
+        let pivot = if steps < MEDIAN_OF_THREE_STEPS {
+            quick::partition(part, start, compare, &mut Unobserved)
+        } else {
+            let pivot = median_of_medians(part, compare);
+            quick::partition_around(part, start, pivot, compare, &mut Unobserved)
+        };
+        steps += 1;
+
+        let k = k - start;
+        if k < pivot {
+            end = start + pivot;
+            continue;
+        }
+
+        // Gather the elements equal to the pivot right after it: none of them has to be
+        // partitioned again.
+        let mut equal = pivot + 1;
+        for i in pivot+1..part.len() {
+            if compare(&part[pivot], &part[i]) != Ordering::Less {
+                part.swap(equal, i);
+                equal += 1;
+            }
+        }
+        if k < equal {
+            return;
+        }
+        start += equal;
+    }
+
+    if end - start == 2 && compare(&array[start], &array[start+1]) == Ordering::Greater {
+        array.swap(start, start+1);
+    }
+}
+
+/// Index of the median of the medians of groups of five elements, in a vector of at least three
+/// elements.
+///
+/// The medians are moved to the beginning of the vector and their median is selected there.
+fn median_of_medians<T, F>(array: &mut [T], compare: &mut F) -> usize
+    where F: FnMut(&T, &T) -> Ordering
+{
+    let groups = array.len() / GROUP_SIZE;
+    if groups == 0 {
+        // Too few elements to form a group: take the median of all of them.
+        let middle = array.len() / 2;
+        intro_select(array, middle, compare);
+        return middle;
+    }
+
+    for group in 0..groups {
+        let start = group * GROUP_SIZE;
+        // Sort the group using an insertion sort, its median then being in the middle.
+        for i in start+1..start+GROUP_SIZE {
+            let mut j = i;
+            while j > start && compare(&array[j-1], &array[j]) == Ordering::Greater {
+                array.swap(j-1, j);
+                j -= 1;
+            }
+        }
+        array.swap(group, start + GROUP_SIZE/2);
+    }
+
+    intro_select(&mut array[..groups], groups / 2, compare);
+    groups / 2
+}
+
+
+/// Export the selection functions, `ffi_select_nth_<type>()` (see the `ffi` module), using
+/// `$select_nth_unstable_by`.
+///
+/// Every export is given by its name and the type of the values. The exports of floating point
+/// values take a NaN `policy` (see `ffi::select_floats_array()`). build.rs declares the exports
+/// listed by the invocation in the C header.
+macro_rules! select_exports {
+    ($select_nth_unstable_by:path;
+     integers { $($integer:ident: $integer_type:ty),* $(,)? }
+     floats { $($float:ident: $float_type:ty),* $(,)? }) => {
+        $(
+            #[no_mangle]
+            pub extern "C" fn $integer(array_pointer: *mut $integer_type, n: $crate::libc::size_t,
+                                       k: $crate::libc::size_t) -> $crate::ffi::SortStatus {
+                $crate::ffi::select_array(array_pointer, n, k, |to_select, k| {
+                    $select_nth_unstable_by(to_select, k, |a: &$integer_type, b| a.cmp(b));
+                })
+            }
+        )*
+        $(
+            #[no_mangle]
+            pub extern "C" fn $float(array_pointer: *mut $float_type, n: $crate::libc::size_t,
+                                     k: $crate::libc::size_t, policy: $crate::libc::c_int)
+                                     -> $crate::ffi::SortStatus {
+                $crate::ffi::select_floats_array(array_pointer, n, k, policy,
+                                                 |to_select, k, policy| {
+                    $crate::floats::check(to_select, policy)?;
+                    $select_nth_unstable_by(to_select, k, |a, b| {
+                        $crate::floats::compare(a, b, policy)
+                    });
+                    Ok(())
+                })
+            }
+        )*
+    }
+}
+
+select_exports! {
+    select_nth_unstable_by;
+    integers {
+        ffi_select_nth_i8: i8,
+        ffi_select_nth_i16: i16,
+        ffi_select_nth_i32: i32,
+        ffi_select_nth_i64: i64,
+        ffi_select_nth_u8: u8,
+        ffi_select_nth_u16: u16,
+        ffi_select_nth_u32: u32,
+        ffi_select_nth_u64: u64,
+    }
+    floats {
+        ffi_select_nth_f32: f32,
+        ffi_select_nth_f64: f64,
+    }
+}
+
+/// Compute the median of the `n` values at `array_pointer`, re-arranging them, and write it to
+/// `result` (NaN if `n` is zero or a value is NaN).
+#[no_mangle]
+pub extern "C" fn ffi_median_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                 result: *mut libc::c_double) -> ffi::SortStatus {
+    ffi_percentile(array_pointer, n, 50.0, result)
+}
+/// Compute the median of the `n` values at `array_pointer`, re-arranging them, and write it to
+/// `result` (NaN if `n` is zero or a value is NaN).
+#[no_mangle]
+pub extern "C" fn ffi_median_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                 result: *mut libc::c_double) -> ffi::SortStatus {
+    ffi_percentile(array_pointer, n, 50.0, result)
+}
+
+/// Compute the `p`-th percentile (`p` between 0 and 100) of the `n` values at `array_pointer`,
+/// re-arranging them, and write it to `result` (NaN if `n` is zero or a value is NaN).
+#[no_mangle]
+pub extern "C" fn ffi_percentile_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                     p: libc::c_double, result: *mut libc::c_double)
+                                     -> ffi::SortStatus {
+    ffi_percentile(array_pointer, n, p, result)
+}
+/// Compute the `p`-th percentile (`p` between 0 and 100) of the `n` values at `array_pointer`,
+/// re-arranging them, and write it to `result` (NaN if `n` is zero or a value is NaN).
+#[no_mangle]
+pub extern "C" fn ffi_percentile_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                     p: libc::c_double, result: *mut libc::c_double)
+                                     -> ffi::SortStatus {
+    ffi_percentile(array_pointer, n, p, result)
+}
+
+/// Validate the arguments of the percentile exports and compute the percentile.
+fn ffi_percentile<T: Float + Into<f64>>(array_pointer: *mut T, n: libc::size_t, p: f64,
+                                        result: *mut libc::c_double) -> ffi::SortStatus {
+    if result.is_null() {
+        return ffi::SortStatus::NullPointer;
+    }
+    if !(0.0..=100.0).contains(&p) {
+        return ffi::SortStatus::InvalidArgument;
+    }
+    let mut value = f64::NAN;
+    let status = ffi::sort_array(array_pointer, n, |array| value = percentile(array, p));
+    if status == ffi::SortStatus::Ok {
+        unsafe { *result = value };
+    }
+    status
+}
@@ -0,0 +1,856 @@
+//! Timsort algorithm.
+//!
+//! The `efficient` module contains the efficient sorting algorithm "Timsort".
+//!
+//! Source: https://en.wikipedia.org/wiki/Timsort
+
+extern crate libc;
+
+use std::slice;
+use std::ptr;
+use std::cmp;
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use sorter::{Tim, Sorter};
+
+/// Vectors shorter than this are sorted using a single binary insertion sort.
+const MIN_MERGE: usize = 64;
+
+/// Initial number of consecutive wins of one run before switching to galloping mode.
+const MIN_GALLOP: usize = 7;
+
+/// Timsort
+///
+/// # Details
+///
+/// Timsort is a natural merge sort: instead of blindly splitting the vector in two like `merge`,
+/// it looks for the runs already present in the data and merges them.
+///
+/// The vector is scanned from left to right. At every position the longest run starting there is
+/// found: either non-descending, or strictly descending in which case it is reversed in place
+/// (strictly, so equal elements are never reordered). Runs shorter than a minimum length
+/// ("minrun", between 32 and 64) are extended using binary insertion sort.
+///
+/// Every run found is pushed on a stack. To keep the merges balanced, the lengths of the three
+/// runs A, B and C on the top of the stack must satisfy A > B + C and B > C; runs are merged
+/// until these invariants hold again. Once the whole vector was scanned, the remaining runs are
+/// merged together.
+///
+/// When merging, if one run "wins" many times in a row, the merge switches to "galloping mode":
+/// an exponential search finds how many elements of that run can be moved at once.
+///
+/// # Scaling
+///
+/// Timsort is O(N log N) in the worst case and O(N) when the vector is already sorted (or
+/// reverse sorted): a single run is found and nothing needs to be merged. More generally, it
+/// adapts to the number of runs present in the data, making it very efficient on partially
+/// sorted vectors like appended logs or concatenated sorted batches.
+///
+/// Like `merge`, a temporary buffer of at most half the vector's size is needed, hence the space
+/// complexity is O(N).
+///
+/// # Optimizations
+///
+/// Before merging two runs, the elements of the first run smaller than the start of the second
+/// and the elements of the second run larger than the end of the first are skipped as they are
+/// already in place. The smallest of the two runs is then moved to the temporary buffer.
+///
+/// # Notes
+///
+/// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
+/// compiler knows how to compare the elements and sort them.
+///
+/// The implementation is 'stable' as it does preserve the relative order of items with
+/// equal values.
+///
+/// If the comparison panics, the elements still in the temporary buffer are moved back into the
+/// vector before unwinding: the vector is left as a permutation of its initial elements and every
+/// element is dropped exactly once.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::tim::sort(&mut data);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// ```
+///
+pub fn sort<T: PartialOrd>(array: &mut [T]) {
+    sort_by(array, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Timsort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::efficientsorts::tim::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(array: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(array, compare, &mut Unobserved);
+}
+
+/// Timsort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison, for the allocation of
+/// the temporary buffer, for every element moved to or from it, for every element moved by the
+/// binary insertion sort and for every swap reversing a descending run (see the `instrument`
+/// module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// // A single descending run, reversed in place.
+/// let mut data: Vec<i32> = vec![5, 4, 3, 2, 1];
+/// let mut counts = Counts::default();
+/// sorting::efficientsorts::tim::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(counts.swaps, 2);
+/// assert_eq!(counts.moves, 0);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(array: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = array.len();
+
+    if n < 2 {
+        return;
+    }
+
+    if n < MIN_MERGE {
+        // Too small to bother with merging: extend the first run to the whole vector.
+        let run_length = count_run(array, 0, &mut compare, observer);
+        binary_insertion_sort(array, 0, run_length, &mut compare, observer);
+        return;
+    }
+
+    let min_run = min_run_length(n);
+
+    // The temporary buffer never holds more than the smallest of the two runs being merged.
+    // Its length stays zero so dropping it never drops the elements moved through it.
+    let mut buffer: Vec<T> = Vec::with_capacity(n / 2);
+    observer.allocate(n / 2);
+    let mut state = MergeState {
+        runs: Vec::new(),
+        buffer: buffer.as_mut_ptr(),
+        min_gallop: MIN_GALLOP,
+    };
+
+    let mut start = 0;
+    while start < n {
+        // Find the next run, extending it to "min_run" elements if it is too short.
+        let mut run_length = count_run(&mut array[start..], start, &mut compare, observer);
+        if run_length < min_run {
+            let forced = cmp::min(min_run, n - start);
+            binary_insertion_sort(&mut array[start..start+forced], start, run_length, &mut compare,
+                                  observer);
+            run_length = forced;
+        }
+
+        state.runs.push(Run { start, length: run_length });
+        start += run_length;
+
+        state.merge_collapse(array, &mut compare, observer);
+    }
+
+    state.merge_force_collapse(array, &mut compare, observer);
+    debug_assert_eq!(state.runs.len(), 1);
+}
+
+/// Timsort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::efficientsorts::tim::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(array: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(array, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Timsort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::efficientsorts::tim::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(array: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(array, policy)?;
+    sort_by(array, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Timsort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// As the algorithm is stable, the indices of equal elements stay in increasing order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::efficientsorts::tim::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(array: &[T]) -> Vec<usize> {
+    Tim.argsort(array)
+}
+
+/// Timsort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::efficientsorts::tim::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(array: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Tim.argsort_by(array, compare)
+}
+
+/// Minimum run length for a vector of `n` elements.
+///
+/// Take the six most significant bits of `n`, adding one if any of the remaining bits is set.
+/// The result is between `MIN_MERGE / 2` and `MIN_MERGE` and `n / min_run` is a power of two (or
+/// slightly less), which keeps the final merges balanced.
+fn min_run_length(n: usize) -> usize {
+    let mut n = n;
+    let mut r = 0;
+    while n >= MIN_MERGE {
+        r |= n & 1;
+        n >>= 1;
+    }
+    n + r
+}
+
+/// Length of the run starting at the beginning of `array`.
+///
+/// A strictly descending run is reversed in place so the returned run is always sorted. `array`
+/// is located at index `offset` of the vector seen by `observer`.
+fn count_run<T, F, O>(array: &mut [T], offset: usize, compare: &mut F, observer: &mut O) -> usize
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    let n = array.len();
+    if n < 2 {
+        return n;
+    }
+
+    let mut end = 2;
+    observer.compare(offset+1, offset);
+    if compare(&array[1], &array[0]) == Ordering::Less {
+        // Strictly descending: equal elements would end the run so reversing it keeps the
+        // sort stable.
+        while end < n {
+            observer.compare(offset+end, offset+end-1);
+            if compare(&array[end], &array[end-1]) != Ordering::Less {
+                break;
+            }
+            end += 1;
+        }
+        for i in 0..end/2 {
+            array.swap(i, end-1-i);
+            observer.swap(offset+i, offset+end-1-i);
+        }
+    } else {
+        while end < n {
+            observer.compare(offset+end, offset+end-1);
+            if compare(&array[end], &array[end-1]) == Ordering::Less {
+                break;
+            }
+            end += 1;
+        }
+    }
+
+    end
+}
+
+/// Sort `array` knowing its first `sorted` elements are already sorted.
+///
+/// Every following element is inserted in the sorted part at a location found using binary
+/// search (after any equal element, for stability). `array` is located at index `offset` of the
+/// vector seen by `observer`.
+fn binary_insertion_sort<T, F, O>(array: &mut [T], offset: usize, sorted: usize, compare: &mut F,
+                                  observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    for i in cmp::max(sorted, 1)..array.len() {
+        let mut lo = 0;
+        let mut hi = i;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            observer.compare(offset+i, offset+mid);
+            if compare(&array[i], &array[mid]) == Ordering::Less {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        if lo < i {
+            array[lo..i+1].rotate_right(1);
+            for (k, value) in array[lo..i+1].iter().enumerate() {
+                observer.write(offset+lo+k, value);
+            }
+        }
+    }
+}
+
+/// Number of leading elements of `run` for which `is_before` is true.
+///
+/// `is_before` must be true for a prefix of `run` and false after. The search starts at `hint`
+/// and looks at offsets 1, 3, 7, 15... away from it ("galloping") before doing a binary search in
+/// the last interval found. Finding `k` elements this way takes O(log k) comparisons instead of
+/// the O(k) of a linear scan. `is_before` is given the index in `run` of the element it tests.
+fn gallop<T, P>(run: &[T], hint: usize, mut is_before: P) -> usize
+    where P: FnMut(usize, &T) -> bool
+{
+    let n = run.len();
+    if n == 0 {
+        return 0;
+    }
+    debug_assert!(hint < n);
+
+    let (mut lo, mut hi);
+    if is_before(hint, &run[hint]) {
+        // Gallop to the right until an element is not before anymore.
+        let mut last = hint;
+        let mut offset = 1;
+        while hint + offset < n && is_before(hint + offset, &run[hint + offset]) {
+            last = hint + offset;
+            offset = offset * 2 + 1;
+        }
+        lo = last + 1;
+        hi = cmp::min(hint + offset, n);
+    } else {
+        // Gallop to the left until an element is before.
+        let mut last = hint;
+        let mut offset = 1;
+        while offset <= hint && !is_before(hint - offset, &run[hint - offset]) {
+            last = hint - offset;
+            offset = offset * 2 + 1;
+        }
+        lo = if offset <= hint { hint - offset + 1 } else { 0 };
+        hi = last;
+    }
+
+    // Binary search in the interval found.
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if is_before(mid, &run[mid]) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo
+}
+
+/// Number of elements of `run` strictly smaller than `key`.
+///
+/// `key` and `run` are located at indices `key_index` and `run_index` of the vector seen by
+/// `observer` (or were moved to the temporary buffer from there).
+fn gallop_left<T, F, O>(key: &T, key_index: usize, run: &[T], run_index: usize, hint: usize,
+                        compare: &mut F, observer: &mut O) -> usize
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    gallop(run, hint, |i, x| {
+        observer.compare(run_index + i, key_index);
+        compare(x, key) == Ordering::Less
+    })
+}
+
+/// Number of elements of `run` smaller than or equal to `key` (see `gallop_left()`).
+fn gallop_right<T, F, O>(key: &T, key_index: usize, run: &[T], run_index: usize, hint: usize,
+                         compare: &mut F, observer: &mut O) -> usize
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    gallop(run, hint, |i, x| {
+        observer.compare(key_index, run_index + i);
+        compare(key, x) != Ordering::Less
+    })
+}
+
+/// A sorted run: `array[start..start+length]`.
+#[derive(Clone, Copy)]
+struct Run {
+    start: usize,
+    length: usize,
+}
+
+struct MergeState<T> {
+    runs: Vec<Run>,
+    buffer: *mut T,
+    min_gallop: usize,
+}
+
+impl<T> MergeState<T> {
+    /// Merge runs on the top of the stack until the invariants hold again.
+    ///
+    /// The invariants are checked on the four top-most runs (and not only three) as the
+    /// original formulation was shown not to guarantee them on the whole stack.
+    fn merge_collapse<F, O>(&mut self, array: &mut [T], compare: &mut F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        while self.runs.len() > 1 {
+            let n = self.runs.len();
+            let len = |i: usize| self.runs[i].length;
+
+            let mut i = n - 2;
+            if (n >= 3 && len(n-3) <= len(n-2) + len(n-1)) ||
+               (n >= 4 && len(n-4) <= len(n-3) + len(n-2)) {
+                if len(n-3) < len(n-1) {
+                    i = n - 3;
+                }
+            } else if len(n-2) > len(n-1) {
+                break;
+            }
+
+            self.merge_at(i, array, compare, observer);
+        }
+    }
+
+    /// Merge all runs on the stack, once the whole vector has been scanned.
+    fn merge_force_collapse<F, O>(&mut self, array: &mut [T], compare: &mut F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        while self.runs.len() > 1 {
+            let n = self.runs.len();
+            let mut i = n - 2;
+            if n >= 3 && self.runs[n-3].length < self.runs[n-1].length {
+                i = n - 3;
+            }
+            self.merge_at(i, array, compare, observer);
+        }
+    }
+
+    /// Merge the runs at indices `i` and `i+1` of the stack.
+    fn merge_at<F, O>(&mut self, i: usize, array: &mut [T], compare: &mut F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        let run1 = self.runs[i];
+        let run2 = self.runs[i+1];
+        debug_assert_eq!(run1.start + run1.length, run2.start);
+
+        self.runs[i].length = run1.length + run2.length;
+        self.runs.remove(i+1);
+
+        let merged = &mut array[run1.start..run2.start+run2.length];
+        let mut mid = run1.length;
+
+        // Elements of the first run smaller than or equal to the second run's first element are
+        // already in place.
+        let skip = gallop_right(&merged[mid], run1.start + mid, &merged[..mid], run1.start, 0,
+                                compare, observer);
+        let merged = &mut merged[skip..];
+        let offset = run1.start + skip;
+        mid -= skip;
+        if mid == 0 {
+            return;
+        }
+
+        // Elements of the second run larger than the first run's last element are already in
+        // place.
+        let hint = merged.len() - mid - 1;
+        let keep = gallop_left(&merged[mid-1], offset + mid - 1, &merged[mid..], offset + mid, hint,
+                               compare, observer);
+        let merged = &mut merged[..mid+keep];
+
+        if mid <= keep {
+            self.merge_lo(merged, offset, mid, compare, observer);
+        } else {
+            self.merge_hi(merged, offset, mid, compare, observer);
+        }
+    }
+
+    /// Merge `array[..mid]` and `array[mid..]`, the first run being the smallest.
+    ///
+    /// The first run is moved to the buffer and the elements are merged from the front. `array`
+    /// is located at index `offset` of the vector seen by `observer`.
+    fn merge_lo<F, O>(&mut self, array: &mut [T], offset: usize, mid: usize, compare: &mut F,
+                      observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        let n = array.len();
+        let array = array.as_mut_ptr();
+        let buffer = self.buffer;
+
+        unsafe {
+            ptr::copy_nonoverlapping(array, buffer, mid);
+            observer.move_to_buffer(offset, mid);
+
+            // "buffer[hole.start..hole.end]" is the rest of the first run. It belongs to the hole
+            // "array[hole.dest..right]", which is always exactly that size.
+            let mut hole = MergeHole { buffer, start: 0, end: mid, array, dest: 0 };
+            let mut right = mid;
+
+            'merge: while hole.start < hole.end && right < n {
+                // One pair at a time, counting how many times in a row each run won.
+                let mut left_wins = 0;
+                let mut right_wins = 0;
+                while left_wins < self.min_gallop && right_wins < self.min_gallop {
+                    // The buffer's elements were moved from the beginning of "array".
+                    observer.compare(offset + right, offset + hole.start);
+                    if compare(&*array.add(right), &*buffer.add(hole.start)) == Ordering::Less {
+                        ptr::copy_nonoverlapping(array.add(right), array.add(hole.dest), 1);
+                        right += 1;
+                        right_wins += 1;
+                        left_wins = 0;
+                    } else {
+                        ptr::copy_nonoverlapping(buffer.add(hole.start), array.add(hole.dest), 1);
+                        hole.start += 1;
+                        left_wins += 1;
+                        right_wins = 0;
+                    }
+                    report_writes(observer, offset, array, hole.dest, 1);
+                    hole.dest += 1;
+                    if hole.start == hole.end || right == n {
+                        break 'merge;
+                    }
+                }
+
+                // Galloping mode: move as many elements as possible at once from each run.
+                self.min_gallop += 1;
+                loop {
+                    self.min_gallop -= (self.min_gallop > 1) as usize;
+
+                    let left_run = slice::from_raw_parts(buffer.add(hole.start),
+                                                         hole.end - hole.start);
+                    let left_count = gallop_right(&*array.add(right), offset + right, left_run,
+                                                  offset + hole.start, 0, compare, observer);
+                    ptr::copy_nonoverlapping(buffer.add(hole.start), array.add(hole.dest),
+                                             left_count);
+                    report_writes(observer, offset, array, hole.dest, left_count);
+                    hole.start += left_count;
+                    hole.dest += left_count;
+                    if hole.start == hole.end {
+                        break 'merge;
+                    }
+
+                    ptr::copy_nonoverlapping(array.add(right), array.add(hole.dest), 1);
+                    report_writes(observer, offset, array, hole.dest, 1);
+                    right += 1;
+                    hole.dest += 1;
+                    if right == n {
+                        break 'merge;
+                    }
+
+                    let right_run = slice::from_raw_parts(array.add(right), n - right);
+                    let right_count = gallop_left(&*buffer.add(hole.start), offset + hole.start,
+                                                  right_run, offset + right, 0, compare,
+                                                  observer);
+                    // The hole might be smaller than the number of elements to move.
+                    ptr::copy(array.add(right), array.add(hole.dest), right_count);
+                    report_writes(observer, offset, array, hole.dest, right_count);
+                    right += right_count;
+                    hole.dest += right_count;
+                    if right == n {
+                        break 'merge;
+                    }
+
+                    ptr::copy_nonoverlapping(buffer.add(hole.start), array.add(hole.dest), 1);
+                    report_writes(observer, offset, array, hole.dest, 1);
+                    hole.start += 1;
+                    hole.dest += 1;
+                    if hole.start == hole.end {
+                        break 'merge;
+                    }
+
+                    if left_count < MIN_GALLOP && right_count < MIN_GALLOP {
+                        break;
+                    }
+                }
+                // Galloping did not pay off: make it harder to enter again.
+                self.min_gallop += 1;
+            }
+
+            // Dropping "hole" moves what is left of the first run into its final location. If the
+            // second run is what is left, it is already in place.
+            hole.report(offset, observer);
+        }
+    }
+
+    /// Merge `array[..mid]` and `array[mid..]`, the second run being the smallest.
+    ///
+    /// The second run is moved to the buffer and the elements are merged from the back. `array`
+    /// is located at index `offset` of the vector seen by `observer`.
+    fn merge_hi<F, O>(&mut self, array: &mut [T], offset: usize, mid: usize, compare: &mut F,
+                      observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        let n = array.len();
+        let array = array.as_mut_ptr();
+        let buffer = self.buffer;
+
+        unsafe {
+            ptr::copy_nonoverlapping(array.add(mid), buffer, n - mid);
+            observer.move_to_buffer(offset + mid, n - mid);
+
+            // "buffer[..hole.end]" is the rest of the second run. It belongs to the hole
+            // "array[hole.dest..hole.dest+hole.end]", "array[..hole.dest]" being the rest of the
+            // first run. Elements are written at the end of the hole.
+            let mut hole = MergeHole { buffer, start: 0, end: n - mid, array, dest: mid };
+
+            'merge: while hole.dest > 0 && hole.end > 0 {
+                let mut left_wins = 0;
+                let mut right_wins = 0;
+                while left_wins < self.min_gallop && right_wins < self.min_gallop {
+                    let last = hole.dest + hole.end - 1;
+                    // Take from the first run only if strictly larger: on equality, the element
+                    // of the second run must end up last.
+                    // The buffer's elements were moved from "array[mid..]".
+                    observer.compare(offset + mid + hole.end - 1, offset + hole.dest - 1);
+                    if compare(&*buffer.add(hole.end - 1),
+                               &*array.add(hole.dest - 1)) == Ordering::Less {
+                        ptr::copy_nonoverlapping(array.add(hole.dest - 1), array.add(last), 1);
+                        hole.dest -= 1;
+                        left_wins += 1;
+                        right_wins = 0;
+                    } else {
+                        ptr::copy_nonoverlapping(buffer.add(hole.end - 1), array.add(last), 1);
+                        hole.end -= 1;
+                        right_wins += 1;
+                        left_wins = 0;
+                    }
+                    report_writes(observer, offset, array, last, 1);
+                    if hole.dest == 0 || hole.end == 0 {
+                        break 'merge;
+                    }
+                }
+
+                self.min_gallop += 1;
+                loop {
+                    self.min_gallop -= (self.min_gallop > 1) as usize;
+
+                    // Elements of the first run strictly larger than the second run's last one.
+                    let left_run = slice::from_raw_parts(array, hole.dest);
+                    let left_count = hole.dest - gallop_right(&*buffer.add(hole.end - 1),
+                                                              offset + mid + hole.end - 1,
+                                                              left_run, offset, hole.dest - 1,
+                                                              compare, observer);
+                    ptr::copy(array.add(hole.dest - left_count),
+                              array.add(hole.dest + hole.end - left_count), left_count);
+                    report_writes(observer, offset, array, hole.dest + hole.end - left_count,
+                                  left_count);
+                    hole.dest -= left_count;
+                    if hole.dest == 0 {
+                        break 'merge;
+                    }
+
+                    ptr::copy_nonoverlapping(buffer.add(hole.end - 1),
+                                             array.add(hole.dest + hole.end - 1), 1);
+                    report_writes(observer, offset, array, hole.dest + hole.end - 1, 1);
+                    hole.end -= 1;
+                    if hole.end == 0 {
+                        break 'merge;
+                    }
+
+                    // Elements of the second run larger than or equal to the first run's last one.
+                    let right_run = slice::from_raw_parts(buffer, hole.end);
+                    let right_count = hole.end - gallop_left(&*array.add(hole.dest - 1),
+                                                             offset + hole.dest - 1, right_run,
+                                                             offset + mid, hole.end - 1,
+                                                             compare, observer);
+                    ptr::copy_nonoverlapping(buffer.add(hole.end - right_count),
+                                             array.add(hole.dest + hole.end - right_count),
+                                             right_count);
+                    report_writes(observer, offset, array, hole.dest + hole.end - right_count,
+                                  right_count);
+                    hole.end -= right_count;
+                    if hole.end == 0 {
+                        break 'merge;
+                    }
+
+                    ptr::copy_nonoverlapping(array.add(hole.dest - 1),
+                                             array.add(hole.dest + hole.end - 1), 1);
+                    report_writes(observer, offset, array, hole.dest + hole.end - 1, 1);
+                    hole.dest -= 1;
+                    if hole.dest == 0 {
+                        break 'merge;
+                    }
+
+                    if left_count < MIN_GALLOP && right_count < MIN_GALLOP {
+                        break;
+                    }
+                }
+                self.min_gallop += 1;
+            }
+
+            // Dropping "hole" moves what is left of the second run into its final location. If
+            // the first run is what is left, it is already in place.
+            hole.report(offset, observer);
+        }
+    }
+}
+
+/// Report to `observer` the `count` elements moved to `array[start..]`, `array` being located at
+/// index `offset` of the vector seen by `observer`.
+unsafe fn report_writes<T, O>(observer: &mut O, offset: usize, array: *const T, start: usize,
+                              count: usize)
+    where O: Observer<T>
+{
+    for i in start..start + count {
+        observer.write(offset + i, &*array.add(i));
+    }
+}
+
+/// Elements of a run that are still in the temporary buffer during a merge.
+///
+/// When dropped (at the end of the merge or while unwinding from a panicking comparison),
+/// `buffer[start..end]` is moved to `array[dest..]`.
+struct MergeHole<T> {
+    buffer: *mut T,
+    start: usize,
+    end: usize,
+    array: *mut T,
+    dest: usize,
+}
+
+impl<T> MergeHole<T> {
+    /// Report to `observer` the elements moved when the hole is dropped, `array` being located at
+    /// index `offset` of the vector seen by `observer`.
+    fn report<O: Observer<T>>(&self, offset: usize, observer: &mut O) {
+        for i in self.start..self.end {
+            observer.write(offset + self.dest + i - self.start, unsafe { &*self.buffer.add(i) });
+        }
+    }
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::copy_nonoverlapping(self.buffer.add(self.start),
+                                     self.array.add(self.dest),
+                                     self.end - self.start);
+        }
+    }
+}
+
+
+
+#[no_mangle]
+pub extern "C" fn ffi_timsort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                 -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_timsort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_timsort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_timsort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+
+
+#[no_mangle]
+pub extern "C" fn ffi_timsort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                 -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_timsort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_timsort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+#[no_mangle]
+pub extern "C" fn ffi_timsort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                  -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
+}
+
+#[no_mangle]
+pub extern "C" fn ffi_timsort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                  policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+#[no_mangle]
+pub extern "C" fn ffi_timsort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                  policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
+#[no_mangle]
+pub extern "C" fn ffi_timsort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                      size: libc::size_t, compar: Option<ffi::Comparator>,
+                                      ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Tim, base, nmemb, size, compar, ctx)
+}
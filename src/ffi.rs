@@ -0,0 +1,474 @@
+//! Helpers for the C ABI.
+//!
+//! The `ffi` module contains what is shared by the `extern "C"` functions exported by the
+//! algorithm modules.
+//!
+//! # Errors
+//!
+//! A Rust panic must not unwind into the C caller. Instead of asserting their arguments are
+//! valid, all exported functions return a `SortStatus`, `SortStatus::Ok` (zero) meaning the array
+//! was sorted. Empty arrays are valid and left untouched, whatever the pointer, unless an index
+//! into them is expected (`SortStatus::InvalidArgument`). A panic while sorting is caught and
+//! reported as `SortStatus::Panic`; the array then contains the same elements in an unspecified
+//! order.
+//!
+//! # Generic sorting
+//!
+//! Every comparison sort exports a `qsort()`-like function, `ffi_<name>_generic()`, sorting an
+//! array of `nmemb` records of `size` bytes each using a C comparator:
+//!
+//! ```c
+//! void ffi_quicksort_generic(void *base, size_t nmemb, size_t size,
+//!                            int (*compar)(const void *, const void *, void *),
+//!                            void *ctx);
+//! ```
+//!
+//! Like `qsort_r()`, the comparator returns a negative, zero or positive integer if the first
+//! record is respectively smaller than, equal to or larger than the second one. The `ctx` pointer
+//! is passed, untouched, as the comparator's third argument.
+//!
+//! # Sorting with a payload
+//!
+//! Quicksort, merge sort and heap sort export, for every numeric type of the keys, a function
+//! sorting `n` keys in ascending order and moving `n` records of `size` bytes along with them
+//! (see `sort_with_payload()` in their modules):
+//!
+//! ```c
+//! sorting_status ffi_quicksort_payload_i32(int32_t *keys_pointer, size_t n,
+//!                                          void *values_pointer, size_t size);
+//! ```
+//!
+//! The records are the rows of the C array at `values_pointer`, which must not overlap the keys.
+//! Floating point keys are ordered following a `NanPolicy`, the last argument of their exports.
+//!
+//! # Selection
+//!
+//! For every numeric type, `ffi_select_nth_<type>()` re-arranges `n` values so that the value at
+//! index `k` is the one which would be there if they were sorted (see
+//! `select::select_nth_unstable()`):
+//!
+//! ```c
+//! sorting_status ffi_select_nth_i32(int32_t *array_pointer, size_t n, size_t k);
+//! ```
+//!
+//! Floating point values are ordered following a `NanPolicy`, the last argument of their
+//! exports.
+//!
+//! # Argsort
+//!
+//! For every numeric type, `ffi_argsort_<type>()` writes to `indices` the permutation sorting `n`
+//! values, which are left untouched (see `permutation::apply()`). The sort is stable, like
+//! NumPy's `argsort(kind='stable')`:
+//!
+//! ```c
+//! sorting_status ffi_argsort_i32(const int32_t *array_pointer, size_t n, size_t *indices);
+//! ```
+//!
+//! Floating point values are ordered following a `NanPolicy`, the last argument of their
+//! exports.
+
+extern crate libc;
+
+use std::cmp;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use floats::{Float, NanError, NanPolicy};
+use permutation::Payload;
+use sorter::Sorter;
+
+/// Result of an exported function.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortStatus {
+    /// The array was sorted.
+    Ok = 0,
+    /// The array (or the comparator) pointer was null while there were elements to sort.
+    NullPointer = 1,
+    /// The array's size in bytes does not fit in memory.
+    InvalidLength = 2,
+    /// The comparator was null.
+    ComparatorError = 3,
+    /// A panic occurred while sorting and was caught.
+    Panic = 4,
+    /// Another argument, such as an index into the array or a percentile, was out of range, or a
+    /// NaN was found while sorting with `NanPolicy::Error`.
+    InvalidArgument = 5,
+}
+
+/// Type of the elements of a key column passed to `ffi_lexsort()`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// `int8_t`
+    I8 = 0,
+    /// `int16_t`
+    I16 = 1,
+    /// `int32_t`
+    I32 = 2,
+    /// `int64_t`
+    I64 = 3,
+    /// `uint8_t`
+    U8 = 4,
+    /// `uint16_t`
+    U16 = 5,
+    /// `uint32_t`
+    U32 = 6,
+    /// `uint64_t`
+    U64 = 7,
+    /// `float`
+    F32 = 8,
+    /// `double`
+    F64 = 9,
+}
+
+impl ColumnType {
+    /// Column type of a code received from C, `None` if it is unknown.
+    pub(crate) fn from_raw(code: libc::c_int) -> Option<ColumnType> {
+        match code {
+            0 => Some(ColumnType::I8),
+            1 => Some(ColumnType::I16),
+            2 => Some(ColumnType::I32),
+            3 => Some(ColumnType::I64),
+            4 => Some(ColumnType::U8),
+            5 => Some(ColumnType::U16),
+            6 => Some(ColumnType::U32),
+            7 => Some(ColumnType::U64),
+            8 => Some(ColumnType::F32),
+            9 => Some(ColumnType::F64),
+            _ => None,
+        }
+    }
+}
+
+/// Largest number of elements of `size` bytes an array can contain.
+pub(crate) fn max_length(size: usize) -> usize {
+    isize::MAX as usize / cmp::max(size, 1)
+}
+
+/// Validate the arguments of an exported function and sort the array using `sort`, catching
+/// panics.
+pub(crate) fn sort_array<T, F>(array_pointer: *mut T, n: libc::size_t, sort: F) -> SortStatus
+    where F: FnOnce(&mut [T])
+{
+    if n == 0 {
+        return SortStatus::Ok;
+    }
+    if array_pointer.is_null() {
+        return SortStatus::NullPointer;
+    }
+    if n > max_length(mem::size_of::<T>()) as libc::size_t {
+        return SortStatus::InvalidLength;
+    }
+    let to_sort = unsafe { slice::from_raw_parts_mut(array_pointer, n as usize) };
+    match panic::catch_unwind(AssertUnwindSafe(|| sort(to_sort))) {
+        Ok(())  => SortStatus::Ok,
+        Err(_)  => SortStatus::Panic,
+    }
+}
+
+/// NaN policy of a code received from C (the discriminant of a `NanPolicy`), `None` if it is
+/// unknown.
+fn nan_policy(code: libc::c_int) -> Option<NanPolicy> {
+    match code {
+        0 => Some(NanPolicy::First),
+        1 => Some(NanPolicy::Last),
+        2 => Some(NanPolicy::TotalOrder),
+        3 => Some(NanPolicy::Error),
+        _ => None,
+    }
+}
+
+/// Validate the arguments of an exported function sorting floating point values and sort the
+/// array using `sort_floats` following the NaN `policy`, catching panics.
+///
+/// # Errors
+///
+/// Returns `SortStatus::InvalidArgument` if `policy` is not a `NanPolicy`, or if the array
+/// contains a NaN with `NanPolicy::Error`; the array is then left untouched (see also
+/// `sort_array()`).
+///
+pub(crate) fn sort_floats_array<T, F>(array_pointer: *mut T, n: libc::size_t, policy: libc::c_int,
+                                      sort_floats: F) -> SortStatus
+    where T: Float,
+          F: FnOnce(&mut [T], NanPolicy) -> Result<(), NanError>
+{
+    with_nan_policy(policy, |policy, result| {
+        sort_array(array_pointer, n, |array| *result = sort_floats(array, policy))
+    })
+}
+
+/// Run `run` with the NaN `policy` received from C. `run` stores in its second argument whether a
+/// NaN was found with `NanPolicy::Error`, which is reported as `SortStatus::InvalidArgument`.
+fn with_nan_policy<F>(policy: libc::c_int, run: F) -> SortStatus
+    where F: FnOnce(NanPolicy, &mut Result<(), NanError>) -> SortStatus
+{
+    let policy = match nan_policy(policy) {
+        Some(policy) => policy,
+        None         => return SortStatus::InvalidArgument,
+    };
+    let mut result = Ok(());
+    match run(policy, &mut result) {
+        SortStatus::Ok if result.is_err() => SortStatus::InvalidArgument,
+        status                            => status,
+    }
+}
+
+/// Validate the arguments of an exported selection function and re-arrange the array using
+/// `select`, catching panics.
+///
+/// # Errors
+///
+/// Returns `SortStatus::InvalidArgument` if `k` is not smaller than `n` (see also `sort_array()`).
+///
+pub(crate) fn select_array<T, F>(array_pointer: *mut T, n: libc::size_t, k: libc::size_t,
+                                 select: F) -> SortStatus
+    where F: FnOnce(&mut [T], usize)
+{
+    if k >= n {
+        return SortStatus::InvalidArgument;
+    }
+    sort_array(array_pointer, n, |to_select| select(to_select, k as usize))
+}
+
+/// Validate the arguments of an exported function selecting among floating point values and
+/// re-arrange the array using `select` following the NaN `policy`, catching panics.
+///
+/// # Errors
+///
+/// Returns `SortStatus::InvalidArgument` if `policy` is not a `NanPolicy`, or if the array
+/// contains a NaN with `NanPolicy::Error`; the array is then left untouched (see also
+/// `select_array()`).
+///
+pub(crate) fn select_floats_array<T, F>(array_pointer: *mut T, n: libc::size_t, k: libc::size_t,
+                                        policy: libc::c_int, select: F) -> SortStatus
+    where T: Float,
+          F: FnOnce(&mut [T], usize, NanPolicy) -> Result<(), NanError>
+{
+    with_nan_policy(policy, |policy, result| {
+        select_array(array_pointer, n, k, |to_select, k| *result = select(to_select, k, policy))
+    })
+}
+
+/// Validate the arguments of an exported argsort function, compute the permutation sorting the
+/// array using `argsort` and write it to `indices`, catching panics.
+///
+/// # Errors
+///
+/// Returns `SortStatus::NullPointer` if `indices` is null while the array is not empty (see also
+/// `sort_array()`). `indices` is left untouched on error.
+///
+pub(crate) fn argsort_array<T, F>(array_pointer: *const T, n: libc::size_t,
+                                  indices: *mut libc::size_t, argsort: F) -> SortStatus
+    where F: FnOnce(&[T]) -> Vec<usize>
+{
+    if n == 0 {
+        return SortStatus::Ok;
+    }
+    if array_pointer.is_null() || indices.is_null() {
+        return SortStatus::NullPointer;
+    }
+    let max_n = cmp::min(max_length(mem::size_of::<T>()),
+                         max_length(mem::size_of::<libc::size_t>()));
+    if n > max_n as libc::size_t {
+        return SortStatus::InvalidLength;
+    }
+    let array = unsafe { slice::from_raw_parts(array_pointer, n as usize) };
+    let permutation = match panic::catch_unwind(AssertUnwindSafe(|| argsort(array))) {
+        Ok(permutation) => permutation,
+        Err(_)          => return SortStatus::Panic,
+    };
+    let indices = unsafe { slice::from_raw_parts_mut(indices, n as usize) };
+    for (output, index) in indices.iter_mut().zip(permutation) {
+        *output = index as libc::size_t;
+    }
+    SortStatus::Ok
+}
+
+/// Validate the arguments of an exported argsort function of floating point values, compute the
+/// permutation sorting the array using `argsort` following the NaN `policy` and write it to
+/// `indices`, catching panics.
+///
+/// # Errors
+///
+/// Returns `SortStatus::InvalidArgument` if `policy` is not a `NanPolicy`, or if the array
+/// contains a NaN with `NanPolicy::Error`; `indices` is then left untouched (see also
+/// `argsort_array()`).
+///
+pub(crate) fn argsort_floats_array<T, F>(array_pointer: *const T, n: libc::size_t,
+                                         indices: *mut libc::size_t, policy: libc::c_int,
+                                         argsort: F) -> SortStatus
+    where T: Float,
+          F: FnOnce(&[T], NanPolicy) -> Result<Vec<usize>, NanError>
+{
+    with_nan_policy(policy, |policy, result| {
+        argsort_array(array_pointer, n, indices, |array| {
+            // An empty permutation leaves `indices` untouched.
+            argsort(array, policy).unwrap_or_else(|error| {
+                *result = Err(error);
+                Vec::new()
+            })
+        })
+    })
+}
+
+/// Records of `size` bytes, re-arranged along with the keys sorted by the exported
+/// `sort_with_payload()` functions.
+pub(crate) struct Records<'a> {
+    bytes: &'a mut [u8],
+    size: usize,
+    len: usize,
+}
+
+impl<'a> Payload for Records<'a> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        if i == j || self.size == 0 {
+            return;
+        }
+        let (low, high) = (cmp::min(i, j), cmp::max(i, j));
+        let (start, end) = self.bytes.split_at_mut(high * self.size);
+        start[low * self.size..(low + 1) * self.size].swap_with_slice(&mut end[..self.size]);
+    }
+}
+
+/// Validate the arguments of an exported function sorting keys along with records of `size`
+/// bytes and sort them using `sort`, catching panics.
+///
+/// # Notes
+///
+/// `values_pointer` must point to `n * size` bytes valid for reads and writes, which do not
+/// overlap the keys.
+///
+/// # Errors
+///
+/// Returns `SortStatus::NullPointer` if `values_pointer` is null while there are records to
+/// move, and `SortStatus::InvalidLength` if the size of the records in bytes does not fit in
+/// memory (see also `sort_array()`).
+///
+pub(crate) fn sort_with_payload_array<T, F>(keys_pointer: *mut T, n: libc::size_t,
+                                            values_pointer: *mut libc::c_void,
+                                            size: libc::size_t, sort: F) -> SortStatus
+    where F: FnOnce(&mut [T], &mut Records)
+{
+    if n == 0 {
+        return SortStatus::Ok;
+    }
+    if keys_pointer.is_null() || (values_pointer.is_null() && size != 0) {
+        return SortStatus::NullPointer;
+    }
+    let max_n = cmp::min(max_length(mem::size_of::<T>()), max_length(size as usize));
+    if n > max_n as libc::size_t {
+        return SortStatus::InvalidLength;
+    }
+    let (n, size) = (n as usize, size as usize);
+    let keys = unsafe { slice::from_raw_parts_mut(keys_pointer, n) };
+    let bytes: &mut [u8] = if size == 0 {
+        &mut []
+    } else {
+        unsafe { slice::from_raw_parts_mut(values_pointer as *mut u8, n * size) }
+    };
+    let mut records = Records { bytes, size, len: n };
+    match panic::catch_unwind(AssertUnwindSafe(|| sort(keys, &mut records))) {
+        Ok(())  => SortStatus::Ok,
+        Err(_)  => SortStatus::Panic,
+    }
+}
+
+/// Validate the arguments of an exported function sorting floating point keys along with records
+/// of `size` bytes and sort them using `sort` following the NaN `policy`, catching panics.
+///
+/// # Errors
+///
+/// Returns `SortStatus::InvalidArgument` if `policy` is not a `NanPolicy`, or if the keys contain
+/// a NaN with `NanPolicy::Error`; the keys and the records are then left untouched (see also
+/// `sort_with_payload_array()`).
+///
+pub(crate) fn sort_floats_with_payload_array<T, F>(keys_pointer: *mut T, n: libc::size_t,
+                                                   values_pointer: *mut libc::c_void,
+                                                   size: libc::size_t, policy: libc::c_int,
+                                                   sort: F) -> SortStatus
+    where T: Float,
+          F: FnOnce(&mut [T], &mut Records, NanPolicy) -> Result<(), NanError>
+{
+    with_nan_policy(policy, |policy, result| {
+        sort_with_payload_array(keys_pointer, n, values_pointer, size, |keys, values| {
+            *result = sort(keys, values, policy)
+        })
+    })
+}
+
+/// C comparator: `int compar(const void *a, const void *b, void *ctx)`.
+///
+/// Wrapped in an `Option` by the exported functions as C can pass a null function pointer.
+pub type Comparator = extern "C" fn(*const libc::c_void, *const libc::c_void, *mut libc::c_void)
+                                    -> libc::c_int;
+
+/// Sort `nmemb` records of `size` bytes located at `base` using `sorter` and a C comparator.
+///
+/// # Details
+///
+/// The algorithms are generic over the type of the elements, which must be known at compile
+/// time. Records whose size is only known at runtime are thus not sorted directly: the indices
+/// of the records are sorted instead, comparing the records they point to. The records are then
+/// moved to their sorted location using a temporary copy of the array.
+///
+/// As the indices are sorted, the result is stable if the algorithm is.
+///
+/// # Notes
+///
+/// `base` must point to `nmemb * size` bytes valid for reads and writes. `compar` must be safe to
+/// call with pointers to any two of these records and `ctx`.
+///
+/// # Errors
+///
+/// Returns `SortStatus::ComparatorError` if `compar` is null (see also `sort_array()`).
+///
+pub(crate) fn sort_generic<S: Sorter>(sorter: S, base: *mut libc::c_void, nmemb: libc::size_t,
+                                          size: libc::size_t, compar: Option<Comparator>,
+                                          ctx: *mut libc::c_void) -> SortStatus {
+    if nmemb == 0 {
+        return SortStatus::Ok;
+    }
+    if base.is_null() {
+        return SortStatus::NullPointer;
+    }
+    let compar = match compar {
+        Some(compar) => compar,
+        None         => return SortStatus::ComparatorError,
+    };
+    if nmemb > max_length(size as usize) as libc::size_t {
+        return SortStatus::InvalidLength;
+    }
+    let (nmemb, size) = (nmemb as usize, size as usize);
+    if nmemb == 1 || size == 0 {
+        return SortStatus::Ok;
+    }
+
+    let base = base as *mut u8;
+    let record = |i: usize| unsafe { base.add(i * size) as *const libc::c_void };
+
+    let mut indices: Vec<usize> = (0..nmemb).collect();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        sorter.sort_by(&mut indices, |&a, &b| compar(record(a), record(b), ctx).cmp(&0));
+    }));
+    if result.is_err() {
+        return SortStatus::Panic;
+    }
+
+    // Gather the records in sorted order into a temporary array and copy it back.
+    let mut sorted: Vec<u8> = Vec::with_capacity(nmemb * size);
+    unsafe {
+        for (i, &index) in indices.iter().enumerate() {
+            ptr::copy_nonoverlapping(base.add(index * size), sorted.as_mut_ptr().add(i * size),
+                                     size);
+        }
+        ptr::copy_nonoverlapping(sorted.as_ptr(), base, nmemb * size);
+    }
+    SortStatus::Ok
+}
@@ -0,0 +1,127 @@
+//! Sorting floating point values.
+//!
+//! The `floats` module defines how floating point values are ordered when sorted with the
+//! `sort_floats()` function of the algorithm modules.
+//!
+//! Floating point values only implement `PartialOrd`: any comparison involving a NaN ("not a
+//! number") is false. Sorting algorithms relying on `PartialOrd` consider a NaN equal to
+//! everything, which breaks the transitivity of the ordering: a single NaN can leave the vector
+//! unsorted. The other special values are handled by `PartialOrd` but `-0.0` and `+0.0` compare
+//! equal, so their relative order in the output is unspecified.
+//!
+//! Here, values are ordered following IEEE 754's totalOrder predicate: negative NaNs, negative
+//! infinity, negative numbers, `-0.0`, `+0.0`, positive numbers, positive infinity and positive
+//! NaNs. Where NaNs end up can be chosen using a `NanPolicy`.
+//!
+//! Source: https://en.wikipedia.org/wiki/IEEE_754#Total-ordering_predicate
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+/// Where NaN values end up when sorting floating point values.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// All NaNs are placed at the beginning of the vector.
+    First = 0,
+    /// All NaNs are placed at the end of the vector (as NumPy does).
+    Last = 1,
+    /// IEEE 754 totalOrder: NaNs with their sign bit set are placed at the beginning, the others
+    /// at the end.
+    TotalOrder = 2,
+    /// NaNs are not allowed: sorting fails with a `NanError` and the vector is left untouched.
+    Error = 3,
+}
+
+/// Error returned when sorting a vector containing a NaN with `NanPolicy::Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NanError {
+    /// Index of the first NaN found.
+    pub index: usize,
+}
+
+impl fmt::Display for NanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot sort: NaN found at index {}", self.index)
+    }
+}
+
+impl Error for NanError {}
+
+/// Floating point types that can be sorted in a total order.
+pub trait Float: Copy + PartialOrd {
+    /// Returns `true` if the value is a NaN.
+    fn is_nan(self) -> bool;
+
+    /// IEEE 754 totalOrder comparison.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+impl Float for f32 {
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+}
+
+impl Float for f64 {
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+/// Compare two floating point values following `policy`.
+///
+/// # Details
+///
+/// Values that are not NaNs are always compared using IEEE 754's totalOrder, making `-0.0` smaller
+/// than `+0.0`. All NaNs compare equal to each other with `NanPolicy::First` and `NanPolicy::Last`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use sorting::floats::{compare, NanPolicy};
+///
+/// assert_eq!(compare(&std::f64::NAN, &1.0, NanPolicy::First), Ordering::Less);
+/// assert_eq!(compare(&std::f64::NAN, &1.0, NanPolicy::Last), Ordering::Greater);
+/// assert_eq!(compare(&-0.0, &0.0, NanPolicy::Last), Ordering::Less);
+/// ```
+///
+pub fn compare<T: Float>(a: &T, b: &T, policy: NanPolicy) -> Ordering {
+    match policy {
+        NanPolicy::First | NanPolicy::Last => {
+            let ordering = match (a.is_nan(), b.is_nan()) {
+                (true, true)   => return Ordering::Equal,
+                (true, false)  => Ordering::Less,
+                (false, true)  => Ordering::Greater,
+                (false, false) => return a.total_cmp(b),
+            };
+            if policy == NanPolicy::First { ordering } else { ordering.reverse() }
+        },
+        NanPolicy::TotalOrder | NanPolicy::Error => a.total_cmp(b),
+    }
+}
+
+/// Verify the values can be sorted following `policy`.
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN.
+///
+pub fn check<T: Float>(input: &[T], policy: NanPolicy) -> Result<(), NanError> {
+    if policy == NanPolicy::Error {
+        if let Some(index) = input.iter().position(|x| x.is_nan()) {
+            return Err(NanError { index });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Instrumentation of the sorting algorithms.
+//!
+//! The `instrument` module counts the operations performed by an algorithm, to check the
+//! complexities documented in the "Scaling" sections of the algorithm modules without relying on
+//! timings.
+//!
+//! Every comparison sort has a `sort_by_observed()` function (also available through
+//! `Sorter::sort_by_observed()`) which reports the comparisons, swaps, element moves and
+//! allocations it does to an `Observer`, along with the partitioning steps and recursive calls of
+//! the algorithms using them. The plain `sort_by()` functions use the `Unobserved` observer, whose
+//! methods do nothing and are optimized away.
+//!
+//! The `trace` module records these operations, for example to animate an algorithm.
+//!
+//! Radix sort does not compare elements and has no `sort_by()`: `radix::sort_observed()` and
+//! `radix::sort_msd_observed()` report the elements it moves or swaps, its temporary vector and
+//! its recursive calls, along with the comparisons of the insertion sort of its small buckets.
+//!
+//! # Examples
+//!
+//! ```
+//! use sorting::instrument;
+//! use sorting::sorter::Bubble;
+//!
+//! // Bubble sort stops after a single pass over an already sorted vector.
+//! let mut data: Vec<i32> = vec![1, 2, 3, 4, 5];
+//! let counts = instrument::count(&Bubble, &mut data, |a, b| a.cmp(b));
+//! assert_eq!(counts.comparisons, 4);
+//! assert_eq!(counts.swaps, 0);
+//! ```
+
+use std::cmp::Ordering;
+
+use sorter::Sorter;
+
+/// Receives the operations performed on the vector being sorted.
+///
+/// Indices are always relative to the whole vector given to `sort_by_observed()`, even when the
+/// algorithm works on a part of it. All methods do nothing by default.
+pub trait Observer<T> {
+    /// The elements at indices `i` and `j` are about to be compared, in that order. An element
+    /// which was moved to a temporary buffer is designated by the index it was moved from.
+    fn compare(&mut self, _i: usize, _j: usize) {}
+
+    /// The elements at indices `i` and `j` were swapped.
+    fn swap(&mut self, _i: usize, _j: usize) {}
+
+    /// An element was moved to index `i`, from another index or from a temporary buffer. `value`
+    /// is the element now at `i`.
+    fn write(&mut self, _i: usize, _value: &T) {}
+
+    /// The `len` elements starting at index `start` were moved to a temporary buffer.
+    fn move_to_buffer(&mut self, _start: usize, _len: usize) {}
+
+    /// A temporary buffer of `len` elements was allocated.
+    fn allocate(&mut self, _len: usize) {}
+
+    /// The elements `start..end` were partitioned: the pivot is at its final location, index
+    /// `pivot`, with no larger element before it and no smaller element after it.
+    fn partition(&mut self, _start: usize, _pivot: usize, _end: usize) {}
+
+    /// A recursive call starts sorting the elements `start..end`.
+    fn enter(&mut self, _start: usize, _end: usize) {}
+
+    /// The recursive call sorting the elements `start..end` returns.
+    fn exit(&mut self, _start: usize, _end: usize) {}
+}
+
+/// Observer ignoring all operations.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unobserved;
+
+impl<T> Observer<T> for Unobserved {}
+
+/// Number of operations performed while sorting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Calls to the comparator.
+    pub comparisons: usize,
+    /// Swaps of two elements.
+    pub swaps: usize,
+    /// Elements moved one at a time, to another index or to and from a temporary buffer.
+    pub moves: usize,
+    /// Heap allocations.
+    pub allocations: usize,
+}
+
+impl<T> Observer<T> for Counts {
+    fn compare(&mut self, _i: usize, _j: usize) {
+        self.comparisons += 1;
+    }
+
+    fn swap(&mut self, _i: usize, _j: usize) {
+        self.swaps += 1;
+    }
+
+    fn write(&mut self, _i: usize, _value: &T) {
+        self.moves += 1;
+    }
+
+    fn move_to_buffer(&mut self, _start: usize, len: usize) {
+        self.moves += len;
+    }
+
+    fn allocate(&mut self, _len: usize) {
+        self.allocations += 1;
+    }
+}
+
+/// Sort `input` with `sorter` using `compare`, counting the operations performed.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument;
+/// use sorting::sorter::Merge;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let counts = instrument::count(&Merge, &mut data, |a, b| a.cmp(b));
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(counts.swaps, 1);
+/// assert_eq!(counts.allocations, 1);
+/// ```
+///
+pub fn count<S, T, F>(sorter: &S, input: &mut [T], compare: F) -> Counts
+    where S: Sorter,
+          F: FnMut(&T, &T) -> Ordering
+{
+    let mut counts = Counts::default();
+    sorter.sort_by_observed(input, compare, &mut counts);
+    counts
+}
@@ -0,0 +1,282 @@
+//! Lexicographic sort over several key columns.
+//!
+//! The `lexsort` module sorts the rows of a table whose columns are stored in separate vectors,
+//! possibly of different numeric types, comparing the rows column by column: the first key
+//! column is compared first, and the following ones only break its ties. Every key column is
+//! sorted in ascending or descending order.
+//!
+//! ```
+//! use sorting::lexsort::{self, SortKey};
+//!
+//! let mut days: Vec<u32> = vec![2, 1, 2, 1];
+//! let mut scores: Vec<f64> = vec![0.5, 0.2, 0.9, 0.7];
+//! let mut names: Vec<&str> = vec!["a", "b", "c", "d"];
+//!
+//! // Ascending by day, then descending by score.
+//! let indices = lexsort::lexsort(&[SortKey::ascending(&days), SortKey::descending(&scores)]);
+//! assert_eq!(indices, vec![3, 1, 2, 0]);
+//!
+//! sorting::permutation::apply_payload(&mut (&mut days, &mut scores, &mut names), &indices);
+//! assert_eq!(names, vec!["d", "b", "c", "a"]);
+//! ```
+//!
+//! Source: https://numpy.org/doc/stable/reference/generated/numpy.lexsort.html
+
+extern crate libc;
+
+use std::cmp::Ordering;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use efficientsorts::merge;
+use ffi::{self, ColumnType};
+use floats::{self, NanPolicy};
+
+/// Order of a key column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Smallest values first.
+    Ascending,
+    /// Largest values first.
+    Descending,
+}
+
+/// Key column, comparing its elements by index.
+///
+/// # Details
+///
+/// `Column` is implemented for slices and vectors of the integer types and of `f32` and `f64`,
+/// and for references to columns. Floating point values are ordered in the total order of
+/// `floats::compare()`, NaNs being placed after all the other values in ascending order (as NumPy
+/// does) and before them in descending order.
+///
+pub trait Column {
+    /// Number of elements.
+    fn len(&self) -> usize;
+
+    /// Is the column empty?
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Compare the elements at indices `i` and `j`.
+    fn compare(&self, i: usize, j: usize) -> Ordering;
+}
+
+macro_rules! integer_column {
+    ($($t:ty)*) => {$(
+        impl Column for [$t] {
+            fn len(&self) -> usize {
+                <[$t]>::len(self)
+            }
+
+            fn compare(&self, i: usize, j: usize) -> Ordering {
+                self[i].cmp(&self[j])
+            }
+        }
+    )*}
+}
+
+integer_column!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+macro_rules! float_column {
+    ($($t:ty)*) => {$(
+        impl Column for [$t] {
+            fn len(&self) -> usize {
+                <[$t]>::len(self)
+            }
+
+            fn compare(&self, i: usize, j: usize) -> Ordering {
+                floats::compare(&self[i], &self[j], NanPolicy::Last)
+            }
+        }
+    )*}
+}
+
+float_column!(f32 f64);
+
+impl<T> Column for Vec<T> where [T]: Column {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn compare(&self, i: usize, j: usize) -> Ordering {
+        self.as_slice().compare(i, j)
+    }
+}
+
+impl<C: Column + ?Sized> Column for &C {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn compare(&self, i: usize, j: usize) -> Ordering {
+        (**self).compare(i, j)
+    }
+}
+
+/// Key column and its direction.
+pub struct SortKey<'a> {
+    column: Box<dyn Column + 'a>,
+    direction: Direction,
+}
+
+impl<'a> SortKey<'a> {
+    /// Key sorting `column`, usually a reference to a slice or a vector, in the given `direction`.
+    pub fn new<C: Column + 'a>(column: C, direction: Direction) -> SortKey<'a> {
+        SortKey { column: Box::new(column), direction }
+    }
+
+    /// Key sorting `column` in ascending order.
+    pub fn ascending<C: Column + 'a>(column: C) -> SortKey<'a> {
+        SortKey::new(column, Direction::Ascending)
+    }
+
+    /// Key sorting `column` in descending order.
+    pub fn descending<C: Column + 'a>(column: C) -> SortKey<'a> {
+        SortKey::new(column, Direction::Descending)
+    }
+
+    /// Compare the rows `i` and `j` of the column, in the key's direction.
+    fn compare(&self, i: usize, j: usize) -> Ordering {
+        match self.direction {
+            Direction::Ascending  => self.column.compare(i, j),
+            Direction::Descending => self.column.compare(j, i),
+        }
+    }
+}
+
+/// Lexicographic sort of the rows of several key columns.
+///
+/// # Details
+///
+/// Returns the permutation sorting the rows, without moving them: row `i` is made of element `i`
+/// of every key column. Rows are compared on the first key column, then on the second one if
+/// they are equal, and so on. Rows equal on all key columns stay in increasing order.
+///
+/// The indices of the rows are sorted using merge sort (see the `merge` module), whose stability
+/// guarantees the order of the equal rows. The `permutation` module applies the result to the key
+/// columns and to any other column of the table (see `permutation::apply_payload()`).
+///
+/// Unlike NumPy's `lexsort()`, whose _last_ key is the primary one, the keys are given in order
+/// of priority.
+///
+/// # Scaling
+///
+/// The complexity is O(K N log N) for N rows and K key columns, and the space complexity O(N).
+///
+/// # Panics
+///
+/// Panics if there are no keys or if the key columns differ in length.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::lexsort::{lexsort, SortKey};
+///
+/// let groups = vec![3u8, 1, 2, 1];
+/// let ages = vec![27i64, 52, 35, 19];
+/// let indices = lexsort(&[SortKey::ascending(&groups), SortKey::ascending(&ages)]);
+/// assert_eq!(indices, vec![3, 1, 2, 0]);
+/// ```
+///
+pub fn lexsort(keys: &[SortKey]) -> Vec<usize> {
+    assert!(!keys.is_empty(), "no key columns to sort");
+    let n = keys[0].column.len();
+    assert!(keys.iter().all(|key| key.column.len() == n), "the key columns differ in length");
+
+    let mut indices: Vec<usize> = (0..n).collect();
+    merge::sort_by(&mut indices, |&i, &j| {
+        keys.iter().map(|key| key.compare(i, j))
+                   .find(|&ordering| ordering != Ordering::Equal)
+                   .unwrap_or(Ordering::Equal)
+    });
+    indices
+}
+
+
+/// Key of `n` elements of the type given by its `ColumnType` code, at `pointer`.
+unsafe fn raw_key<'a>(pointer: *const libc::c_void, column_type: ColumnType, n: usize,
+                      direction: Direction) -> SortKey<'a> {
+    match column_type {
+        ColumnType::I8  => SortKey::new(slice::from_raw_parts(pointer as *const i8, n), direction),
+        ColumnType::I16 => SortKey::new(slice::from_raw_parts(pointer as *const i16, n), direction),
+        ColumnType::I32 => SortKey::new(slice::from_raw_parts(pointer as *const i32, n), direction),
+        ColumnType::I64 => SortKey::new(slice::from_raw_parts(pointer as *const i64, n), direction),
+        ColumnType::U8  => SortKey::new(slice::from_raw_parts(pointer as *const u8, n), direction),
+        ColumnType::U16 => SortKey::new(slice::from_raw_parts(pointer as *const u16, n), direction),
+        ColumnType::U32 => SortKey::new(slice::from_raw_parts(pointer as *const u32, n), direction),
+        ColumnType::U64 => SortKey::new(slice::from_raw_parts(pointer as *const u64, n), direction),
+        ColumnType::F32 => SortKey::new(slice::from_raw_parts(pointer as *const f32, n), direction),
+        ColumnType::F64 => SortKey::new(slice::from_raw_parts(pointer as *const f64, n), direction),
+    }
+}
+
+/// Write to `indices` the permutation sorting the `n` rows of `ncolumns` key columns, like
+/// NumPy's `lexsort()` but with the primary key first. `columns[c]` points to the `n` elements of
+/// column `c`, of the type given by `types[c]` (a `sorting_column_type`). Column `c` is sorted in
+/// descending order if `descending[c]` is non-zero; `descending` may be null to sort all the
+/// columns in ascending order. NaNs are placed last in ascending order.
+///
+/// Returns `SORTING_STATUS_INVALID_ARGUMENT` if there are no key columns or a type is unknown.
+#[no_mangle]
+pub extern "C" fn ffi_lexsort(columns: *const *const libc::c_void, types: *const libc::c_int,
+                              descending: *const libc::c_int, ncolumns: libc::size_t,
+                              n: libc::size_t, indices: *mut libc::size_t) -> ffi::SortStatus {
+    lexsort_columns(columns, types, descending, ncolumns, n, indices)
+}
+
+/// Validate the arguments of `ffi_lexsort()`, sort the rows of the columns and write the
+/// permutation to `indices`, catching panics.
+fn lexsort_columns(columns: *const *const libc::c_void, types: *const libc::c_int,
+                   descending: *const libc::c_int, ncolumns: libc::size_t, n: libc::size_t,
+                   indices: *mut libc::size_t) -> ffi::SortStatus {
+    if n == 0 {
+        return ffi::SortStatus::Ok;
+    }
+    if ncolumns == 0 {
+        return ffi::SortStatus::InvalidArgument;
+    }
+    if columns.is_null() || types.is_null() || indices.is_null() {
+        return ffi::SortStatus::NullPointer;
+    }
+    if n > ffi::max_length(mem::size_of::<u64>()) as libc::size_t ||
+       ncolumns > ffi::max_length(mem::size_of::<*const libc::c_void>()) as libc::size_t {
+        return ffi::SortStatus::InvalidLength;
+    }
+    let (n, ncolumns) = (n as usize, ncolumns as usize);
+    let pointers = unsafe { slice::from_raw_parts(columns, ncolumns) };
+    let types = unsafe { slice::from_raw_parts(types, ncolumns) };
+    let descending: &[libc::c_int] = if descending.is_null() {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(descending, ncolumns) }
+    };
+
+    let mut keys = Vec::with_capacity(ncolumns);
+    for (c, (&pointer, &column_type)) in pointers.iter().zip(types).enumerate() {
+        let column_type = match ColumnType::from_raw(column_type) {
+            Some(column_type) => column_type,
+            None              => return ffi::SortStatus::InvalidArgument,
+        };
+        if pointer.is_null() {
+            return ffi::SortStatus::NullPointer;
+        }
+        let direction = match descending.get(c) {
+            Some(&flag) if flag != 0 => Direction::Descending,
+            _                        => Direction::Ascending,
+        };
+        keys.push(unsafe { raw_key(pointer, column_type, n, direction) });
+    }
+
+    let permutation = match panic::catch_unwind(AssertUnwindSafe(|| lexsort(&keys))) {
+        Ok(permutation) => permutation,
+        Err(_)          => return ffi::SortStatus::Panic,
+    };
+    let indices = unsafe { slice::from_raw_parts_mut(indices, n) };
+    for (output, index) in indices.iter_mut().zip(permutation) {
+        *output = index as libc::size_t;
+    }
+    ffi::SortStatus::Ok
+}
@@ -40,43 +40,233 @@
 //!
 //! ## Simple sorts
 //!
-//! * [Insertion sort](https://en.wikipedia.org/wiki/Insertion_sort)
+//! * [Insertion sort](https://en.wikipedia.org/wiki/Insertion_sort) (stable)
 //! * [Selection sort](https://en.wikipedia.org/wiki/Selection_sort)
-//! * [Bubble sort](https://en.wikipedia.org/wiki/Bubble_sort)
+//! * [Bubble sort](https://en.wikipedia.org/wiki/Bubble_sort) (stable)
 //!
 //! ## Efficient sorts
 //!
 //! * [Quicksort](https://en.wikipedia.org/wiki/Quicksort)
-//! * [Merge sort](https://en.wikipedia.org/wiki/Merge_sort)
+//! * [Merge sort](https://en.wikipedia.org/wiki/Merge_sort) (stable)
 //! * [Heapsort](https://en.wikipedia.org/wiki/Heapsort)
+//! * [Introsort](https://en.wikipedia.org/wiki/Introsort)
+//! * [Timsort](https://en.wikipedia.org/wiki/Timsort) (stable)
+//! * [Radix sort](https://en.wikipedia.org/wiki/Radix_sort) (integers only)
+//!
+//! Selection sort, quicksort, heap sort and introsort are not stable: they may change the relative
+//! order of items with equal values. `Sorter::is_stable()` gives the guarantee of every algorithm.
+//!
+//! ## Selection
+//!
+//! * [Introselect](https://en.wikipedia.org/wiki/Introselect), in the `select` module:
+//!   `select_nth_unstable()` finds the element which would be at a given index once the vector is
+//!   sorted, in linear time and without sorting it. `median()` and `percentile()` use it on
+//!   floating point values.
+//! * [Partial sort](https://en.wikipedia.org/wiki/Partial_sorting), in the `partial` module:
+//!   `partial_sort()` sorts only the `k` smallest elements of a vector, in O(N log k), and
+//!   `top_k()` extracts them from an iterator without collecting it.
+//!
+//! ```
+//! let mut data: Vec<f64> = vec![4.0, 2.0, 3.0, 1.0, 5.0];
+//! assert_eq!(sorting::efficientsorts::select::median(&mut data), 3.0);
+//! ```
+//!
+//! ## Lexicographic sort
+//!
+//! The `lexsort` module sorts the rows of a table stored as separate columns, possibly of
+//! different numeric types, on several key columns with a direction per column (like NumPy's
+//! `lexsort()`, but with the primary key first). `lexsort()` returns the permutation sorting the
+//! rows, computed with the stable merge sort, and `permutation::apply_payload()` re-arranges all
+//! the columns along it.
+//!
+//! ```
+//! use sorting::lexsort::{lexsort, SortKey};
+//!
+//! let days: Vec<u32> = vec![2, 1, 2, 1];
+//! let scores: Vec<f64> = vec![0.5, 0.2, 0.9, 0.7];
+//! let indices = lexsort(&[SortKey::ascending(&days), SortKey::descending(&scores)]);
+//! assert_eq!(indices, vec![3, 1, 2, 0]);
+//! ```
 //!
 //!
 //! # Usage
 //!
+//! Every comparison sort exposes the same functions: `sort()` (using `PartialOrd`), `sort_by()`
+//! (using a comparator), `sort_by_key()`, `sort_floats()` and `sort_by_observed()` (reporting
+//! the operations performed, see the `instrument` module). Radix sort exposes `sort()` and
+//! `sort_msd()`, and their `sort_observed()` and `sort_msd_observed()` counterparts.
+//!
+//! ```
+//! let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+//! sorting::efficientsorts::quick::sort(&mut data);
+//! assert_eq!(vec![1, 2, 3, 4, 5], data);
+//! ```
+//!
+//! The comparison sorts also expose `argsort()` and `argsort_by()`, returning the indices sorting
+//! the vector without moving its elements. The `permutation` module applies or inverts them:
+//!
+//! ```
+//! let mut data: Vec<i32> = vec![40, 20, 30, 10];
+//! let indices = sorting::efficientsorts::merge::argsort(&data);
+//! assert_eq!(indices, vec![3, 1, 2, 0]);
+//! sorting::permutation::apply(&mut data, &indices);
+//! assert_eq!(data, vec![10, 20, 30, 40]);
+//! ```
+//!
+//! Quicksort, merge sort and heap sort also sort keys along with one or more companion slices,
+//! applying the same permutation to them, through `sort_with_payload()`:
+//!
+//! ```
+//! let mut keys: Vec<u32> = vec![30, 10, 20];
+//! let mut values: Vec<&str> = vec!["c", "a", "b"];
+//! sorting::efficientsorts::quick::sort_with_payload(&mut keys, &mut values);
+//! assert_eq!(values, vec!["a", "b", "c"]);
+//! ```
+//!
+//! The algorithms also implement the `sorter::Sorter` trait, giving access to their properties,
+//! and can be chosen at runtime by name through the `registry`:
+//!
+//! ```
+//! use sorting::sorter::Sorter;
+//!
+//! for algorithm in sorting::registry::all() {
+//!     let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+//!     algorithm.sort(&mut data);
+//!     assert_eq!(vec![1, 2, 3, 4, 5], data);
+//! }
+//! ```
 //!
 //! # Notes
 //!
+//! Floating point values only implement `PartialOrd` and a NaN breaks the ordering assumed by
+//! the sorting algorithms. Every algorithm module provides a `sort_floats()` function sorting
+//! `f32` and `f64` values in a total order, see the `floats` module.
+//!
+//! If a comparison panics (the comparator, the key function or `PartialOrd`), the panic is
+//! propagated once the vector is back to a permutation of its initial elements: no element is
+//! lost, duplicated or dropped twice.
 //!
 //! # Testing and validation
 //!
+//! Every algorithm goes through the same conformance suite (`tests/lib.rs`), on all numeric types
+//! and vectors of every pattern of the `patterns` module with random lengths up to 10 000 (500 for
+//! the algorithms quadratic in the worst case): the result must be ordered, a permutation of the
+//! input and left untouched by a second sort. Algorithms claiming to be stable must keep equal
+//! elements in their initial order. The comparison sorts must also sort in descending order
+//! through `sort_by()`, on a field through `sort_by_key()` and special floating point values with
+//! every NaN policy through `sort_floats()`.
+//!
+//! The `fuzz` directory contains `cargo fuzz` targets sorting arbitrary values of every numeric
+//! type with every algorithm (`sort`) and every exported C function (`ffi`), and sorting with an
+//! inconsistent comparator (`inconsistent_comparator`), which must neither panic nor loop forever:
+//!
+//! ```ignore
+//! cargo +nightly fuzz run sort
+//! ```
+//!
+//! The number of comparisons, swaps, element moves and allocations done by an algorithm can be
+//! counted using the `instrument` module, to check the complexities documented by every
+//! algorithm without relying on timings:
+//!
+//! ```
+//! use sorting::instrument;
+//! use sorting::sorter::Insertion;
+//!
+//! let mut data: Vec<i32> = (0..100).collect();
+//! let counts = instrument::count(&Insertion, &mut data, |a, b| a.cmp(b));
+//! assert_eq!(counts.comparisons, 99);
+//! ```
+//!
+//! The `trace` module records these operations, along with the partitioning steps and recursive
+//! calls, as events which can be written as JSON lines to replay them, for example to animate an
+//! algorithm.
+//!
+//! # Animations
+//!
+//! The `svg` module renders recorded sorts as bar charts, either one SVG image per operation or a
+//! single animated SVG: the bars compared are orange, the bars moved red and the bars at their
+//! final location green. The `animate` binary writes the animations of the simple sorts,
+//! quicksort, merge sort and heap sort to `figures/<name>.svg`:
+//!
+//! ```ignore
+//! cargo run --bin animate
+//! cargo run --bin animate -- --n 16 --pattern reversed --frames quicksort
+//! ```
+//!
+//! ![Quicksort](figures/quicksort.svg "Quicksort of 32 random values")
+//!
+//! The `sorting-viz` binary animates any algorithm of the `registry` directly in a terminal, with
+//! live comparison, swap and write counters:
+//!
+//! ```ignore
+//! cargo run --bin sorting-viz -- --n 40 --pattern few-unique:5 --delay 50 heapsort
+//! ```
+//!
+//! # C interface
+//!
+//! The library is built as a shared (`libsorting.so`) and a static (`libsorting.a`) C library.
+//! The build script generates `sorting.h`, declaring all the exported functions, and a
+//! pkg-config file, which the `install-c` binary installs with the libraries under a prefix:
+//!
+//! ```ignore
+//! cargo build --release
+//! cargo run --release --bin install-c -- PREFIX
+//! cc program.c $(PKG_CONFIG_PATH=PREFIX/lib/pkgconfig pkg-config --cflags --libs --static sorting)
+//! ```
+//!
+//! The header is `PREFIX/include/sorting.h`. `tests/c/test_sorting.c` is an example,
+//! built and run by `cargo test`.
+//!
 //! # Benchmarks
 //!
-//! The library uses Rust's FFI to export the sorting functions. A Python 3 module is provided
-//! `rust_sorting.py` that loads the library and exposes the different functions. The script
-//! `benchmark.py` will run the algorithms with arrays of random data multiple times and plot the
-//! timing calculations.
+//! The `benchmark` binary runs the algorithms with vectors of random data multiple times and
+//! writes the timings to `benchmark/<name>.txt`. The script `benchmark.py` plots them.
+//!
+//! The library also uses Rust's FFI to export the sorting functions. A Python 3 module is provided
+//! `rust_sorting.py` that loads the library and exposes the different functions.
+//!
+//! Every comparison sort also exports a `qsort()`-like function, `ffi_<name>_generic()`, sorting
+//! records of any size with a C comparator (see the `ffi` module and `rust_sorting.sort_generic()`).
+//!
+//! The `select` module exports `ffi_select_nth_<type>()`, `ffi_median_<type>()` and
+//! `ffi_percentile_<type>()`, exposed as `rust_sorting.select_nth()`, `median()` and `percentile()`.
+//!
+//! The `permutation` module exports `ffi_argsort_<type>()`, writing the indices sorting an array
+//! to a caller-provided buffer, exposed as `rust_sorting.argsort()` (like NumPy's `argsort()`).
+//!
+//! Keys are sorted along with an array of records of any size by `ffi_<name>_payload_<type>()`,
+//! for quicksort, merge sort and heap sort, exposed as `rust_sorting.sort_with_payload()`.
+//!
+//! The `lexsort` module exports `ffi_lexsort()`, taking an array of column pointers along with
+//! their `sorting_column_type` and direction, exposed as `rust_sorting.lexsort()` (like NumPy's
+//! `lexsort()`, the last key being the primary one).
+//!
+//! The exports sorting, selecting or computing the argsort of `f32` and `f64` values take a
+//! `floats::NanPolicy` (`sorting_nan_policy` in C) as their last argument, exposed as the `policy`
+//! argument of the `rust_sorting.py` functions: `"first"`, `"last"` (the default, as NumPy does),
+//! `"total_order"` or `"error"`.
+//!
+//! The exported functions never panic: they return a `ffi::SortStatus` that `rust_sorting.py`
+//! turns into a `SortError` exception. Empty arrays are valid and left untouched.
 //!
 //! To reproduce:
 //!
 //! ```ignore
-//! cargo build --release
+//! cargo run --release --bin benchmark
 //! python3 benchmark.py
 //! ```
 //!
-//! To reload data and replot (no need for previous run):
+//! Algorithms can be selected by name and the largest size limited, for example:
 //!
 //! ```ignore
-//! python3 benchmark.py --reload
+//! cargo run --release --bin benchmark -- --max-n 10000 quicksort mergesort
+//! ```
+//!
+//! The input follows one of the patterns of the `patterns` module (sorted, reversed, few unique
+//! values...):
+//!
+//! ```ignore
+//! cargo run --release --bin benchmark -- --pattern sorted --output benchmark/sorted
 //! ```
 //!
 //! ![Benchmark](figures/benchmark.png "Profiling of the different algorithms")
@@ -89,6 +279,16 @@
 
 pub mod simplesorts;
 pub mod efficientsorts;
+pub mod floats;
+pub mod sorter;
+pub mod registry;
+pub mod ffi;
+pub mod patterns;
+pub mod instrument;
+pub mod trace;
+pub mod svg;
+pub mod permutation;
+pub mod lexsort;
 
 
 // Expose Rust's sort() method as if it was implemented here.
@@ -96,80 +296,67 @@ pub mod efficientsorts;
 
 extern crate libc;
 
-use std::slice;
-
 
 #[no_mangle]
-pub extern "C" fn ffi_sort_i8(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i8, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                              -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
 }
 #[no_mangle]
-pub extern "C" fn ffi_sort_i16(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i16, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                               -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
 }
 #[no_mangle]
-pub extern "C" fn ffi_sort_i32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                               -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
 }
 #[no_mangle]
-pub extern "C" fn ffi_sort_i64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                               -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
 }
 
 
 #[no_mangle]
-pub extern "C" fn ffi_sort_u8(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u8, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                              -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
 }
 #[no_mangle]
-pub extern "C" fn ffi_sort_u16(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u16, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                               -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
 }
 #[no_mangle]
-pub extern "C" fn ffi_sort_u32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u32, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                               -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
+}
+#[no_mangle]
+pub extern "C" fn ffi_sort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                               -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, |to_sort| to_sort.sort())
+}
+
+// Rust's sort() cannot be used on floating points as they do not implement `Ord`: sort them in
+// IEEE 754 total order instead, NaNs being placed following the `policy`.
+#[no_mangle]
+pub extern "C" fn ffi_sort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                               policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, |to_sort, policy| {
+        floats::check(to_sort, policy)?;
+        to_sort.sort_by(|a, b| floats::compare(a, b, policy));
+        Ok(())
+    })
 }
 #[no_mangle]
-pub extern "C" fn ffi_sort_u64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u64, n as usize)
-    };
-    to_sort.sort();
+pub extern "C" fn ffi_sort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                               policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, |to_sort, policy| {
+        floats::check(to_sort, policy)?;
+        to_sort.sort_by(|a, b| floats::compare(a, b, policy));
+        Ok(())
+    })
 }
@@ -0,0 +1,284 @@
+//! Input patterns.
+//!
+//! The `patterns` module generates vectors to sort following typical input distributions, for
+//! testing and benchmarking. Sorting algorithms can behave very differently on already sorted,
+//! reversed or repetitive inputs than on random ones.
+//!
+//! Generation is reproducible: the same pattern, length and seed always give the same vector.
+//!
+//! # Examples
+//!
+//! ```
+//! use sorting::patterns::{self, Pattern};
+//!
+//! let reversed: Vec<u8> = patterns::generate(Pattern::Reversed, 5, 0);
+//! assert_eq!(reversed, vec![4, 3, 2, 1, 0]);
+//!
+//! let few_unique: Vec<f64> = patterns::generate(Pattern::FewUnique(3), 100, 42);
+//! assert_eq!(few_unique, patterns::generate(Pattern::FewUnique(3), 100, 42));
+//! ```
+
+extern crate rand;
+
+use std::fmt;
+use std::str::FromStr;
+
+use self::rand::{Rng, SeedableRng, XorShiftRng};
+
+use efficientsorts::quick;
+
+/// A distribution of the values to sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    /// Increasing values.
+    Sorted,
+    /// Decreasing values.
+    Reversed,
+    /// Increasing values up to the middle, then decreasing: `0 1 2 3 2 1 0`.
+    OrganPipe,
+    /// Increasing runs of about √N values: `0 1 2 0 1 2 0 1 2`.
+    Sawtooth,
+    /// Uniformly distributed values.
+    Random,
+    /// Uniformly distributed values among the given number of distinct values.
+    FewUnique(u64),
+    /// All values are equal.
+    AllEqual,
+    /// Increasing values where the given percentage of the elements were swapped at random.
+    Perturbed(u32),
+    /// Values following a Zipf distribution (exponent 1): value `k` appears about twice as often
+    /// as value `2k + 1`.
+    Zipf,
+    /// Input making quicksort with a median-of-three pivot (`efficientsorts::quick`) quadratic.
+    MedianOf3Killer,
+}
+
+/// All the patterns, with default parameters.
+pub const PATTERNS: [Pattern; 10] = [
+    Pattern::Sorted,
+    Pattern::Reversed,
+    Pattern::OrganPipe,
+    Pattern::Sawtooth,
+    Pattern::Random,
+    Pattern::FewUnique(10),
+    Pattern::AllEqual,
+    Pattern::Perturbed(10),
+    Pattern::Zipf,
+    Pattern::MedianOf3Killer,
+];
+
+/// List all the patterns, with default parameters.
+pub fn all() -> &'static [Pattern] {
+    &PATTERNS
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Pattern::Sorted          => write!(f, "sorted"),
+            Pattern::Reversed        => write!(f, "reversed"),
+            Pattern::OrganPipe       => write!(f, "organ-pipe"),
+            Pattern::Sawtooth        => write!(f, "sawtooth"),
+            Pattern::Random          => write!(f, "random"),
+            Pattern::FewUnique(k)    => write!(f, "few-unique:{}", k),
+            Pattern::AllEqual        => write!(f, "all-equal"),
+            Pattern::Perturbed(k)    => write!(f, "perturbed:{}", k),
+            Pattern::Zipf            => write!(f, "zipf"),
+            Pattern::MedianOf3Killer => write!(f, "median-of-3-killer"),
+        }
+    }
+}
+
+/// Error returned when parsing an unknown pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePatternError(String);
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown pattern: {}", self.0)
+    }
+}
+
+impl ::std::error::Error for ParsePatternError {}
+
+impl FromStr for Pattern {
+    type Err = ParsePatternError;
+
+    /// Parse a pattern from its name (see `Display`). The parameter of `few-unique` and
+    /// `perturbed` is optional.
+    fn from_str(s: &str) -> Result<Pattern, ParsePatternError> {
+        let error = || ParsePatternError(s.to_string());
+        let mut parts = s.splitn(2, ':');
+        let name = parts.next().unwrap();
+        let parameter = parts.next();
+
+        let pattern = match name {
+            "few-unique" => Pattern::FewUnique(match parameter {
+                Some(k) => k.parse().map_err(|_| error())?,
+                None    => 10,
+            }),
+            "perturbed" => Pattern::Perturbed(match parameter {
+                Some(k) => k.parse().map_err(|_| error())?,
+                None    => 10,
+            }),
+            _ if parameter.is_some() => return Err(error()),
+            "sorted"             => Pattern::Sorted,
+            "reversed"           => Pattern::Reversed,
+            "organ-pipe"         => Pattern::OrganPipe,
+            "sawtooth"           => Pattern::Sawtooth,
+            "random"             => Pattern::Random,
+            "all-equal"          => Pattern::AllEqual,
+            "zipf"               => Pattern::Zipf,
+            "median-of-3-killer" => Pattern::MedianOf3Killer,
+            _                    => return Err(error()),
+        };
+        Ok(pattern)
+    }
+}
+
+/// Types the patterns can be generated for.
+pub trait Element: Copy {
+    /// Convert `rank`, between 0 and `ranks - 1`, to a value. Larger ranks give larger (or equal)
+    /// values.
+    fn from_rank(rank: u64, ranks: u64) -> Self;
+}
+
+macro_rules! impl_element_integer {
+    ($($t:ty),*) => {$(
+        impl Element for $t {
+            /// Ranks are centered on zero for signed types. If there are more ranks than values,
+            /// they are scaled to the type's range.
+            fn from_rank(rank: u64, ranks: u64) -> $t {
+                let (min, max) = (<$t>::MIN as i128, <$t>::MAX as i128);
+                let start = if min < 0 { -((ranks / 2) as i128) } else { 0 };
+                if start + (ranks as i128 - 1) <= max {
+                    (start + rank as i128) as $t
+                } else {
+                    (min + rank as i128 * (max - min) / (ranks as i128 - 1)) as $t
+                }
+            }
+        }
+    )*}
+}
+
+impl_element_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl Element for f32 {
+    /// Ranks are centered on zero.
+    fn from_rank(rank: u64, ranks: u64) -> f32 {
+        rank as f32 - (ranks / 2) as f32
+    }
+}
+
+impl Element for f64 {
+    /// Ranks are centered on zero.
+    fn from_rank(rank: u64, ranks: u64) -> f64 {
+        rank as f64 - (ranks / 2) as f64
+    }
+}
+
+/// Generate `n` values following `pattern`, using `seed` for the random patterns.
+///
+/// # Details
+///
+/// The patterns are first generated as ranks (0, 1, 2...) which are then converted to `T`
+/// following `Element::from_rank()`.
+///
+pub fn generate<T: Element>(pattern: Pattern, n: usize, seed: u64) -> Vec<T> {
+    let (ranks, nb_ranks) = generate_ranks(pattern, n, seed);
+    ranks.into_iter().map(|rank| T::from_rank(rank, nb_ranks)).collect()
+}
+
+/// Random number generator for `seed` (XorShift, which must not be seeded with zeros only).
+fn rng(seed: u64) -> XorShiftRng {
+    XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32, 0x9e37_79b9, 0x7f4a_7c15])
+}
+
+/// Generate the ranks of the values and the number of possible ranks.
+fn generate_ranks(pattern: Pattern, n: usize, seed: u64) -> (Vec<u64>, u64) {
+    if n == 0 {
+        return (Vec::new(), 1);
+    }
+    let mut rng = rng(seed);
+    let n64 = n as u64;
+    match pattern {
+        Pattern::Sorted => ((0..n64).collect(), n64),
+        Pattern::Reversed => ((0..n64).rev().collect(), n64),
+        Pattern::OrganPipe => {
+            ((0..n64).map(|i| if i <= (n64 - 1) / 2 { i } else { n64 - 1 - i }).collect(),
+             n64.div_ceil(2))
+        },
+        Pattern::Sawtooth => {
+            let period = ((n as f64).sqrt() as u64).max(1);
+            ((0..n64).map(|i| i % period).collect(), period)
+        },
+        Pattern::Random => ((0..n64).map(|_| rng.gen_range(0, n64)).collect(), n64),
+        Pattern::FewUnique(k) => {
+            let k = k.max(1);
+            ((0..n64).map(|_| rng.gen_range(0, k)).collect(), k)
+        },
+        Pattern::AllEqual => (vec![0; n], 1),
+        Pattern::Perturbed(percent) => {
+            let mut ranks: Vec<u64> = (0..n64).collect();
+            if n > 1 {
+                for _ in 0..(n * percent.min(100) as usize / 100).div_ceil(2) {
+                    let (i, j) = (rng.gen_range(0, n), rng.gen_range(0, n));
+                    ranks.swap(i, j);
+                }
+            }
+            (ranks, n64)
+        },
+        Pattern::Zipf => {
+            // Inverse transform sampling: P(k) is proportional to 1 / (k + 1).
+            let mut cumulative = Vec::with_capacity(n);
+            let mut total = 0.0;
+            for k in 0..n {
+                total += 1.0 / (k + 1) as f64;
+                cumulative.push(total);
+            }
+            let ranks = (0..n).map(|_| {
+                let u = rng.gen::<f64>() * total;
+                cumulative.partition_point(|&c| c <= u).min(n - 1) as u64
+            }).collect();
+            (ranks, n64)
+        },
+        Pattern::MedianOf3Killer => (median_of_3_killer(n), n64),
+    }
+}
+
+/// Build an input for which quicksort with a median-of-three pivot is quadratic.
+///
+/// # Details
+///
+/// Uses M. D. McIlroy's adversary ("A Killer Adversary for Quicksort", 1999): quicksort is run on
+/// elements whose values are not yet decided ("gas"). Every time two gas elements are compared,
+/// one of them is frozen to the smallest value not yet used, and the adversary tries to keep the
+/// pivot candidate as gas so it ends up being a bad pivot. Once the sort is done, the values
+/// decided are an input for which quicksort makes the same (bad) choices.
+///
+fn median_of_3_killer(n: usize) -> Vec<u64> {
+    let gas = n as u64;
+    let mut values: Vec<u64> = vec![gas; n];
+    let mut nb_solid = 0;
+    let mut candidate = 0;
+
+    let mut ids: Vec<usize> = (0..n).collect();
+    quick::sort_by(&mut ids, |&x, &y| {
+        if values[x] == gas && values[y] == gas {
+            if x == candidate {
+                values[x] = nb_solid;
+            } else {
+                values[y] = nb_solid;
+            }
+            nb_solid += 1;
+        }
+        if values[x] == gas {
+            candidate = x;
+        } else if values[y] == gas {
+            candidate = y;
+        }
+        values[x].cmp(&values[y])
+    });
+
+    values
+}
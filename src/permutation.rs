@@ -0,0 +1,329 @@
+//! Permutations of vectors.
+//!
+//! The `permutation` module applies and inverts the permutations returned by the `argsort()`
+//! functions of the algorithm modules: a permutation of `N` elements is a vector containing every
+//! index from `0` to `N-1` exactly once.
+//!
+//! ```
+//! use sorting::permutation;
+//!
+//! let mut data: Vec<char> = vec!['c', 'a', 'd', 'b'];
+//! let mut indices = sorting::efficientsorts::merge::argsort(&data);
+//! assert_eq!(indices, vec![1, 3, 0, 2]);
+//!
+//! permutation::apply(&mut data, &indices);
+//! assert_eq!(data, vec!['a', 'b', 'c', 'd']);
+//!
+//! // The inverse gives the rank of every element of the initial vector.
+//! permutation::invert(&mut indices);
+//! assert_eq!(indices, vec![2, 0, 3, 1]);
+//! ```
+//!
+//! The `Payload` trait describes the companion slices re-arranged along with the keys sorted by
+//! the `sort_with_payload()` functions of the quicksort, merge sort and heap sort modules.
+
+use efficientsorts::merge;
+use instrument::Observer;
+
+/// Is `permutation` a permutation of the indices `0` to `N-1`, `N` being its length?
+///
+/// # Examples
+///
+/// ```
+/// assert!(sorting::permutation::is_valid(&[2, 0, 1]));
+/// assert!(!sorting::permutation::is_valid(&[2, 0, 2]));
+/// ```
+///
+pub fn is_valid(permutation: &[usize]) -> bool {
+    let mut seen = vec![false; permutation.len()];
+    for &index in permutation {
+        if index >= seen.len() || seen[index] {
+            return false;
+        }
+        seen[index] = true;
+    }
+    true
+}
+
+/// Re-arrange a vector following a permutation.
+///
+/// # Details
+///
+/// The element at index `permutation[i]` is moved to index `i`: applying the result of
+/// `argsort()` sorts the vector.
+///
+/// Elements are swapped along the cycles of the permutation, so they are neither copied nor
+/// cloned. Only a flag per element, marking the elements already at their location, is allocated.
+///
+/// # Panics
+///
+/// Panics if `permutation` is not a valid permutation (see `is_valid()`) of the vector's indices.
+/// The vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<&str> = vec!["b", "c", "a"];
+/// sorting::permutation::apply(&mut data, &[2, 0, 1]);
+/// assert_eq!(data, vec!["a", "b", "c"]);
+/// ```
+///
+pub fn apply<T>(array: &mut [T], permutation: &[usize]) {
+    assert_eq!(array.len(), permutation.len(), "the permutation and the vector differ in length");
+    assert!(is_valid(permutation), "invalid permutation");
+    apply_by_swaps(permutation, |i, j| array.swap(i, j));
+}
+
+/// Re-arrange a payload following a permutation.
+///
+/// # Details
+///
+/// Same as `apply()`, for any `Payload`: a tuple of columns is re-arranged at once, for example
+/// following the permutation returned by `lexsort::lexsort()`.
+///
+/// # Panics
+///
+/// Panics if `permutation` is not a valid permutation (see `is_valid()`) of the payload's
+/// indices. The payload is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// let mut names: Vec<&str> = vec!["b", "c", "a"];
+/// let mut ages: Vec<u32> = vec![27, 35, 52];
+/// sorting::permutation::apply_payload(&mut (&mut names, &mut ages), &[2, 0, 1]);
+/// assert_eq!(names, vec!["a", "b", "c"]);
+/// assert_eq!(ages, vec![52, 27, 35]);
+/// ```
+///
+pub fn apply_payload<P: Payload + ?Sized>(payload: &mut P, permutation: &[usize]) {
+    assert_eq!(payload.len(), permutation.len(),
+               "the permutation and the payload differ in length");
+    assert!(is_valid(permutation), "invalid permutation");
+    apply_by_swaps(permutation, |i, j| payload.swap(i, j));
+}
+
+/// Apply a valid permutation to `keys` and to `payload`, which have the same length as it.
+pub(crate) fn apply_with_payload<K, P>(keys: &mut [K], payload: &mut P, permutation: &[usize])
+    where P: Payload + ?Sized
+{
+    apply_by_swaps(permutation, |i, j| {
+        keys.swap(i, j);
+        payload.swap(i, j);
+    });
+}
+
+/// Apply a valid permutation by calling `swap` along its cycles (see `apply()`).
+fn apply_by_swaps<F: FnMut(usize, usize)>(permutation: &[usize], mut swap: F) {
+    let mut done = vec![false; permutation.len()];
+    for start in 0..permutation.len() {
+        // Follow the cycle starting at "start": every swap moves one element to its location.
+        let mut i = start;
+        while !done[i] {
+            done[i] = true;
+            let next = permutation[i];
+            if next == start {
+                break;
+            }
+            swap(i, next);
+            i = next;
+        }
+    }
+}
+
+/// Invert a permutation in place.
+///
+/// # Details
+///
+/// On return, `permutation[j]` is the index `i` such that the initial `permutation[i]` was `j`.
+/// The inverse of the result of `argsort()` is the rank of every element of the vector: the index
+/// it would be moved to by sorting.
+///
+/// # Panics
+///
+/// Panics if `permutation` is not a valid permutation (see `is_valid()`). It is then left
+/// untouched.
+///
+/// # Examples
+///
+/// ```
+/// let mut permutation: Vec<usize> = vec![2, 0, 1];
+/// sorting::permutation::invert(&mut permutation);
+/// assert_eq!(permutation, vec![1, 2, 0]);
+/// ```
+///
+pub fn invert(permutation: &mut [usize]) {
+    assert!(is_valid(permutation), "invalid permutation");
+
+    let mut done = vec![false; permutation.len()];
+    for start in 0..permutation.len() {
+        if done[start] {
+            continue;
+        }
+        // Reverse the cycle starting at "start": every element points back to its predecessor.
+        let mut previous = start;
+        let mut current = permutation[start];
+        while current != start {
+            let next = permutation[current];
+            permutation[current] = previous;
+            done[current] = true;
+            previous = current;
+            current = next;
+        }
+        permutation[start] = previous;
+        done[start] = true;
+    }
+}
+
+
+/// Companion data re-arranged along with sorted keys.
+///
+/// # Details
+///
+/// The `sort_with_payload()` functions apply to the payload the permutation sorting the keys,
+/// element `i` of the payload following key `i`. The payload is only accessed through swaps.
+///
+/// `Payload` is implemented for slices, vectors and mutable references to payloads. Tuples of up
+/// to four payloads, possibly of different types, are re-arranged together: their lengths must
+/// be the same.
+///
+/// # Examples
+///
+/// ```
+/// let mut keys: Vec<u32> = vec![3, 1, 2];
+/// let mut names: Vec<&str> = vec!["c", "a", "b"];
+/// let mut scores: Vec<f64> = vec![0.3, 0.1, 0.2];
+/// sorting::efficientsorts::merge::sort_with_payload(&mut keys, &mut (&mut names, &mut scores));
+/// assert_eq!(names, vec!["a", "b", "c"]);
+/// assert_eq!(scores, vec![0.1, 0.2, 0.3]);
+/// ```
+///
+pub trait Payload {
+    /// Number of elements.
+    fn len(&self) -> usize;
+
+    /// Is the payload empty?
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Swap the elements at indices `i` and `j`.
+    fn swap(&mut self, i: usize, j: usize);
+}
+
+impl<T> Payload for [T] {
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j);
+    }
+}
+
+impl<T> Payload for Vec<T> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        <[T]>::swap(self, i, j);
+    }
+}
+
+impl<P: Payload + ?Sized> Payload for &mut P {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        (**self).swap(i, j);
+    }
+}
+
+macro_rules! tuple_payload {
+    ($first:ident $($name:ident $index:tt)*) => {
+        impl<$first: Payload, $($name: Payload),*> Payload for ($first, $($name),*) {
+            /// Length of the payloads, which must all be the same.
+            fn len(&self) -> usize {
+                let len = self.0.len();
+                $(assert_eq!(self.$index.len(), len, "the payloads differ in length");)*
+                len
+            }
+
+            fn swap(&mut self, i: usize, j: usize) {
+                self.0.swap(i, j);
+                $(self.$index.swap(i, j);)*
+            }
+        }
+    }
+}
+
+tuple_payload!(A B 1);
+tuple_payload!(A B 1 C 2);
+tuple_payload!(A B 1 C 2 D 3);
+
+/// Observer mirroring on a payload the swaps of the keys being sorted, for the algorithms which
+/// only swap elements.
+pub(crate) struct SwapPayload<'a, P: 'a + ?Sized>(pub(crate) &'a mut P);
+
+impl<'a, T, P: Payload + ?Sized> Observer<T> for SwapPayload<'a, P> {
+    fn swap(&mut self, i: usize, j: usize) {
+        self.0.swap(i, j);
+    }
+}
+
+
+/// Export the argsort functions, `ffi_argsort_<type>()` (see the `ffi` module), using
+/// `$argsort_by`.
+///
+/// Every export is given by its name and the type of the values. The exports of floating point
+/// values take a NaN `policy` (see `ffi::argsort_floats_array()`). build.rs declares the exports
+/// listed by the invocation in the C header.
+macro_rules! argsort_exports {
+    ($argsort_by:path;
+     integers { $($integer:ident: $integer_type:ty),* $(,)? }
+     floats { $($float:ident: $float_type:ty),* $(,)? }) => {
+        $(
+            #[no_mangle]
+            pub extern "C" fn $integer(array_pointer: *const $integer_type,
+                                       n: $crate::libc::size_t,
+                                       indices: *mut $crate::libc::size_t)
+                                       -> $crate::ffi::SortStatus {
+                $crate::ffi::argsort_array(array_pointer, n, indices, |array| {
+                    $argsort_by(array, |a: &$integer_type, b| a.cmp(b))
+                })
+            }
+        )*
+        $(
+            #[no_mangle]
+            pub extern "C" fn $float(array_pointer: *const $float_type, n: $crate::libc::size_t,
+                                     indices: *mut $crate::libc::size_t,
+                                     policy: $crate::libc::c_int) -> $crate::ffi::SortStatus {
+                $crate::ffi::argsort_floats_array(array_pointer, n, indices, policy,
+                                                  |array, policy| {
+                    $crate::floats::check(array, policy)?;
+                    Ok($argsort_by(array, |a, b| $crate::floats::compare(a, b, policy)))
+                })
+            }
+        )*
+    }
+}
+
+argsort_exports! {
+    merge::argsort_by;
+    integers {
+        ffi_argsort_i8: i8,
+        ffi_argsort_i16: i16,
+        ffi_argsort_i32: i32,
+        ffi_argsort_i64: i64,
+        ffi_argsort_u8: u8,
+        ffi_argsort_u16: u16,
+        ffi_argsort_u32: u32,
+        ffi_argsort_u64: u64,
+    }
+    floats {
+        ffi_argsort_f32: f32,
+        ffi_argsort_f64: f64,
+    }
+}
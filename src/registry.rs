@@ -0,0 +1,130 @@
+//! Registry of the sorting algorithms.
+//!
+//! The `registry` module lists all the algorithms implementing the `Sorter` trait and allows
+//! choosing one at runtime, by name.
+//!
+//! As `Sorter` has generic methods, it cannot be used as a trait object. Instead, the `Algorithm`
+//! enum has one variant per algorithm and implements `Sorter` by forwarding every call to the
+//! corresponding zero-sized type of the `sorter` module.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use instrument::Observer;
+use sorter::{Allocation, Complexity, Sorter};
+use sorter::{Bubble, Insertion, Selection, Quick, Merge, Heap, Intro, Tim};
+
+/// One of the sorting algorithms, chosen at runtime.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::registry;
+/// use sorting::sorter::Sorter;
+///
+/// let algorithm = registry::find("heapsort").unwrap();
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// algorithm.sort(&mut data);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Bubble,
+    Insertion,
+    Selection,
+    Quick,
+    Merge,
+    Heap,
+    Intro,
+    Tim,
+}
+
+/// All the algorithms, simple sorts first.
+pub const ALGORITHMS: [Algorithm; 8] = [
+    Algorithm::Bubble,
+    Algorithm::Insertion,
+    Algorithm::Selection,
+    Algorithm::Quick,
+    Algorithm::Merge,
+    Algorithm::Heap,
+    Algorithm::Intro,
+    Algorithm::Tim,
+];
+
+/// List all the algorithms.
+pub fn all() -> &'static [Algorithm] {
+    &ALGORITHMS
+}
+
+/// Find an algorithm by its name (see `Sorter::name()`).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::registry::{self, Algorithm};
+///
+/// assert_eq!(registry::find("quicksort"), Some(Algorithm::Quick));
+/// assert_eq!(registry::find("bogosort"), None);
+/// ```
+///
+pub fn find(name: &str) -> Option<Algorithm> {
+    all().iter().cloned().find(|algorithm| algorithm.name() == name)
+}
+
+/// Call `$method` on the zero-sized type corresponding to the `Algorithm`.
+macro_rules! dispatch {
+    ($algorithm:expr, $method:ident($($arg:expr),*)) => {
+        match $algorithm {
+            Algorithm::Bubble    => Bubble.$method($($arg),*),
+            Algorithm::Insertion => Insertion.$method($($arg),*),
+            Algorithm::Selection => Selection.$method($($arg),*),
+            Algorithm::Quick     => Quick.$method($($arg),*),
+            Algorithm::Merge     => Merge.$method($($arg),*),
+            Algorithm::Heap      => Heap.$method($($arg),*),
+            Algorithm::Intro     => Intro.$method($($arg),*),
+            Algorithm::Tim       => Tim.$method($($arg),*),
+        }
+    }
+}
+
+impl Sorter for Algorithm {
+    fn name(&self) -> &'static str {
+        dispatch!(*self, name())
+    }
+
+    fn sort_by<T, F>(&self, input: &mut [T], compare: F)
+        where F: FnMut(&T, &T) -> Ordering
+    {
+        dispatch!(*self, sort_by(input, compare))
+    }
+
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        dispatch!(*self, sort_by_observed(input, compare, observer))
+    }
+
+    fn is_stable(&self) -> bool {
+        dispatch!(*self, is_stable())
+    }
+
+    fn is_in_place(&self) -> bool {
+        dispatch!(*self, is_in_place())
+    }
+
+    fn allocation(&self) -> Allocation {
+        dispatch!(*self, allocation())
+    }
+
+    fn complexity(&self) -> Complexity {
+        dispatch!(*self, complexity())
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
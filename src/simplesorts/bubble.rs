@@ -6,7 +6,12 @@
 
 extern crate libc;
 
-use std::slice;
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use sorter::{Bubble, Sorter};
 
 /// Simple sort: bubble sort.
 ///
@@ -40,6 +45,12 @@ use std::slice;
 /// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
 /// compiler knows how to compare the elements and sort them.
 ///
+/// The implementation is 'stable' as it does preserve the relative order of items with
+/// equal values: adjacent elements are only swapped when the first one is strictly greater.
+///
+/// Elements are only moved by swapping them: if the comparison panics, the vector is left as a
+/// permutation of its initial elements.
+///
 /// Just don't use this sorting function. It's purely academic.
 ///
 /// # Examples
@@ -51,6 +62,54 @@ use std::slice;
 /// ```
 ///
 pub fn sort<T: PartialOrd>(input: &mut [T]) {
+    sort_by(input, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Simple sort: bubble sort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::simplesorts::bubble::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(input: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(input, compare, &mut Unobserved);
+}
+
+/// Simple sort: bubble sort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison and swap (see the
+/// `instrument` module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::simplesorts::bubble::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(counts.swaps, 5);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(input: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
     let n = input.len();
 
     // External loop indicates the number of elements to skip at end of vector
@@ -62,9 +121,12 @@ pub fn sort<T: PartialOrd>(input: &mut [T]) {
         // Note that the end of iteration is the total number of elements minus "i" minus one. The
         // minus one is important as we compare element "j" with the next one "j+1".
         for j in 0..n-i-1 {
-            // If element is larger than the next one, swap them.
-            if input[j] > input[j+1] {
+            // If element is larger than the next one, swap them. Equal elements are never
+            // swapped, keeping the sort stable.
+            observer.compare(j, j+1);
+            if compare(&input[j], &input[j+1]) == Ordering::Greater {
                 input.swap(j,j+1);
+                observer.swap(j, j+1);
                 swap_occured = true;
             }
         }
@@ -76,98 +138,154 @@ pub fn sort<T: PartialOrd>(input: &mut [T]) {
     }
 }
 
+/// Simple sort: bubble sort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::simplesorts::bubble::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(input: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(input, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Simple sort: bubble sort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::simplesorts::bubble::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(input: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(input, policy)?;
+    sort_by(input, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Bubble sort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// As the algorithm is stable, the indices of equal elements stay in increasing order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::simplesorts::bubble::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(input: &[T]) -> Vec<usize> {
+    Bubble.argsort(input)
+}
+
+/// Bubble sort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::simplesorts::bubble::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(input: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Bubble.argsort_by(input, compare)
+}
+
 
 
 #[no_mangle]
-pub extern fn ffi_bubblesort_i8(array_pointer: *const libc::int8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_i16(array_pointer: *const libc::int16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                     -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_i32(array_pointer: *const libc::int32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                     -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_i64(array_pointer: *const libc::int64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                     -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_u8(array_pointer: *const libc::uint8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                    -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_u16(array_pointer: *const libc::uint16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                     -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_u32(array_pointer: *const libc::uint32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                     -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_u64(array_pointer: *const libc::uint64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                     -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_f32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                     policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+#[no_mangle]
+pub extern "C" fn ffi_bubblesort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                     policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
 }
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
 #[no_mangle]
-pub extern "C" fn ffi_bubblesort_f64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_bubblesort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                         size: libc::size_t, compar: Option<ffi::Comparator>,
+                                         ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Bubble, base, nmemb, size, compar, ctx)
 }
@@ -6,7 +6,12 @@
 
 extern crate libc;
 
-use std::slice;
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use sorter::{Insertion, Sorter};
 
 /// Simple sort: insertion sort.
 ///
@@ -15,13 +20,13 @@ use std::slice;
 /// Insertion sort is one of the simplest sorting algorithm. Every elements of the list to sort
 /// are picked one at a time and inserted into a the sorted list at the right location.
 ///
-/// This function takes an immutable reference vector of any type and returns a vector of `usize`
-/// of the same length containing the indices of the initial vector sorted.
+/// The sorted list is the beginning of the vector. Every following element is moved back, one
+/// swap at a time, until the previous element is not larger than it.
 ///
 /// # Scaling
 ///
-/// In the best case scenario, the list is already sorted and the work is simply to append the
-/// element at the end of the list, hence a O(N) scaling.
+/// In the best case scenario, the list is already sorted and every element is only compared to
+/// the previous one, hence a O(N) scaling (exactly N-1 comparisons).
 ///
 /// In the worst case scenario, the list is in reverse order. Hence, every element is compared to
 /// all previous elements already sorted until this new one gets inserted at the beginning of the
@@ -32,15 +37,17 @@ use std::slice;
 ///
 /// # Optimizations
 ///
-/// Every element of the vector to sort are compared to the first and last element of the sorted
-/// vector. This prevents the worst case scenario to happen. The implementation still keeps an
-/// O(N^2) scaling though.
+/// The sorted list is scanned backward, from its end, so the scan stops as soon as the insertion
+/// location is found. Nearly sorted vectors are thus sorted in close to linear time.
 ///
 /// # Notes
 ///
 /// The implementation is 'stable' as it does preserve the relative order of items with
 /// equal values.
 ///
+/// Every element is moved back by swapping it with the previous one, so a panicking comparison
+/// leaves the vector as a permutation of its initial elements.
+///
 /// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
 /// compiler knows how to compare the elements and sort them.
 ///
@@ -53,122 +60,230 @@ use std::slice;
 /// ```
 ///
 pub fn sort<T: PartialOrd>(input: &mut [T]) {
+    sort_by(input, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Simple sort: insertion sort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::simplesorts::insertion::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(input: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(input, compare, &mut Unobserved);
+}
+
+/// Simple sort: insertion sort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison and swap (see the
+/// `instrument` module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::simplesorts::insertion::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(counts.swaps, 5);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(input: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
+    insertion_sort(input, 0, &mut compare, observer);
+}
+
+/// Insertion sort of `input`, located at index `offset` of the vector seen by `observer`.
+pub(crate) fn insertion_sort<T, F, O>(input: &mut [T], offset: usize, compare: &mut F,
+                                      observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
     let n = input.len();
 
     // Start at second element, and insert every elements at the right location
     for i in 1..n {
-        // Loop back over the already sorted subvector, and insert element "i" at the
-        // proper location.
-        for j in 0..i {
-            // Element "i" is smaller than "j", insert element "i" before "j" and
-            // break the "j" loop.
-            if input[j] > input[i] {
-
-                // Move element "i" back in the vector by swaping it with all others until
-                // it reaches the proper position (before "j").
-                for k in (j..i).rev() {
-                    input.swap(k+1, k);
-                }
-
-                // Go to the next element "i".
+        // Move element "i" back in the vector by swaping it with the previous one as long as the
+        // previous one is larger. Equal elements are never swapped, keeping the sort stable.
+        let mut j = i;
+        while j > 0 {
+            observer.compare(offset+j-1, offset+j);
+            if compare(&input[j-1], &input[j]) != Ordering::Greater {
                 break;
             }
+            input.swap(j-1, j);
+            observer.swap(offset+j-1, offset+j);
+            j -= 1;
         }
     }
 }
 
+/// Simple sort: insertion sort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::simplesorts::insertion::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(input: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(input, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Simple sort: insertion sort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::simplesorts::insertion::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(input: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(input, policy)?;
+    sort_by(input, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Insertion sort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// As the algorithm is stable, the indices of equal elements stay in increasing order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::simplesorts::insertion::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(input: &[T]) -> Vec<usize> {
+    Insertion.argsort(input)
+}
+
+/// Insertion sort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::simplesorts::insertion::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(input: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Insertion.argsort_by(input, compare)
+}
+
 
 
 #[no_mangle]
-pub extern fn ffi_insertionsort_i8(array_pointer: *const libc::int8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                       -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_i16(array_pointer: *const libc::int16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_i32(array_pointer: *const libc::int32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_i64(array_pointer: *const libc::int64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_u8(array_pointer: *const libc::uint8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                       -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_u16(array_pointer: *const libc::uint16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_u32(array_pointer: *const libc::uint32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_u64(array_pointer: *const libc::uint64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_f32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                        policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+#[no_mangle]
+pub extern "C" fn ffi_insertionsort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                        policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
 }
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
 #[no_mangle]
-pub extern "C" fn ffi_insertionsort_f64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut f64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_insertionsort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                            size: libc::size_t, compar: Option<ffi::Comparator>,
+                                            ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Insertion, base, nmemb, size, compar, ctx)
 }
@@ -6,7 +6,12 @@
 
 extern crate libc;
 
-use std::slice;
+use std::cmp::Ordering;
+
+use floats::{self, Float, NanError, NanPolicy};
+use ffi;
+use instrument::{Observer, Unobserved};
+use sorter::{Selection, Sorter};
 
 /// Simple sort: selection sort.
 ///
@@ -31,6 +36,13 @@ use std::slice;
 /// The type T of the vector elements to sort _must_ implement the `PartialOrd` trait so the
 /// compiler knows how to compare the elements and sort them.
 ///
+/// The implementation is not 'stable': swapping the minimum with the first unsorted element can
+/// move that element after others equal to it. Sorting `[(1, 'a'), (1, 'b'), (0, 'c')]` by the
+/// first field gives `[(0, 'c'), (1, 'b'), (1, 'a')]`.
+///
+/// As the minimum is moved with a swap, the vector is still a permutation of its initial elements
+/// if the comparison panics.
+///
 /// # Examples
 ///
 /// ```
@@ -40,6 +52,54 @@ use std::slice;
 /// ```
 ///
 pub fn sort<T: PartialOrd>(array: &mut [T]) {
+    sort_by(array, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+}
+
+/// Simple sort: selection sort, using a comparator function.
+///
+/// # Details
+///
+/// Same algorithm as `sort()` but the ordering of the elements is given by the `compare` closure
+/// instead of the `PartialOrd` trait. This allows sorting in descending order or sorting structs
+/// by one of their fields.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// sorting::simplesorts::selection::sort_by(&mut data, |a, b| b.cmp(a));
+/// assert_eq!(vec![5, 4, 3, 2, 1], data);
+/// ```
+///
+pub fn sort_by<T, F>(array: &mut [T], compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    sort_by_observed(array, compare, &mut Unobserved);
+}
+
+/// Simple sort: selection sort, reporting its operations to an observer.
+///
+/// # Details
+///
+/// Same algorithm as `sort_by()`, calling `observer` for every comparison and swap (see the
+/// `instrument` module).
+///
+/// # Examples
+///
+/// ```
+/// use sorting::instrument::Counts;
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let mut counts = Counts::default();
+/// sorting::simplesorts::selection::sort_by_observed(&mut data, |a, b| a.cmp(b), &mut counts);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(counts.swaps, 1);
+/// ```
+///
+pub fn sort_by_observed<T, F, O>(array: &mut [T], mut compare: F, observer: &mut O)
+    where F: FnMut(&T, &T) -> Ordering,
+          O: Observer<T>
+{
 
     if !array.is_empty() {
         // Let's loop over the input array, skipping last value (important as we want to get a
@@ -57,7 +117,8 @@ pub fn sort<T: PartialOrd>(array: &mut [T]) {
                 //     k.1: Smallest value of "int_slice".
                 let k = int_slice.iter().enumerate().fold(
                     (0, int_slice.first().unwrap()), |acc, item| {
-                        if acc.1 > item.1 { item } else { acc }
+                        observer.compare(i + acc.0 + 1, i + item.0 + 1);
+                        if compare(acc.1, item.1) == Ordering::Greater { item } else { acc }
                 });
 
                 // Return from the block the smallest element between the one at index "i" of
@@ -65,107 +126,166 @@ pub fn sort<T: PartialOrd>(array: &mut [T]) {
                 // NOTE: Since "k" worked on the subarray "int_slice", it's first element is the
                 //       i^th element of "array". If "k" is actually smaller than "array[i]", we
                 //       need to adapt the index as stored in "k.0".
-                if k.1 < &array[i] { i + k.0 + 1 } else { i }
+                observer.compare(i + k.0 + 1, i);
+                if compare(k.1, &array[i]) == Ordering::Less { i + k.0 + 1 } else { i }
             };
 
-            array.swap(i, j);
+            if j != i {
+                array.swap(i, j);
+                observer.swap(i, j);
+            }
         }
     }
 }
 
+/// Simple sort: selection sort, using a key extraction function.
+///
+/// # Details
+///
+/// Elements are ordered by comparing the keys returned by `key` for each of them.
+///
+/// # Examples
+///
+/// ```
+/// let mut data: Vec<(&str, u32)> = vec![("b", 2), ("c", 3), ("a", 1)];
+/// sorting::simplesorts::selection::sort_by_key(&mut data, |&(_, age)| age);
+/// assert_eq!(vec![("a", 1), ("b", 2), ("c", 3)], data);
+/// ```
+///
+pub fn sort_by_key<T, K, F>(array: &mut [T], mut key: F)
+    where K: PartialOrd,
+          F: FnMut(&T) -> K
+{
+    sort_by(array, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal));
+}
+
+/// Simple sort: selection sort, for floating point values.
+///
+/// # Details
+///
+/// Sort `f32` or `f64` values in a total order: `-0.0` is placed before `+0.0` and NaNs are
+/// placed according to `policy` (see `floats::NanPolicy`).
+///
+/// # Errors
+///
+/// Returns a `NanError` if `policy` is `NanPolicy::Error` and the vector contains a NaN. The
+/// vector is then left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::floats::NanPolicy;
+///
+/// let mut data: Vec<f64> = vec![4.0, std::f64::NAN, 3.0, 0.0, -0.0];
+/// sorting::simplesorts::selection::sort_floats(&mut data, NanPolicy::Last).unwrap();
+/// assert_eq!(data[..4].to_vec(), vec![-0.0, 0.0, 3.0, 4.0]);
+/// assert!(data[0].is_sign_negative() && data[4].is_nan());
+/// ```
+///
+pub fn sort_floats<T: Float>(array: &mut [T], policy: NanPolicy) -> Result<(), NanError> {
+    floats::check(array, policy)?;
+    sort_by(array, |a, b| floats::compare(a, b, policy));
+    Ok(())
+}
+
+/// Selection sort of the indices of a vector.
+///
+/// # Details
+///
+/// Returns the permutation sorting the vector, the index of its smallest element first, without
+/// moving the elements: the indices are sorted, comparing the elements they point to. The
+/// `permutation` module applies or inverts the result.
+///
+/// The algorithm is not stable: the indices of equal elements end up in any order.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// assert_eq!(sorting::simplesorts::selection::argsort(&data), vec![3, 1, 2, 0, 4]);
+/// ```
+///
+pub fn argsort<T: PartialOrd>(array: &[T]) -> Vec<usize> {
+    Selection.argsort(array)
+}
+
+/// Selection sort of the indices of a vector, using a comparator function.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// let descending = sorting::simplesorts::selection::argsort_by(&data, |a, b| b.cmp(a));
+/// assert_eq!(descending, vec![4, 0, 2, 1, 3]);
+/// ```
+///
+pub fn argsort_by<T, F>(array: &[T], compare: F) -> Vec<usize>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    Selection.argsort_by(array, compare)
+}
 
 
 
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_i8(array_pointer: *const libc::int8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_i8(array_pointer: *mut libc::int8_t, n: libc::size_t)
+                                       -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_i16(array_pointer: *const libc::int16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_i16(array_pointer: *mut libc::int16_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_i32(array_pointer: *const libc::int32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_i32(array_pointer: *mut libc::int32_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_i64(array_pointer: *const libc::int64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_i64(array_pointer: *mut libc::int64_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_u8(array_pointer: *const libc::uint8_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u8, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_u8(array_pointer: *mut libc::uint8_t, n: libc::size_t)
+                                       -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_u16(array_pointer: *const libc::uint16_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u16, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_u16(array_pointer: *mut libc::uint16_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_u32(array_pointer: *const libc::uint32_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_u32(array_pointer: *mut libc::uint32_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_u64(array_pointer: *const libc::uint64_t, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut u64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_u64(array_pointer: *mut libc::uint64_t, n: libc::size_t)
+                                        -> ffi::SortStatus {
+    ffi::sort_array(array_pointer, n, sort)
 }
 
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_f32(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i32, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_f32(array_pointer: *mut libc::c_float, n: libc::size_t,
+                                        policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
+}
+#[no_mangle]
+pub extern "C" fn ffi_selectionsort_f64(array_pointer: *mut libc::c_double, n: libc::size_t,
+                                        policy: libc::c_int) -> ffi::SortStatus {
+    ffi::sort_floats_array(array_pointer, n, policy, sort_floats)
 }
+
+/// Sort the `nmemb` records of `size` bytes at `base` using the C comparator `compar`, like
+/// `qsort_r()`: `ctx` is passed to `compar` as its third argument (see the `ffi` module).
 #[no_mangle]
-pub extern "C" fn ffi_selectionsort_f64(array_pointer: *const libc::c_void, n: libc::size_t) {
-    assert!(!array_pointer.is_null());
-    assert!(n != 0);
-    let mut to_sort = unsafe {
-        slice::from_raw_parts_mut(array_pointer as *mut i64, n as usize)
-    };
-    sort(&mut to_sort);
+pub extern "C" fn ffi_selectionsort_generic(base: *mut libc::c_void, nmemb: libc::size_t,
+                                            size: libc::size_t, compar: Option<ffi::Comparator>,
+                                            ctx: *mut libc::c_void) -> ffi::SortStatus {
+    ffi::sort_generic(Selection, base, nmemb, size, compar, ctx)
 }
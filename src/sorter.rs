@@ -0,0 +1,291 @@
+//! Common interface to the sorting algorithms.
+//!
+//! The `sorter` module defines the `Sorter` trait, implemented by a zero-sized type for every
+//! comparison sort of the `simplesorts` and `efficientsorts` modules. It gives access to the
+//! algorithm's functions and to its properties (stability, memory usage and complexity) so the
+//! algorithms can be used interchangeably, for example by the `registry`.
+//!
+//! Radix sort is not a comparison sort (it cannot sort using a comparator) and thus does not
+//! implement `Sorter`.
+//!
+//! All the algorithms are panic safe: if a comparison panics, the vector is left as a permutation
+//! of its initial elements before the panic is propagated.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use instrument::{Observer, Unobserved};
+use simplesorts::{bubble, insertion, selection};
+use efficientsorts::{heap, intro, merge, quick, tim};
+
+/// Order of growth of a number of operations with the number N of elements to sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Order {
+    /// O(N)
+    Linear,
+    /// O(N log N)
+    Linearithmic,
+    /// O(N^2)
+    Quadratic,
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Order::Linear       => "O(N)",
+            Order::Linearithmic => "O(N log N)",
+            Order::Quadratic    => "O(N^2)",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Time complexity of an algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Complexity {
+    /// Best case (usually an already sorted vector).
+    pub best: Order,
+    /// Average case (random vector).
+    pub average: Order,
+    /// Worst case.
+    pub worst: Order,
+}
+
+/// Heap memory allocated by an algorithm while sorting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Allocation {
+    /// Nothing is allocated.
+    None,
+    /// Small temporary allocations whose size does not depend on the number of elements.
+    Constant,
+    /// A temporary buffer whose size is proportional to the number of elements.
+    Linear,
+}
+
+/// A comparison sorting algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use sorting::sorter::{Sorter, Quick};
+///
+/// let mut data: Vec<i32> = vec![4, 2, 3, 1, 5];
+/// Quick.sort(&mut data);
+/// assert_eq!(vec![1, 2, 3, 4, 5], data);
+/// assert_eq!(Quick.name(), "quicksort");
+/// assert!(!Quick.is_stable());
+/// ```
+///
+pub trait Sorter {
+    /// Name of the algorithm, as used in the FFI exports (`ffi_<name>_i32`) and benchmark files.
+    fn name(&self) -> &'static str;
+
+    /// Sort the vector using the `PartialOrd` trait.
+    fn sort<T: PartialOrd>(&self, input: &mut [T]) {
+        self.sort_by(input, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    }
+
+    /// Sort the vector using a comparator function.
+    fn sort_by<T, F>(&self, input: &mut [T], compare: F)
+        where F: FnMut(&T, &T) -> Ordering
+    {
+        self.sort_by_observed(input, compare, &mut Unobserved);
+    }
+
+    /// Sort the vector using a comparator function, reporting the operations performed to
+    /// `observer` (see the `instrument` module).
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>;
+
+    /// Indices of the elements of the vector in sorted order, using the `PartialOrd` trait. The
+    /// vector itself is left untouched (see the `permutation` module).
+    fn argsort<T: PartialOrd>(&self, input: &[T]) -> Vec<usize> {
+        self.argsort_by(input, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+    }
+
+    /// Indices of the elements of the vector in sorted order, using a comparator function.
+    ///
+    /// The indices are sorted, comparing the elements they point to: the result is stable if the
+    /// algorithm is.
+    fn argsort_by<T, F>(&self, input: &[T], mut compare: F) -> Vec<usize>
+        where F: FnMut(&T, &T) -> Ordering
+    {
+        let mut indices: Vec<usize> = (0..input.len()).collect();
+        self.sort_by(&mut indices, |&a, &b| compare(&input[a], &input[b]));
+        indices
+    }
+
+    /// Does the algorithm preserve the relative order of items with equal values?
+    fn is_stable(&self) -> bool;
+
+    /// Does the algorithm sort without a temporary buffer proportional to the number of elements?
+    fn is_in_place(&self) -> bool {
+        self.allocation() != Allocation::Linear
+    }
+
+    /// Heap memory allocated while sorting.
+    fn allocation(&self) -> Allocation;
+
+    /// Time complexity (in number of comparisons).
+    fn complexity(&self) -> Complexity;
+}
+
+/// Bubble sort (see `simplesorts::bubble`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bubble;
+
+/// Insertion sort (see `simplesorts::insertion`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Insertion;
+
+/// Selection sort (see `simplesorts::selection`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Selection;
+
+/// Quicksort (see `efficientsorts::quick`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quick;
+
+/// Merge sort (see `efficientsorts::merge`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Merge;
+
+/// Heap sort (see `efficientsorts::heap`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Heap;
+
+/// Introsort (see `efficientsorts::intro`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Intro;
+
+/// Timsort (see `efficientsorts::tim`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tim;
+
+impl Sorter for Bubble {
+    fn name(&self) -> &'static str { "bubblesort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        bubble::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { true }
+    fn allocation(&self) -> Allocation { Allocation::None }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Linear, average: Order::Quadratic, worst: Order::Quadratic }
+    }
+}
+
+impl Sorter for Insertion {
+    fn name(&self) -> &'static str { "insertionsort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        insertion::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { true }
+    fn allocation(&self) -> Allocation { Allocation::None }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Linear, average: Order::Quadratic, worst: Order::Quadratic }
+    }
+}
+
+impl Sorter for Selection {
+    fn name(&self) -> &'static str { "selectionsort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        selection::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { false }
+    fn allocation(&self) -> Allocation { Allocation::None }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Quadratic, average: Order::Quadratic, worst: Order::Quadratic }
+    }
+}
+
+impl Sorter for Quick {
+    fn name(&self) -> &'static str { "quicksort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        quick::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { false }
+    // The three pivot candidates are stored in a small vector at every partitioning step.
+    fn allocation(&self) -> Allocation { Allocation::Constant }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Linearithmic, average: Order::Linearithmic,
+                     worst: Order::Quadratic }
+    }
+}
+
+impl Sorter for Merge {
+    fn name(&self) -> &'static str { "mergesort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        merge::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { true }
+    fn allocation(&self) -> Allocation { Allocation::Linear }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Linearithmic, average: Order::Linearithmic,
+                     worst: Order::Linearithmic }
+    }
+}
+
+impl Sorter for Heap {
+    fn name(&self) -> &'static str { "heapsort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        heap::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { false }
+    fn allocation(&self) -> Allocation { Allocation::None }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Linearithmic, average: Order::Linearithmic,
+                     worst: Order::Linearithmic }
+    }
+}
+
+impl Sorter for Intro {
+    fn name(&self) -> &'static str { "introsort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        intro::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { false }
+    // Uses quicksort's partitioning.
+    fn allocation(&self) -> Allocation { Allocation::Constant }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Linearithmic, average: Order::Linearithmic,
+                     worst: Order::Linearithmic }
+    }
+}
+
+impl Sorter for Tim {
+    fn name(&self) -> &'static str { "timsort" }
+    fn sort_by_observed<T, F, O>(&self, input: &mut [T], compare: F, observer: &mut O)
+        where F: FnMut(&T, &T) -> Ordering,
+              O: Observer<T>
+    {
+        tim::sort_by_observed(input, compare, observer)
+    }
+    fn is_stable(&self) -> bool { true }
+    fn allocation(&self) -> Allocation { Allocation::Linear }
+    fn complexity(&self) -> Complexity {
+        Complexity { best: Order::Linear, average: Order::Linearithmic,
+                     worst: Order::Linearithmic }
+    }
+}
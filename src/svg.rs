@@ -0,0 +1,293 @@
+//! SVG rendering of recorded sorts.
+//!
+//! The `svg` module draws the states of a vector while it is being sorted, from the events
+//! recorded by the `trace` module, as bar charts. Every comparison, swap and write gives a frame:
+//!
+//! * the bars compared are orange, the bars swapped or written are red,
+//! * the bars which reached their final location (they will not move anymore) are green,
+//! * the part of the vector being sorted by the current recursive call has a grey background.
+//!
+//! The frames are rendered either as separate SVG images or as a single animated SVG.
+//!
+//! # Examples
+//!
+//! ```
+//! use sorting::sorter::Quick;
+//! use sorting::{svg, trace};
+//!
+//! let initial: Vec<i32> = vec![4, 2, 3, 1, 5];
+//! let mut data = initial.clone();
+//! let events = trace::record(&Quick, &mut data, |a, b| a.cmp(b));
+//!
+//! let frames = svg::frames(&initial, &events);
+//! assert_eq!(frames.last().unwrap().ranks, vec![0, 1, 2, 3, 4]);
+//!
+//! let animation = svg::render_animation(&frames, &svg::Options::default());
+//! assert!(animation.starts_with("<svg"));
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+use trace::Event;
+
+/// Color of the bars not involved in the current operation.
+const COLOR_DEFAULT: &str = "#4c72b0";
+/// Color of the bars being compared.
+const COLOR_COMPARE: &str = "#dd8452";
+/// Color of the bars being swapped or written.
+const COLOR_MOVE: &str = "#c44e52";
+/// Color of the bars at their final location.
+const COLOR_DONE: &str = "#55a868";
+/// Background of the part of the vector being sorted by the current recursive call.
+const COLOR_RANGE: &str = "#e8e8e8";
+
+/// Size and speed of the rendering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// Width of the image, in pixels.
+    pub width: u32,
+    /// Height of the image, in pixels.
+    pub height: u32,
+    /// Duration of a frame in an animation, in seconds.
+    pub frame_duration: f64,
+    /// Number of frames the final (sorted) state is shown before the animation restarts.
+    pub final_frames: usize,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { width: 480, height: 240, frame_duration: 0.05, final_frames: 40 }
+    }
+}
+
+/// Operation shown by a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// Initial state, nothing highlighted.
+    None,
+    /// The elements at the two indices are compared.
+    Compare(usize, usize),
+    /// The elements at the two indices were swapped.
+    Swap(usize, usize),
+    /// An element was written at the index.
+    Write(usize),
+}
+
+/// State of the vector after an operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Rank of every element among the initial values (0 for the smallest), giving the height of
+    /// its bar. Equal elements have the same rank.
+    pub ranks: Vec<usize>,
+    /// Operation which led to this state.
+    pub highlight: Highlight,
+    /// Which elements are at their final location.
+    pub done: Vec<bool>,
+    /// Part of the vector being sorted by the current recursive call, if any.
+    pub range: Option<(usize, usize)>,
+}
+
+/// Compute the frames of a sort of `initial` which reported `events`.
+///
+/// # Details
+///
+/// The first frame is the initial state. The events are then replayed (see
+/// `trace::Event::apply()`) and every comparison, swap and write adds a frame. The last frame is
+/// thus the sorted vector.
+///
+pub fn frames<T: Clone + PartialOrd>(initial: &[T], events: &[Event<T>]) -> Vec<Frame> {
+    let mut sorted = initial.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let rank = |value: &T| sorted.partition_point(|x| x < value);
+
+    let mut ranks: Vec<usize> = initial.iter().map(&rank).collect();
+    let mut frames = vec![Frame { ranks: ranks.clone(), highlight: Highlight::None,
+                                  done: Vec::new(), range: None }];
+    let mut calls: Vec<(usize, usize)> = Vec::new();
+    // Frame after which every element does not move anymore.
+    let mut last_move = vec![0; initial.len()];
+
+    for event in events {
+        let highlight = match *event {
+            Event::Compare(i, j) => Highlight::Compare(i, j),
+            Event::Swap(i, j) => {
+                ranks.swap(i, j);
+                last_move[i] = frames.len();
+                last_move[j] = frames.len();
+                Highlight::Swap(i, j)
+            },
+            Event::Write(i, ref value) => {
+                ranks[i] = rank(value);
+                last_move[i] = frames.len();
+                Highlight::Write(i)
+            },
+            Event::Enter(start, end) => {
+                calls.push((start, end));
+                continue;
+            },
+            Event::Exit(..) => {
+                calls.pop();
+                continue;
+            },
+            _ => continue,
+        };
+        frames.push(Frame { ranks: ranks.clone(), highlight, done: Vec::new(),
+                            range: calls.last().cloned() });
+    }
+
+    for (f, frame) in frames.iter_mut().enumerate() {
+        frame.done = last_move.iter().map(|&last| f >= last).collect();
+    }
+    frames
+}
+
+/// Format a coordinate with at most two decimals.
+fn number(x: f64) -> String {
+    let formatted = format!("{:.2}", x);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Color of the bar at index `i` in `frame`.
+fn color(frame: &Frame, i: usize) -> &'static str {
+    match frame.highlight {
+        Highlight::Compare(a, b) if i == a || i == b => COLOR_COMPARE,
+        Highlight::Swap(a, b) if i == a || i == b    => COLOR_MOVE,
+        Highlight::Write(a) if i == a                => COLOR_MOVE,
+        _ if frame.done[i]                           => COLOR_DONE,
+        _                                            => COLOR_DEFAULT,
+    }
+}
+
+/// Geometry of the bars of a vector of `n` elements.
+struct Layout {
+    bar_width: f64,
+    gap: f64,
+    unit_height: f64,
+}
+
+impl Layout {
+    fn new(n: usize, options: &Options) -> Layout {
+        let n = n.max(1) as f64;
+        let bar_width = options.width as f64 / n;
+        Layout {
+            bar_width,
+            gap: if bar_width >= 4.0 { 1.0 } else { 0.0 },
+            unit_height: options.height as f64 / n,
+        }
+    }
+
+    fn x(&self, i: usize) -> String {
+        number(i as f64 * self.bar_width)
+    }
+
+    fn width(&self, count: usize) -> String {
+        number(count as f64 * self.bar_width - self.gap)
+    }
+
+    /// Width of the elements `start..end`, zero if there are none.
+    fn range_width(&self, (start, end): (usize, usize)) -> String {
+        if end > start { self.width(end - start) } else { "0".to_string() }
+    }
+
+    fn height(&self, rank: usize) -> String {
+        number((rank + 1) as f64 * self.unit_height)
+    }
+}
+
+/// Start of an SVG image, with a white background.
+fn header(svg: &mut String, options: &Options) {
+    writeln!(svg, concat!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" "#,
+                          r#"viewBox="0 0 {0} {1}">"#),
+             options.width, options.height).unwrap();
+    writeln!(svg, r#"<rect width="{}" height="{}" fill="white"/>"#,
+             options.width, options.height).unwrap();
+}
+
+/// Render a frame as an SVG image.
+pub fn render_frame(frame: &Frame, options: &Options) -> String {
+    let layout = Layout::new(frame.ranks.len(), options);
+    let mut svg = String::new();
+    header(&mut svg, options);
+
+    if let Some(range) = frame.range {
+        writeln!(svg, r#"<rect x="{}" width="{}" height="{}" fill="{}"/>"#,
+                 layout.x(range.0), layout.range_width(range), options.height, COLOR_RANGE).unwrap();
+    }
+
+    // Bars are drawn upward from the bottom of the image.
+    writeln!(svg, r#"<g transform="translate(0 {}) scale(1 -1)">"#, options.height).unwrap();
+    for (i, &rank) in frame.ranks.iter().enumerate() {
+        writeln!(svg, r#"<rect x="{}" width="{}" height="{}" fill="{}"/>"#,
+                 layout.x(i), layout.width(1), layout.height(rank), color(frame, i)).unwrap();
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+/// Discrete animation of an attribute, only listing the frames where its value changes.
+fn animate(svg: &mut String, attribute: &str, values: &[String], options: &Options) {
+    let nb_frames = values.len() + options.final_frames;
+    let mut changes: Vec<(usize, &str)> = Vec::new();
+    for (f, value) in values.iter().enumerate() {
+        if changes.last().is_none_or(|&(_, last)| last != value) {
+            changes.push((f, value));
+        }
+    }
+    if changes.len() < 2 {
+        return;
+    }
+
+    let key_times: Vec<String> = changes.iter()
+                                        .map(|&(f, _)| number(f as f64 / nb_frames as f64))
+                                        .collect();
+    let values: Vec<&str> = changes.iter().map(|&(_, value)| value).collect();
+    writeln!(svg, concat!(r#"<animate attributeName="{}" calcMode="discrete" dur="{}s" "#,
+                          r#"repeatCount="indefinite" keyTimes="{}" values="{}"/>"#),
+             attribute, number(nb_frames as f64 * options.frame_duration), key_times.join(";"),
+             values.join(";")).unwrap();
+}
+
+/// Render all the frames as a single animated SVG image, looping forever.
+///
+/// # Details
+///
+/// Every bar is animated (using SMIL) so only its changes are stored: the image stays small even
+/// with thousands of frames. Browsers render it, most image viewers only show the first frame.
+///
+pub fn render_animation(frames: &[Frame], options: &Options) -> String {
+    let n = frames.first().map_or(0, |frame| frame.ranks.len());
+    let layout = Layout::new(n, options);
+    let mut svg = String::new();
+    header(&mut svg, options);
+
+    // The background of the current recursive call, hidden when there is none.
+    if frames.iter().any(|frame| frame.range.is_some()) {
+        let range = |frame: &Frame| frame.range.unwrap_or((0, 0));
+        writeln!(svg, r#"<rect x="{}" width="{}" height="{}" fill="{}">"#,
+                 layout.x(range(&frames[0]).0), layout.range_width(range(&frames[0])),
+                 options.height, COLOR_RANGE).unwrap();
+        let xs: Vec<String> = frames.iter().map(|frame| layout.x(range(frame).0)).collect();
+        let widths: Vec<String> = frames.iter().map(|frame| layout.range_width(range(frame)))
+                                        .collect();
+        animate(&mut svg, "x", &xs, options);
+        animate(&mut svg, "width", &widths, options);
+        svg.push_str("</rect>\n");
+    }
+
+    writeln!(svg, r#"<g transform="translate(0 {}) scale(1 -1)">"#, options.height).unwrap();
+    for i in 0..n {
+        writeln!(svg, r#"<rect x="{}" width="{}" height="{}" fill="{}">"#,
+                 layout.x(i), layout.width(1), layout.height(frames[0].ranks[i]),
+                 color(&frames[0], i)).unwrap();
+        let heights: Vec<String> = frames.iter().map(|frame| layout.height(frame.ranks[i]))
+                                         .collect();
+        let colors: Vec<String> = frames.iter().map(|frame| color(frame, i).to_string())
+                                        .collect();
+        animate(&mut svg, "height", &heights, options);
+        animate(&mut svg, "fill", &colors, options);
+        svg.push_str("</rect>\n");
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
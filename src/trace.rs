@@ -0,0 +1,240 @@
+//! Recording of the operations of a sorting algorithm.
+//!
+//! The `trace` module records the operations an algorithm reports through its
+//! `sort_by_observed()` function (see the `instrument` module) as a sequence of `Event`s, for
+//! example to animate how quicksort partitions a vector or how heap sort sifts elements down.
+//!
+//! The events can be written as JSON lines so external tools can replay them: the first line
+//! holds the initial values, every following line one event.
+//!
+//! ```text
+//! {"event":"start","values":[2,1]}
+//! {"event":"compare","i":0,"j":1}
+//! {"event":"swap","i":0,"j":1}
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use sorting::sorter::Bubble;
+//! use sorting::trace::{self, Event};
+//!
+//! let initial: Vec<i32> = vec![2, 1];
+//! let mut data = initial.clone();
+//! let events = trace::record(&Bubble, &mut data, |a, b| a.cmp(b));
+//! assert_eq!(events, vec![Event::Compare(0, 1), Event::Swap(0, 1)]);
+//!
+//! let mut json = Vec::new();
+//! trace::write_json_lines(&mut json, &initial, &events).unwrap();
+//! let json = String::from_utf8(json).unwrap();
+//! assert_eq!(json.lines().last(), Some(r#"{"event":"swap","i":0,"j":1}"#));
+//! ```
+
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+use instrument::Observer;
+use sorter::Sorter;
+
+/// An operation reported by an algorithm (see `instrument::Observer` for the details).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T> {
+    /// The elements at the two indices are compared.
+    Compare(usize, usize),
+    /// The elements at the two indices are swapped.
+    Swap(usize, usize),
+    /// The element is moved to the index.
+    Write(usize, T),
+    /// The elements starting at the first index are moved to a temporary buffer (start, length).
+    MoveToBuffer(usize, usize),
+    /// A temporary buffer of the given number of elements is allocated.
+    Allocate(usize),
+    /// The elements were partitioned around a pivot (start, pivot, end).
+    Partition(usize, usize, usize),
+    /// A recursive call starts sorting the elements (start, end).
+    Enter(usize, usize),
+    /// A recursive call sorting the elements (start, end) returns.
+    Exit(usize, usize),
+}
+
+impl<T: Clone> Event<T> {
+    /// Apply the event to `array`: swaps and writes modify it, the other events do not.
+    ///
+    /// Applying all the events of a sort, in order, to a copy of the initial vector gives the
+    /// sorted vector. The elements moved to a temporary buffer stay in `array` until written back.
+    pub fn apply(&self, array: &mut [T]) {
+        match *self {
+            Event::Swap(i, j)          => array.swap(i, j),
+            Event::Write(i, ref value) => array[i] = value.clone(),
+            _                          => {},
+        }
+    }
+}
+
+impl<T: JsonValue> Event<T> {
+    /// Serialize the event as a JSON object, on a single line.
+    pub fn to_json(&self) -> String {
+        match *self {
+            Event::Compare(i, j) => format!(r#"{{"event":"compare","i":{},"j":{}}}"#, i, j),
+            Event::Swap(i, j)    => format!(r#"{{"event":"swap","i":{},"j":{}}}"#, i, j),
+            Event::Write(i, ref value) => {
+                format!(r#"{{"event":"write","i":{},"value":{}}}"#, i, value.to_json())
+            },
+            Event::MoveToBuffer(start, len) => {
+                format!(r#"{{"event":"move_to_buffer","start":{},"len":{}}}"#, start, len)
+            },
+            Event::Allocate(len) => format!(r#"{{"event":"allocate","len":{}}}"#, len),
+            Event::Partition(start, pivot, end) => {
+                format!(r#"{{"event":"partition","start":{},"pivot":{},"end":{}}}"#,
+                        start, pivot, end)
+            },
+            Event::Enter(start, end) => {
+                format!(r#"{{"event":"enter","start":{},"end":{}}}"#, start, end)
+            },
+            Event::Exit(start, end) => {
+                format!(r#"{{"event":"exit","start":{},"end":{}}}"#, start, end)
+            },
+        }
+    }
+}
+
+/// Observer recording all the operations as `Event`s.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder<T> {
+    /// The events recorded, in order.
+    pub events: Vec<Event<T>>,
+}
+
+impl<T> Recorder<T> {
+    /// Create a recorder without any event.
+    pub fn new() -> Recorder<T> {
+        Recorder { events: Vec::new() }
+    }
+}
+
+impl<T: Clone> Observer<T> for Recorder<T> {
+    fn compare(&mut self, i: usize, j: usize) {
+        self.events.push(Event::Compare(i, j));
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.events.push(Event::Swap(i, j));
+    }
+
+    fn write(&mut self, i: usize, value: &T) {
+        self.events.push(Event::Write(i, value.clone()));
+    }
+
+    fn move_to_buffer(&mut self, start: usize, len: usize) {
+        self.events.push(Event::MoveToBuffer(start, len));
+    }
+
+    fn allocate(&mut self, len: usize) {
+        self.events.push(Event::Allocate(len));
+    }
+
+    fn partition(&mut self, start: usize, pivot: usize, end: usize) {
+        self.events.push(Event::Partition(start, pivot, end));
+    }
+
+    fn enter(&mut self, start: usize, end: usize) {
+        self.events.push(Event::Enter(start, end));
+    }
+
+    fn exit(&mut self, start: usize, end: usize) {
+        self.events.push(Event::Exit(start, end));
+    }
+}
+
+/// Sort `input` with `sorter` using `compare`, recording the operations performed.
+pub fn record<S, T, F>(sorter: &S, input: &mut [T], compare: F) -> Vec<Event<T>>
+    where S: Sorter,
+          T: Clone,
+          F: FnMut(&T, &T) -> Ordering
+{
+    let mut recorder = Recorder::new();
+    sorter.sort_by_observed(input, compare, &mut recorder);
+    recorder.events
+}
+
+/// Write the `initial` values and the `events` of a sort as JSON lines.
+pub fn write_json_lines<W, T>(mut writer: W, initial: &[T], events: &[Event<T>]) -> io::Result<()>
+    where W: Write,
+          T: JsonValue
+{
+    let values: Vec<String> = initial.iter().map(|value| value.to_json()).collect();
+    writeln!(writer, r#"{{"event":"start","values":[{}]}}"#, values.join(","))?;
+    for event in events {
+        writeln!(writer, "{}", event.to_json())?;
+    }
+    Ok(())
+}
+
+/// Values which can be serialized as JSON.
+pub trait JsonValue {
+    /// Serialize the value.
+    fn to_json(&self) -> String;
+}
+
+macro_rules! impl_json_value_display {
+    ($($t:ty),*) => {$(
+        impl JsonValue for $t {
+            fn to_json(&self) -> String {
+                self.to_string()
+            }
+        }
+    )*}
+}
+
+impl_json_value_display!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, bool);
+
+macro_rules! impl_json_value_float {
+    ($($t:ty),*) => {$(
+        impl JsonValue for $t {
+            /// NaNs and infinities are not valid JSON numbers and are written as `null`.
+            fn to_json(&self) -> String {
+                if self.is_finite() { self.to_string() } else { "null".to_string() }
+            }
+        }
+    )*}
+}
+
+impl_json_value_float!(f32, f64);
+
+impl JsonValue for str {
+    fn to_json(&self) -> String {
+        let mut json = String::with_capacity(self.len() + 2);
+        json.push('"');
+        for c in self.chars() {
+            match c {
+                '"'  => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+        json
+    }
+}
+
+impl JsonValue for String {
+    fn to_json(&self) -> String {
+        self.as_str().to_json()
+    }
+}
+
+impl JsonValue for char {
+    fn to_json(&self) -> String {
+        self.to_string().to_json()
+    }
+}
+
+impl<T: JsonValue + ?Sized> JsonValue for &T {
+    fn to_json(&self) -> String {
+        (**self).to_json()
+    }
+}
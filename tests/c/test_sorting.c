@@ -0,0 +1,208 @@
+/* Sort every supported type through the C interface of the library.
+ *
+ * Built and run by the `c_interface` test of tests/lib.rs, against the generated `sorting.h`.
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "sorting.h"
+
+#define N 64
+
+static int failures = 0;
+
+#define CHECK(condition)                                                        \
+    do {                                                                        \
+        if (!(condition)) {                                                     \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,     \
+                    #condition);                                                \
+            failures++;                                                         \
+        }                                                                       \
+    } while (0)
+
+/* Arguments of the functions sorting integers and floating point values. */
+#define INTEGER_ARGUMENTS(array, n) (array, n)
+#define FLOAT_ARGUMENTS(array, n) (array, n, SORTING_NAN_POLICY_LAST)
+
+/* Fill an array with pseudo-random values (negative ones for signed types), sort it with
+ * `function`, called with `ARGUMENTS`, and verify the result. */
+#define TEST_TYPE(type, function, ARGUMENTS)                                    \
+    do {                                                                        \
+        type array[N];                                                          \
+        size_t i;                                                               \
+        for (i = 0; i < N; i++) {                                               \
+            array[i] = (type) ((rand() % 200) - 50);                            \
+        }                                                                       \
+        CHECK(function ARGUMENTS(array, N) == SORTING_STATUS_OK);               \
+        for (i = 1; i < N; i++) {                                               \
+            CHECK(array[i - 1] <= array[i]);                                    \
+        }                                                                       \
+        CHECK(function ARGUMENTS(NULL, 0) == SORTING_STATUS_OK);                \
+        CHECK(function ARGUMENTS(NULL, N) == SORTING_STATUS_NULL_POINTER);      \
+    } while (0)
+
+/* Test all the types sorted by an algorithm. */
+#define TEST_INTEGERS(algorithm)                                                \
+    do {                                                                        \
+        TEST_TYPE(int8_t,   ffi_##algorithm##_i8,  INTEGER_ARGUMENTS);          \
+        TEST_TYPE(int16_t,  ffi_##algorithm##_i16, INTEGER_ARGUMENTS);          \
+        TEST_TYPE(int32_t,  ffi_##algorithm##_i32, INTEGER_ARGUMENTS);          \
+        TEST_TYPE(int64_t,  ffi_##algorithm##_i64, INTEGER_ARGUMENTS);          \
+        TEST_TYPE(uint8_t,  ffi_##algorithm##_u8,  INTEGER_ARGUMENTS);          \
+        TEST_TYPE(uint16_t, ffi_##algorithm##_u16, INTEGER_ARGUMENTS);          \
+        TEST_TYPE(uint32_t, ffi_##algorithm##_u32, INTEGER_ARGUMENTS);          \
+        TEST_TYPE(uint64_t, ffi_##algorithm##_u64, INTEGER_ARGUMENTS);          \
+    } while (0)
+
+#define TEST_ALL_TYPES(algorithm)                                               \
+    do {                                                                        \
+        TEST_INTEGERS(algorithm);                                               \
+        TEST_TYPE(float,    ffi_##algorithm##_f32, FLOAT_ARGUMENTS);            \
+        TEST_TYPE(double,   ffi_##algorithm##_f64, FLOAT_ARGUMENTS);            \
+    } while (0)
+
+/* A record, sorted by key using the generic functions. */
+struct record {
+    int key;
+    double weight;
+};
+
+static int compare_records(const void *a, const void *b, void *ctx) {
+    const struct record *ra = a;
+    const struct record *rb = b;
+    (*(size_t *) ctx)++;
+    return (ra->key > rb->key) - (ra->key < rb->key);
+}
+
+typedef sorting_status (*generic_sort)(void *, size_t, size_t, sorting_comparator, void *);
+
+static void test_generic(generic_sort function) {
+    struct record records[N];
+    size_t i, calls = 0;
+    for (i = 0; i < N; i++) {
+        records[i].key = rand() % 10;
+        records[i].weight = (double) i;
+    }
+    CHECK(function(records, N, sizeof(struct record), compare_records, &calls) ==
+          SORTING_STATUS_OK);
+    CHECK(calls > 0);
+    for (i = 1; i < N; i++) {
+        CHECK(records[i - 1].key <= records[i].key);
+    }
+    CHECK(function(records, N, sizeof(struct record), NULL, &calls) ==
+          SORTING_STATUS_COMPARATOR_ERROR);
+}
+
+static void test_nan(void) {
+    double array[4] = {2.0, NAN, -1.0, 0.0};
+    CHECK(ffi_quicksort_f64(array, 4, SORTING_NAN_POLICY_LAST) == SORTING_STATUS_OK);
+    CHECK(array[0] == -1.0 && array[1] == 0.0 && array[2] == 2.0 && isnan(array[3]));
+    CHECK(ffi_quicksort_f64(array, 4, SORTING_NAN_POLICY_FIRST) == SORTING_STATUS_OK);
+    CHECK(isnan(array[0]) && array[1] == -1.0 && array[2] == 0.0 && array[3] == 2.0);
+    CHECK(ffi_quicksort_f64(array, 4, SORTING_NAN_POLICY_ERROR) ==
+          SORTING_STATUS_INVALID_ARGUMENT);
+    CHECK(isnan(array[0]) && array[1] == -1.0);
+}
+
+static void test_select(void) {
+    double array[5] = {3.0, 5.0, 1.0, 4.0, 2.0};
+    double result = 0.0;
+    double with_nan[3] = {2.0, NAN, -1.0};
+    CHECK(ffi_select_nth_f64(array, 5, 1, SORTING_NAN_POLICY_LAST) == SORTING_STATUS_OK);
+    CHECK(array[1] == 2.0);
+    CHECK(ffi_select_nth_f64(array, 5, 5, SORTING_NAN_POLICY_LAST) ==
+          SORTING_STATUS_INVALID_ARGUMENT);
+    CHECK(ffi_select_nth_f64(with_nan, 3, 0, SORTING_NAN_POLICY_FIRST) == SORTING_STATUS_OK);
+    CHECK(isnan(with_nan[0]));
+    CHECK(ffi_select_nth_f64(with_nan, 3, 0, SORTING_NAN_POLICY_ERROR) ==
+          SORTING_STATUS_INVALID_ARGUMENT);
+    CHECK(ffi_median_f64(array, 5, &result) == SORTING_STATUS_OK);
+    CHECK(result == 3.0);
+    CHECK(ffi_percentile_f64(array, 5, 62.5, &result) == SORTING_STATUS_OK);
+    CHECK(result == 3.5);
+    CHECK(ffi_percentile_f64(array, 5, 101.0, &result) == SORTING_STATUS_INVALID_ARGUMENT);
+}
+
+static void test_argsort(void) {
+    const int32_t array[4] = {30, 10, 20, 10};
+    size_t indices[4] = {0};
+    CHECK(ffi_argsort_i32(array, 4, indices) == SORTING_STATUS_OK);
+    CHECK(indices[0] == 1 && indices[1] == 3 && indices[2] == 2 && indices[3] == 0);
+    CHECK(ffi_argsort_i32(array, 4, NULL) == SORTING_STATUS_NULL_POINTER);
+
+    const float with_nan[3] = {2.0f, NAN, -1.0f};
+    CHECK(ffi_argsort_f32(with_nan, 3, indices, SORTING_NAN_POLICY_LAST) == SORTING_STATUS_OK);
+    CHECK(indices[0] == 2 && indices[1] == 0 && indices[2] == 1);
+    CHECK(ffi_argsort_f32(with_nan, 3, indices, SORTING_NAN_POLICY_ERROR) ==
+          SORTING_STATUS_INVALID_ARGUMENT);
+}
+
+static void test_payload(void) {
+    int32_t keys[4] = {30, 10, 20, 40};
+    struct record values[4] = {{30, 3.0}, {10, 1.0}, {20, 2.0}, {40, 4.0}};
+    CHECK(ffi_mergesort_payload_i32(keys, 4, values, sizeof(struct record)) == SORTING_STATUS_OK);
+    for (size_t i = 0; i < 4; i++) {
+        CHECK(keys[i] == (int32_t) (10 * (i + 1)) && values[i].key == keys[i]);
+    }
+    CHECK(ffi_quicksort_payload_i32(keys, 4, NULL, sizeof(struct record)) ==
+          SORTING_STATUS_NULL_POINTER);
+
+    double float_keys[3] = {NAN, 2.0, 1.0};
+    int16_t float_values[3] = {0, 2, 1};
+    CHECK(ffi_heapsort_payload_f64(float_keys, 3, float_values, sizeof(int16_t),
+                                   SORTING_NAN_POLICY_FIRST) == SORTING_STATUS_OK);
+    CHECK(isnan(float_keys[0]) && float_values[0] == 0 && float_values[2] == 2);
+    CHECK(ffi_heapsort_payload_f64(float_keys, 3, float_values, sizeof(int16_t),
+                                   SORTING_NAN_POLICY_ERROR) == SORTING_STATUS_INVALID_ARGUMENT);
+}
+
+static void test_lexsort(void) {
+    const uint32_t days[4] = {2, 1, 2, 1};
+    const double scores[4] = {0.5, 0.2, 0.9, 0.7};
+    const void *const columns[2] = {days, scores};
+    const int types[2] = {SORTING_COLUMN_TYPE_U32, SORTING_COLUMN_TYPE_F64};
+    const int descending[2] = {0, 1};
+    size_t indices[4] = {0};
+    CHECK(ffi_lexsort(columns, types, descending, 2, 4, indices) == SORTING_STATUS_OK);
+    CHECK(indices[0] == 3 && indices[1] == 1 && indices[2] == 2 && indices[3] == 0);
+    CHECK(ffi_lexsort(columns, types, NULL, 0, 4, indices) == SORTING_STATUS_INVALID_ARGUMENT);
+}
+
+int main(void) {
+    srand(42);
+
+    TEST_ALL_TYPES(sort);
+    TEST_ALL_TYPES(bubblesort);
+    TEST_ALL_TYPES(insertionsort);
+    TEST_ALL_TYPES(selectionsort);
+    TEST_ALL_TYPES(quicksort);
+    TEST_ALL_TYPES(mergesort);
+    TEST_ALL_TYPES(heapsort);
+    TEST_ALL_TYPES(introsort);
+    TEST_ALL_TYPES(timsort);
+    TEST_INTEGERS(radixsort);
+
+    test_generic(ffi_bubblesort_generic);
+    test_generic(ffi_insertionsort_generic);
+    test_generic(ffi_selectionsort_generic);
+    test_generic(ffi_quicksort_generic);
+    test_generic(ffi_mergesort_generic);
+    test_generic(ffi_heapsort_generic);
+    test_generic(ffi_introsort_generic);
+    test_generic(ffi_timsort_generic);
+
+    test_nan();
+    test_select();
+    test_argsort();
+    test_payload();
+    test_lexsort();
+
+    if (failures != 0) {
+        fprintf(stderr, "%d check(s) failed\n", failures);
+        return EXIT_FAILURE;
+    }
+    printf("All checks passed\n");
+    return EXIT_SUCCESS;
+}
@@ -1,27 +1,38 @@
+extern crate libc;
 extern crate rand;
 
 extern crate sorting;
 
 use rand::Rng;
 
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::panic;
+use std::path::Path;
+use std::process::Command;
+use std::rc::Rc;
+use std::{f32, f64};
+
+use sorting::ffi;
+use sorting::floats::{self, Float, NanError, NanPolicy};
+use sorting::instrument::{self, Observer};
+use sorting::lexsort::{ffi_lexsort, lexsort, Direction, SortKey};
+use sorting::patterns::{self, Pattern};
+use sorting::registry;
+use sorting::sorter::{Allocation, Order, Selection, Sorter, Tim};
+use sorting::svg::{self, Highlight};
+use sorting::trace::{self, Event};
 
 
-const TO_SORT_I8:  [i8;  16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
-const TO_SORT_I16: [i16; 16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
 const TO_SORT_I32: [i32; 16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
 const TO_SORT_I64: [i64; 16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
-const TO_SORT_U8:  [u8;  16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
 const TO_SORT_U16: [u16; 16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
-const TO_SORT_U32: [u32; 16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
-const TO_SORT_U64: [u64; 16] = [6,  5,  3,  1,  2, 4, 10, 7, 3, 32, 44, 56, 67, 3, 44, 2];
 
-const TO_SORT_F32: [f32; 16] = [6.0,   5.0,  3.0,  1.0,  2.4, 4.0, 10.0, 7.0,
-                                3.42, 32.2, 44.2, 56.3, 67.9, 3.2, 44.2, 2.0];
 const TO_SORT_F64: [f64; 16] = [6.0,   5.0,  3.0,  1.0,  2.4, 4.0, 10.0, 7.0,
                                 3.42, 32.2, 44.2, 56.3, 67.9, 3.2, 44.2, 2.0];
 
 
-
 /// Verify input vector as being sorted.
 ///
 /// # Details
@@ -53,12 +64,6 @@ fn verify_sorted<T: PartialOrd>(array: &[T]) {
 }
 
 
-fn test_sort_vec<T: PartialOrd>(to_sort: &mut [T], sorting_fct: fn(&mut [T])) {
-    sorting_fct(to_sort);
-    verify_sorted(to_sort);
-}
-
-
 /// Call the specified sorting function on an empty vector of specific type.
 ///
 /// # Details
@@ -84,1741 +89,2438 @@ fn test_empty_vec<T: PartialOrd>(sorting_fct: fn(&mut [T])) {
 }
 
 
-// ################################################################################################
-// ################################################################################################
-// Simple sorts: Bubble sort
-
-/// Validate against empty vector (isize).
-#[test]
-fn simple_bubble_empty_vec_isize() {
-    test_empty_vec::<isize>(sorting::simplesorts::bubble::sort);
+/// Verify input vector as being sorted according to a comparator.
+///
+/// # Details
+///
+/// Same as `verify_sorted()` but the elements are compared using the `compare` closure: no element
+/// must compare as `Ordering::Greater` than the next one.
+///
+/// # Panics
+///
+/// The sorting of the data vector is enforced using `assert!()`.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<i32> = vec![4, 3, 2, 1];
+/// verify_sorted_by(&data, |a, b| b.cmp(a));
+/// ```
+///
+fn verify_sorted_by<T, F>(array: &[T], mut compare: F)
+    where F: FnMut(&T, &T) -> Ordering
+{
+    assert!(array.windows(2).all(|w| compare(&w[0], &w[1]) != Ordering::Greater));
 }
 
-/// Validate against empty vector (isize).
-#[test]
-fn simple_bubble_empty_vec_usize() {
-    test_empty_vec::<usize>(sorting::simplesorts::bubble::sort);
-}
 
-/// Validate sorting of a const vector (i8).
-#[test]
-fn simple_bubble_vec_i8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I8[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Record used to validate sorting on a field through `sort_by_key()`.
+#[derive(Debug, Clone, PartialEq)]
+struct Person {
+    name: &'static str,
+    age: u32,
 }
 
-/// Validate sorting of a const vector (i16).
-#[test]
-fn simple_bubble_vec_i16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I16[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn people() -> Vec<Person> {
+    vec![Person { name: "Carol", age: 35 },
+         Person { name: "Alice", age: 52 },
+         Person { name: "Dave",  age: 19 },
+         Person { name: "Bob",   age: 27 }]
 }
 
-/// Validate sorting of a const vector (i32).
-#[test]
-fn simple_bubble_vec_i32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
 
-/// Validate sorting of a const vector (i64).
-#[test]
-fn simple_bubble_vec_i64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I64[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Number of comparisons performed by a sorting function on a copy of `input`.
+fn count_comparisons<T, S>(input: &[T], sorting_fct: S) -> usize
+    where T: Ord + Clone,
+          S: FnOnce(&mut [T], &mut dyn FnMut(&T, &T) -> Ordering)
+{
+    let mut to_sort = input.to_vec();
+    let mut comparisons = 0;
+    sorting_fct(&mut to_sort, &mut |a: &T, b: &T| { comparisons += 1; a.cmp(b) });
+    comparisons
 }
 
-/// Validate sorting of a const vector (u8).
-#[test]
-fn simple_bubble_vec_u8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U8[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
 
-/// Validate sorting of a const vector (u16).
-#[test]
-fn simple_bubble_vec_u16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Pair every key with its index in the vector, to verify stability.
+///
+/// # Details
+///
+/// Once the pairs are sorted by key only, `verify_stable()` checks the indices of equal keys are
+/// still increasing.
+///
+/// # Examples
+///
+/// ```
+/// let mut records = with_indices(&[2, 1, 2]);
+/// sorting::efficientsorts::merge::sort_by_key(&mut records, |record| record.0);
+/// verify_stable(&records);
+/// ```
+///
+fn with_indices<K: Clone>(keys: &[K]) -> Vec<(K, usize)> {
+    keys.iter().cloned().enumerate().map(|(i, key)| (key, i)).collect()
 }
 
-/// Validate sorting of a const vector (u32).
-#[test]
-fn simple_bubble_vec_u32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U32[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Is every element of `records`, sorted by key, before the following ones with the same key?
+fn is_stable_order<K: PartialEq>(records: &[(K, usize)]) -> bool {
+    records.windows(2).all(|w| w[0].0 != w[1].0 || w[0].1 < w[1].1)
 }
 
-/// Validate sorting of a const vector (u64).
-#[test]
-fn simple_bubble_vec_u64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U64[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Verify `(key, index)` pairs (see `with_indices()`) as being sorted by key by a stable sort.
+///
+/// # Panics
+///
+/// The ordering of the keys and of the indices of equal keys is enforced using `assert!()`.
+///
+fn verify_stable<K: PartialOrd + Debug>(records: &[(K, usize)]) {
+    assert!(records.windows(2).all(|w| w[0].0 <= w[1].0), "not ordered: {:?}", records);
+    assert!(is_stable_order(records), "not stable: {:?}", records);
 }
 
 
-/// Validate sorting of a const vector (f32).
-#[test]
-fn simple_bubble_vec_f32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F32[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+// ################################################################################################
+// ################################################################################################
+// Conformance
 
-/// Validate sorting of a const vector (f64).
-#[test]
-fn simple_bubble_vec_f64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F64[..]);
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
 
+/// Longest random vector sorted by the conformance suite.
+const CONFORMANCE_MAX_LEN: usize = 10_000;
 
-/// Validate sorting of a random vector (i8).
-#[test]
-fn simple_bubble_rand_vec_i8() {
-    let len = 50;
-    let mut to_sort: Vec<i8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i8>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Longest random vector sorted by the algorithms which are quadratic in the worst case.
+const CONFORMANCE_MAX_LEN_QUADRATIC: usize = 500;
+
+/// Longest random vector the conformance suite sorts with `sorter`.
+fn conformance_max_len<S: Sorter>(sorter: &S) -> usize {
+    if sorter.complexity().worst == Order::Quadratic {
+        CONFORMANCE_MAX_LEN_QUADRATIC
+    } else {
+        CONFORMANCE_MAX_LEN
+    }
 }
 
-/// Validate sorting of a random vector (i16).
-#[test]
-fn simple_bubble_rand_vec_i16() {
-    let len = 50;
-    let mut to_sort: Vec<i16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i16>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Longest vector of `pattern` generated by the conformance suite, at most `max_len`: generating
+/// the median-of-3 killer runs quicksort on the worst case, which is quadratic.
+fn conformance_pattern_max_len(pattern: Pattern, max_len: usize) -> usize {
+    if pattern == Pattern::MedianOf3Killer {
+        max_len.min(CONFORMANCE_MAX_LEN_QUADRATIC)
+    } else {
+        max_len
+    }
 }
 
-/// Validate sorting of a random vector (i32).
-#[test]
-fn simple_bubble_rand_vec_i32() {
-    let len = 50;
-    let mut to_sort: Vec<i32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i32>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Verify a sorting function on vectors of every pattern.
+///
+/// # Details
+///
+/// For every pattern of the `patterns` module, vectors of lengths 0 to 3 and of random lengths up
+/// to `max_len` are generated (with random seeds) and sorted. The result must be:
+///
+/// * ordered,
+/// * a permutation of the input (the same multiset of values),
+/// * left untouched when sorted again.
+///
+/// # Panics
+///
+/// The properties are enforced using `assert!()`. The messages give the pattern, length and seed
+/// to reproduce a failure.
+///
+fn conformance<T>(sorting_fct: fn(&mut [T]), max_len: usize)
+    where T: patterns::Element + PartialOrd + Debug
+{
+    let mut rng = rand::thread_rng();
+    for &pattern in patterns::all() {
+        let max_len = conformance_pattern_max_len(pattern, max_len);
+        let mut lengths = vec![0, 1, 2, 3];
+        lengths.extend((0..3).map(|_| rng.gen_range(0, max_len + 1)));
+        for &n in &lengths {
+            let seed = rng.gen::<u64>();
+            let context = format!("pattern {}, n = {}, seed = {}", pattern, n, seed);
+            let input: Vec<T> = patterns::generate(pattern, n, seed);
+
+            let mut to_sort = input.clone();
+            sorting_fct(&mut to_sort);
+            assert!(to_sort.windows(2).all(|w| w[0] <= w[1]), "not ordered: {}", context);
+
+            let mut expected = input;
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!(to_sort == expected, "not a permutation of the input: {}", context);
+
+            sorting_fct(&mut to_sort);
+            assert!(to_sort == expected, "not idempotent: {}", context);
+        }
+    }
 }
 
-/// Validate sorting of a random vector (i64).
-#[test]
-fn simple_bubble_rand_vec_i64() {
-    let len = 50;
-    let mut to_sort: Vec<i64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i64>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Verify `sorter` keeps equal elements in their initial order if it claims to be stable.
+///
+/// # Details
+///
+/// Records `(key, index)` (see `with_indices()`) of every pattern, with random lengths, are sorted
+/// by key only. The keys must be ordered and the records a permutation of the input. If
+/// `Sorter::is_stable()`, the indices of equal keys must also be increasing.
+///
+fn conformance_stability<S: Sorter>(sorter: &S) {
+    let max_len = conformance_max_len(sorter);
+    let mut rng = rand::thread_rng();
+    for &pattern in patterns::all() {
+        let n = rng.gen_range(0, conformance_pattern_max_len(pattern, max_len) + 1);
+        let seed = rng.gen::<u64>();
+        let context = format!("pattern {}, n = {}, seed = {}", pattern, n, seed);
+        let keys: Vec<u16> = patterns::generate(pattern, n, seed);
+        let mut to_sort = with_indices(&keys);
+        sorter.sort_by(&mut to_sort, |a, b| a.0.cmp(&b.0));
+        assert!(to_sort.windows(2).all(|w| w[0].0 <= w[1].0), "not ordered: {}", context);
+        if sorter.is_stable() {
+            assert!(is_stable_order(&to_sort), "not stable: {}", context);
+        }
+
+        let mut expected = with_indices(&keys);
+        expected.sort();
+        to_sort.sort();
+        assert!(to_sort == expected, "not a permutation of the input: {}", context);
+    }
 }
 
-/// Validate sorting of a random vector (u8).
-#[test]
-fn simple_bubble_rand_vec_u8() {
-    let len = 50;
-    let mut to_sort: Vec<u8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u8>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+const FLOATS_F32: [f32; 12] = [3.0, f32::NAN, -0.0, f32::INFINITY, 0.0, f32::NEG_INFINITY,
+                               -f32::NAN, 1.5, -2.5, 0.0, f32::NAN, -1.0];
+const FLOATS_F64: [f64; 12] = [3.0, f64::NAN, -0.0, f64::INFINITY, 0.0, f64::NEG_INFINITY,
+                               -f64::NAN, 1.5, -2.5, 0.0, f64::NAN, -1.0];
+
+/// Verify the elements of both vectors are the same, in the same order (NaNs and signed zeros
+/// included).
+fn verify_identical<T: Float + Debug>(array: &[T], expected: &[T]) {
+    assert_eq!(array.len(), expected.len());
+    for (a, b) in array.iter().zip(expected.iter()) {
+        assert!(a.total_cmp(b) == Ordering::Equal, "{:?} != {:?}", array, expected);
+    }
 }
 
-/// Validate sorting of a random vector (u16).
-#[test]
-fn simple_bubble_rand_vec_u16() {
-    let len = 50;
-    let mut to_sort: Vec<u16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u16>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Verify a floating point sorting function with every NaN policy.
+///
+/// # Details
+///
+/// The sorted vector must be in IEEE 754 total order, except for the location of the NaNs that
+/// depends on the policy. With `NanPolicy::Error`, the function must fail on a vector with NaNs
+/// (leaving it untouched) and succeed once the NaNs are removed.
+///
+/// # Panics
+///
+/// The validation is enforced using `assert!()`.
+///
+fn conformance_floats<T: Float + Debug>(values: &[T],
+                                        sorting_fct: fn(&mut [T], NanPolicy)
+                                                        -> Result<(), NanError>) {
+    let mut total_order = values.to_vec();
+    total_order.sort_by(|a, b| a.total_cmp(b));
+    let nans: Vec<T> = values.iter().cloned().filter(|x| x.is_nan()).collect();
+    let numbers: Vec<T> = total_order.iter().cloned().filter(|x| !x.is_nan()).collect();
+
+    let mut to_sort = values.to_vec();
+    sorting_fct(&mut to_sort, NanPolicy::TotalOrder).unwrap();
+    verify_identical(&to_sort, &total_order);
+
+    let mut to_sort = values.to_vec();
+    sorting_fct(&mut to_sort, NanPolicy::First).unwrap();
+    assert!(to_sort[..nans.len()].iter().all(|x| x.is_nan()));
+    verify_identical(&to_sort[nans.len()..], &numbers);
+
+    let mut to_sort = values.to_vec();
+    sorting_fct(&mut to_sort, NanPolicy::Last).unwrap();
+    verify_identical(&to_sort[..numbers.len()], &numbers);
+    assert!(to_sort[numbers.len()..].iter().all(|x| x.is_nan()));
+
+    let mut to_sort = values.to_vec();
+    let first_nan = values.iter().position(|x| x.is_nan()).unwrap();
+    assert_eq!(sorting_fct(&mut to_sort, NanPolicy::Error), Err(NanError { index: first_nan }));
+    verify_identical(&to_sort, values);
+
+    let mut to_sort: Vec<T> = values.iter().cloned().filter(|x| !x.is_nan()).collect();
+    sorting_fct(&mut to_sort, NanPolicy::Error).unwrap();
+    verify_identical(&to_sort, &numbers);
+}
+
+/// Verify `sorter` sorts in descending order through a reversed comparator.
+///
+/// # Details
+///
+/// Integers and floating points of every pattern, with random lengths, are sorted using
+/// `Sorter::sort_by()` and must match the descending order given by the standard library.
+///
+fn conformance_descending<S: Sorter>(sorter: &S) {
+    let max_len = conformance_max_len(sorter);
+    let mut rng = rand::thread_rng();
+    for &pattern in patterns::all() {
+        let n = rng.gen_range(0, conformance_pattern_max_len(pattern, max_len) + 1);
+        let seed = rng.gen::<u64>();
+        let context = format!("pattern {}, n = {}, seed = {}", pattern, n, seed);
+
+        let mut to_sort: Vec<i32> = patterns::generate(pattern, n, seed);
+        let mut expected = to_sort.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        sorter.sort_by(&mut to_sort, |a, b| b.cmp(a));
+        assert!(to_sort == expected, "not in descending order: {}", context);
+
+        let mut to_sort: Vec<f64> = patterns::generate(pattern, n, seed);
+        let mut expected = to_sort.clone();
+        expected.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        sorter.sort_by(&mut to_sort, |a, b| b.partial_cmp(a).unwrap());
+        assert!(to_sort == expected, "not in descending order (f64): {}", context);
+    }
 }
 
-/// Validate sorting of a random vector (u32).
-#[test]
-fn simple_bubble_rand_vec_u32() {
-    let len = 50;
-    let mut to_sort: Vec<u32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u32>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Generate a conformance test per element type, calling the given function of `algorithm` (see
+/// `conformance()`).
+macro_rules! conformance_types {
+    ($sort:ident; $max_len:expr; $($t:ident),*) => {$(
+        /// Validate ordering, multiset preservation and idempotence.
+        #[test]
+        fn $t() {
+            conformance::<$t>(algorithm::$sort::<$t>, $max_len);
+        }
+    )*}
+}
+
+/// Generate the conformance tests of an algorithm of the `Sorter` trait, in a module of the same
+/// name, on all numeric types.
+macro_rules! conformance_sorter {
+    ($($name:ident: $group:ident::$module:ident, $sorter:expr;)*) => {$(
+        mod $name {
+            use super::*;
+            use sorting::$group::$module as algorithm;
+
+            conformance_types!(sort; conformance_max_len(&$sorter);
+                               i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+            /// Validate the stability claimed by `Sorter::is_stable()`.
+            #[test]
+            fn stability() {
+                conformance_stability(&$sorter);
+            }
+
+            /// Validate descending sorting through a comparator.
+            #[test]
+            fn sort_by_descending() {
+                conformance_descending(&$sorter);
+            }
+
+            /// Validate sorting structs on a field.
+            #[test]
+            fn sort_by_key() {
+                let mut to_sort = people();
+                algorithm::sort_by_key(&mut to_sort, |p| p.age);
+                let names: Vec<_> = to_sort.iter().map(|p| p.name).collect();
+                assert_eq!(names, vec!["Dave", "Bob", "Carol", "Alice"]);
+            }
+
+            /// Validate sorting special floating point values with every NaN policy.
+            #[test]
+            fn sort_floats() {
+                conformance_floats(&FLOATS_F32, algorithm::sort_floats);
+                conformance_floats(&FLOATS_F64, algorithm::sort_floats);
+            }
+        }
+    )*}
 }
 
-/// Validate sorting of a random vector (u64).
-#[test]
-fn simple_bubble_rand_vec_u64() {
-    let len = 50;
-    let mut to_sort: Vec<u64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u64>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+conformance_sorter! {
+    conformance_bubble:    simplesorts::bubble,       sorting::sorter::Bubble;
+    conformance_insertion: simplesorts::insertion,    sorting::sorter::Insertion;
+    conformance_selection: simplesorts::selection,    sorting::sorter::Selection;
+    conformance_heap:      efficientsorts::heap,      sorting::sorter::Heap;
+    conformance_merge:     efficientsorts::merge,     sorting::sorter::Merge;
+    conformance_quick:     efficientsorts::quick,     sorting::sorter::Quick;
+    conformance_intro:     efficientsorts::intro,     sorting::sorter::Intro;
+    conformance_tim:       efficientsorts::tim,       sorting::sorter::Tim;
 }
 
+/// Radix sort only sorts integers, LSD (`sort()`) and MSD (`sort_msd()`) variants.
+mod conformance_radix {
+    use super::*;
+    use sorting::efficientsorts::radix as algorithm;
 
-/// Validate sorting of a random vector (f32).
-#[test]
-fn simple_bubble_rand_vec_f32() {
-    let len = 50;
-    let mut to_sort: Vec<f32> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f32>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    conformance_types!(sort; CONFORMANCE_MAX_LEN;
+                       i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
 
-/// Validate sorting of a random vector (f64).
-#[test]
-fn simple_bubble_rand_vec_f64() {
-    let len = 50;
-    let mut to_sort: Vec<f64> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f64>>();
-    sorting::simplesorts::bubble::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+    /// The MSD variant.
+    mod msd {
+        use super::*;
+
+        conformance_types!(sort_msd; CONFORMANCE_MAX_LEN;
+                           i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+    }
 }
 
 
-/// Validate sorting of a sorted vector (isize).
-#[test]
-fn simple_bubble_sortedvec_isize() {
-    let mut to_sort: Vec<isize> = vec![1, 2, 3, 4, 5];
+// ################################################################################################
+// ################################################################################################
+// Stability
+
+/// Validate the stable algorithms keep equal keys in order, with `sort_by()` and `sort_by_key()`.
+#[test]
+fn stability_stable_algorithms() {
+    let keys: Vec<u8> = patterns::generate(Pattern::FewUnique(8), 300, 5);
+    for &algorithm in registry::all().iter().filter(|algorithm| algorithm.is_stable()) {
+        let mut records = with_indices(&keys);
+        algorithm.sort_by(&mut records, |a, b| a.0.cmp(&b.0));
+        verify_stable(&records);
+
+        let mut records = with_indices(&keys);
+        // In descending order, equal keys are still in their initial order: reversed, their
+        // indices are decreasing.
+        algorithm.sort_by(&mut records, |a, b| b.0.cmp(&a.0));
+        records.reverse();
+        assert!(records.windows(2).all(|w| w[0].0 != w[1].0 || w[0].1 > w[1].1), "{}", algorithm);
+    }
 
-    test_sort_vec::<isize>(&mut to_sort, sorting::simplesorts::bubble::sort);
+    let mut records = with_indices(&keys);
+    sorting::simplesorts::bubble::sort_by_key(&mut records, |record| record.0);
+    verify_stable(&records);
+    let mut records = with_indices(&keys);
+    sorting::simplesorts::insertion::sort_by_key(&mut records, |record| record.0);
+    verify_stable(&records);
+    let mut records = with_indices(&keys);
+    sorting::efficientsorts::merge::sort_by_key(&mut records, |record| record.0);
+    verify_stable(&records);
+    let mut records = with_indices(&keys);
+    sorting::efficientsorts::tim::sort_by_key(&mut records, |record| record.0);
+    verify_stable(&records);
+}
+
+/// Validate merge sort takes from the left half on ties (`merge()`), with equal keys split
+/// across both halves.
+#[test]
+fn stability_merge_ties() {
+    let keys: Vec<u8> = vec![1, 0, 1, 0, 1, 0, 1, 0, 1];
+    let mut records = with_indices(&keys);
+    sorting::efficientsorts::merge::sort_by_key(&mut records, |record| record.0);
+    assert_eq!(records, vec![(0, 1), (0, 3), (0, 5), (0, 7),
+                             (1, 0), (1, 2), (1, 4), (1, 6), (1, 8)]);
+}
+
+/// Validate the algorithms documented as not stable do reorder equal keys on some input.
+#[test]
+fn stability_unstable_algorithms() {
+    for &algorithm in registry::all().iter().filter(|algorithm| !algorithm.is_stable()) {
+        let unstable = (0..10).any(|seed| {
+            let keys: Vec<u8> = patterns::generate(Pattern::FewUnique(4), 100, seed);
+            let mut records = with_indices(&keys);
+            algorithm.sort_by(&mut records, |a, b| a.0.cmp(&b.0));
+            !is_stable_order(&records)
+        });
+        assert!(unstable, "{} is documented as not stable", algorithm);
+    }
 }
 
-/// Validate sorting of a sorted vector (usize).
+/// Validate the example of the selection sort documentation.
 #[test]
-fn simple_bubble_sortedvec_usize() {
-    let mut to_sort: Vec<usize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<usize>(&mut to_sort, sorting::simplesorts::bubble::sort);
+fn stability_selection_example() {
+    let mut to_sort = vec![(1, 'a'), (1, 'b'), (0, 'c')];
+    sorting::simplesorts::selection::sort_by_key(&mut to_sort, |record| record.0);
+    assert_eq!(to_sort, vec![(0, 'c'), (1, 'b'), (1, 'a')]);
 }
 
-/// Validate sorting of a sorted vector (i8).
-#[test]
-fn simple_bubble_sortedvec_i8() {
-    let mut to_sort: Vec<i8> = vec![1, 2, 3, 4, 5];
 
-    test_sort_vec::<i8>(&mut to_sort, sorting::simplesorts::bubble::sort);
+// ################################################################################################
+// ################################################################################################
+// Panic safety
+
+/// State shared by the `Tracked` elements of a vector.
+struct TrackedState {
+    /// Comparisons done so far.
+    comparisons: Cell<usize>,
+    /// Number of comparisons after which comparing panics.
+    panic_after: usize,
+    /// Number of times every element was dropped, by id.
+    drops: RefCell<Vec<usize>>,
+}
+
+impl TrackedState {
+    /// Count a comparison, panicking if there were already `panic_after` of them.
+    fn compare(&self) {
+        let comparisons = self.comparisons.get() + 1;
+        self.comparisons.set(comparisons);
+        if comparisons > self.panic_after {
+            panic!("comparison #{}", comparisons);
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (i16).
-#[test]
-fn simple_bubble_sortedvec_i16() {
-    let mut to_sort: Vec<i16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i16>(&mut to_sort, sorting::simplesorts::bubble::sort);
+/// Element counting its drops, whose comparison panics after a given number of comparisons.
+struct Tracked {
+    id: usize,
+    value: u8,
+    state: Rc<TrackedState>,
 }
 
-/// Validate sorting of a sorted vector (i32).
-#[test]
-fn simple_bubble_sortedvec_i32() {
-    let mut to_sort: Vec<i32> = vec![1, 2, 3, 4, 5];
+impl PartialEq for Tracked {
+    fn eq(&self, other: &Tracked) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
 
-    test_sort_vec::<i32>(&mut to_sort, sorting::simplesorts::bubble::sort);
+impl PartialOrd for Tracked {
+    fn partial_cmp(&self, other: &Tracked) -> Option<Ordering> {
+        self.state.compare();
+        self.value.partial_cmp(&other.value)
+    }
 }
 
-/// Validate sorting of a sorted vector (i64).
-#[test]
-fn simple_bubble_sortedvec_i64() {
-    let mut to_sort: Vec<i64> = vec![1, 2, 3, 4, 5];
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.state.drops.borrow_mut()[self.id] += 1;
+    }
+}
 
-    test_sort_vec::<i64>(&mut to_sort, sorting::simplesorts::bubble::sort);
+/// Sort `Tracked` elements with `sorting_fct`, the comparison panicking after `panic_after`
+/// comparisons, and verify the elements once the panic is caught.
+///
+/// # Details
+///
+/// The vector must still hold every element exactly once (a permutation of the initial ones) and
+/// no element must have been dropped. Once the vector is dropped, every element must have been
+/// dropped exactly once. Returns the number of comparisons done.
+///
+fn verify_panic_safety<F>(values: &[u8], panic_after: usize, sorting_fct: F) -> usize
+    where F: FnOnce(&mut [Tracked])
+{
+    let state = Rc::new(TrackedState {
+        comparisons: Cell::new(0),
+        panic_after,
+        drops: RefCell::new(vec![0; values.len()]),
+    });
+    let mut to_sort: Vec<Tracked> = values.iter().enumerate().map(|(id, &value)| {
+        Tracked { id, value, state: state.clone() }
+    }).collect();
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| sorting_fct(&mut to_sort)));
+    let comparisons = state.comparisons.get();
+    assert_eq!(result.is_err(), comparisons > panic_after);
+
+    let mut ids: Vec<usize> = to_sort.iter().map(|element| element.id).collect();
+    ids.sort();
+    assert_eq!(ids, (0..values.len()).collect::<Vec<usize>>());
+    assert!(state.drops.borrow().iter().all(|&drops| drops == 0));
+
+    drop(to_sort);
+    assert!(state.drops.borrow().iter().all(|&drops| drops == 1));
+    comparisons
+}
+
+/// Validate every algorithm leaves a permutation, without any drop, when `PartialOrd` panics.
+#[test]
+fn panic_safety_partial_ord() {
+    let values: Vec<u8> = patterns::generate(Pattern::FewUnique(16), 64, 11);
+    for &algorithm in registry::all() {
+        let total = verify_panic_safety(&values, usize::MAX, |to_sort| algorithm.sort(to_sort));
+        for panic_after in (0..total).step_by(total / 50 + 1) {
+            verify_panic_safety(&values, panic_after, |to_sort| algorithm.sort(to_sort));
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (u8).
+/// Validate every algorithm leaves a permutation, without any drop, when the comparator or the
+/// key function panics.
 #[test]
-fn simple_bubble_sortedvec_u8() {
-    let mut to_sort: Vec<u8> = vec![1, 2, 3, 4, 5];
+fn panic_safety_comparator() {
+    let values: Vec<u8> = patterns::generate(Pattern::Random, 100, 3);
+    for &algorithm in registry::all() {
+        let sort_by = |to_sort: &mut [Tracked]| {
+            algorithm.sort_by(to_sort, |a, b| { a.state.compare(); a.value.cmp(&b.value) })
+        };
+        let total = verify_panic_safety(&values, usize::MAX, sort_by);
+        for panic_after in (0..total).step_by(total / 50 + 1) {
+            verify_panic_safety(&values, panic_after, sort_by);
+        }
+    }
 
-    test_sort_vec::<u8>(&mut to_sort, sorting::simplesorts::bubble::sort);
+    let sort_by_key = |to_sort: &mut [Tracked]| {
+        sorting::efficientsorts::merge::sort_by_key(to_sort, |element| {
+            element.state.compare();
+            element.value
+        })
+    };
+    for panic_after in 0..200 {
+        verify_panic_safety(&values, panic_after, sort_by_key);
+    }
 }
 
-/// Validate sorting of a sorted vector (u16).
+
+// ################################################################################################
+// ################################################################################################
+// Efficient sorts: Merge sort
+
+
+/// Validate sorting a vector of heap allocated strings (String).
 #[test]
-fn simple_bubble_sortedvec_u16() {
-    let mut to_sort: Vec<u16> = vec![1, 2, 3, 4, 5];
+fn efficient_merge_vec_string() {
+    let mut to_sort: Vec<String> = TO_SORT_I32.iter().map(|i| format!("{:03}", i)).collect();
+    let mut expected = to_sort.clone();
+    expected.sort();
 
-    test_sort_vec::<u16>(&mut to_sort, sorting::simplesorts::bubble::sort);
+    sorting::efficientsorts::merge::sort(&mut to_sort);
+    assert_eq!(to_sort, expected);
 }
 
-/// Validate sorting of a sorted vector (u32).
+/// Validate sorting a vector of boxed values (Box<i64>).
 #[test]
-fn simple_bubble_sortedvec_u32() {
-    let mut to_sort: Vec<u32> = vec![1, 2, 3, 4, 5];
+fn efficient_merge_vec_box() {
+    let mut to_sort: Vec<Box<i64>> = TO_SORT_I64.iter().map(|&i| Box::new(i)).collect();
 
-    test_sort_vec::<u32>(&mut to_sort, sorting::simplesorts::bubble::sort);
+    sorting::efficientsorts::merge::sort(&mut to_sort);
+    verify_sorted(&to_sort);
+    assert_eq!(to_sort.len(), TO_SORT_I64.len());
 }
 
-/// Validate sorting of a sorted vector (u64).
+/// Validate the vector is still a permutation of its initial elements when the comparison panics.
 #[test]
-fn simple_bubble_sortedvec_u64() {
-    let mut to_sort: Vec<u64> = vec![1, 2, 3, 4, 5];
+fn efficient_merge_panic_in_comparator() {
+    let initial: Vec<String> = TO_SORT_I32.iter().map(|i| format!("{:03}", i)).collect();
+    let mut expected = initial.clone();
+    expected.sort();
+
+    for panic_after in 0..30 {
+        let mut to_sort = initial.clone();
+        let mut comparisons = 0;
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            sorting::efficientsorts::merge::sort_by(&mut to_sort, |a, b| {
+                comparisons += 1;
+                if comparisons > panic_after {
+                    panic!("comparison #{}", comparisons);
+                }
+                a.cmp(b)
+            });
+        }));
+        assert!(result.is_err());
 
-    test_sort_vec::<u64>(&mut to_sort, sorting::simplesorts::bubble::sort);
+        to_sort.sort();
+        assert_eq!(to_sort, expected);
+    }
 }
 
 
-/// Validate sorting a vector of single precision values (f32).
+// ################################################################################################
+// ################################################################################################
+// Efficient sorts: Introsort
+
+
+/// Validate sorting of large sorted, reversed and all-equal vectors, which would recurse N levels
+/// deep in a plain quicksort.
 #[test]
-fn simple_bubble_sortedvec_f32() {
-    let mut to_sort: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+fn efficient_intro_degenerate_vec() {
+    let len = 100_000;
+
+    let mut to_sort: Vec<i32> = (0..len).collect();
+    sorting::efficientsorts::intro::sort(&mut to_sort);
+    verify_sorted(&to_sort);
+
+    let mut to_sort: Vec<i32> = (0..len).rev().collect();
+    sorting::efficientsorts::intro::sort(&mut to_sort);
+    verify_sorted(&to_sort);
 
-    test_sort_vec::<f32>(&mut to_sort, sorting::simplesorts::bubble::sort);
+    let mut to_sort: Vec<i32> = vec![42; len as usize];
+    sorting::efficientsorts::intro::sort(&mut to_sort);
+    verify_sorted(&to_sort);
 }
 
-/// Validate sorting a vector of double precision values (f64).
+/// Validate introsort stays O(N log N) on a "median-of-3 killer" input that makes quicksort
+/// quadratic.
 #[test]
-fn simple_bubble_sortedvec_f64() {
-    let mut to_sort: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+fn efficient_intro_median_of_3_killer() {
+    let len = 2_000;
+    let killer: Vec<usize> = patterns::generate(Pattern::MedianOf3Killer, len, 0);
 
-    test_sort_vec::<f64>(&mut to_sort, sorting::simplesorts::bubble::sort);
+    // The input is indeed quadratic for the plain quicksort...
+    let quick_comparisons = count_comparisons(&killer, |v, c| {
+        sorting::efficientsorts::quick::sort_by(v, c)
+    });
+    assert!(quick_comparisons > len * len / 8);
+
+    // ...but not for introsort.
+    let intro_comparisons = count_comparisons(&killer, |v, c| {
+        sorting::efficientsorts::intro::sort_by(v, c)
+    });
+    let log2_len = (len as f64).log2() as usize;
+    assert!(intro_comparisons < 8 * len * log2_len);
+
+    let mut to_sort = killer.clone();
+    sorting::efficientsorts::intro::sort(&mut to_sort);
+    verify_sorted(&to_sort);
 }
 
 
 // ################################################################################################
 // ################################################################################################
-// Simple sorts: Insertion sort
+// Efficient sorts: Timsort
 
-/// Validate against empty vector (i8).
-#[test]
-fn simple_insertion_empty_vec_i8() {
-    test_empty_vec::<i8>(sorting::simplesorts::insertion::sort);
-}
-/// Validate against empty vector (i32).
-#[test]
-fn simple_insertion_empty_vec_i16() {
-    test_empty_vec::<i16>(sorting::simplesorts::insertion::sort);
-}
-/// Validate against empty vector (i32).
-#[test]
-fn simple_insertion_empty_vec_i32() {
-    test_empty_vec::<i32>(sorting::simplesorts::insertion::sort);
-}
-/// Validate against empty vector (i64).
+
+/// Validate sorting of partially sorted vectors: sorted batches appended one after the other,
+/// descending batches and a sorted vector with a few random elements appended.
 #[test]
-fn simple_insertion_empty_vec_i64() {
-    test_empty_vec::<i64>(sorting::simplesorts::insertion::sort);
+fn efficient_tim_partially_sorted_vec() {
+    let batches: Vec<i32> = (0..5_000).map(|i| (i * 7919) % 1_000 + (i % 3) * 10).collect();
+    let mut to_sort: Vec<i32> = Vec::new();
+    for (i, batch) in batches.chunks(250).enumerate() {
+        let mut batch = batch.to_vec();
+        batch.sort();
+        if i % 2 == 1 {
+            batch.reverse();
+        }
+        to_sort.extend(batch);
+    }
+    let mut rng = rand::thread_rng();
+    to_sort.extend((0..100).map(|_| rng.gen_range(-100, 1_100)));
+
+    let mut expected = to_sort.clone();
+    expected.sort();
+    sorting::efficientsorts::tim::sort(&mut to_sort);
+    assert_eq!(to_sort, expected);
+}
+
+/// Validate equal elements keep their relative order, including within descending runs.
+#[test]
+fn efficient_tim_stable() {
+    let len = 5_000;
+    let mut rng = rand::thread_rng();
+    let mut to_sort: Vec<(u8, usize)> = (0..len).map(|i| {
+        // Long descending runs with repeated keys, then random keys.
+        let key = if i < len / 2 { (255 - (i / 20) % 256) as u8 } else { rng.gen_range(0, 16) };
+        (key, i)
+    }).collect();
+
+    let mut expected = to_sort.clone();
+    expected.sort_by_key(|&(key, _)| key);
+    sorting::efficientsorts::tim::sort_by_key(&mut to_sort, |&(key, _)| key);
+    assert_eq!(to_sort, expected);
+}
+
+/// Validate the vector is still a permutation of its initial elements when the comparison panics.
+#[test]
+fn efficient_tim_panic_in_comparator() {
+    let initial: Vec<String> = (0..200).map(|i| format!("{:03}", (i * 37) % 101)).collect();
+    let mut expected = initial.clone();
+    expected.sort();
+
+    for panic_after in (0..1_000).step_by(50) {
+        let mut to_sort = initial.clone();
+        let mut comparisons = 0;
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            sorting::efficientsorts::tim::sort_by(&mut to_sort, |a, b| {
+                comparisons += 1;
+                if comparisons > panic_after {
+                    panic!("comparison #{}", comparisons);
+                }
+                a.cmp(b)
+            });
+        }));
+        assert!(result.is_err());
+
+        to_sort.sort();
+        assert_eq!(to_sort, expected);
+    }
 }
 
 
-/// Validate against empty vector (u8).
-#[test]
-fn simple_insertion_empty_vec_u8() {
-    test_empty_vec::<u8>(sorting::simplesorts::insertion::sort);
-}
-/// Validate against empty vector (u32).
-#[test]
-fn simple_insertion_empty_vec_u16() {
-    test_empty_vec::<u16>(sorting::simplesorts::insertion::sort);
-}
-/// Validate against empty vector (u32).
-#[test]
-fn simple_insertion_empty_vec_u32() {
-    test_empty_vec::<u32>(sorting::simplesorts::insertion::sort);
-}
-/// Validate against empty vector (u64).
-#[test]
-fn simple_insertion_empty_vec_u64() {
-    test_empty_vec::<u64>(sorting::simplesorts::insertion::sort);
+// ################################################################################################
+// ################################################################################################
+// Efficient sorts: Radix sort
+
+
+/// Validate LSD and MSD sorting of a random vector over the whole range of the type, including
+/// its extreme values.
+fn efficient_radix_full_range<T>(min: T, max: T)
+    where T: sorting::efficientsorts::radix::RadixKey + rand::Rand + Default + Ord + Debug
+{
+    let len = 1_000;
+    let mut rng = rand::thread_rng();
+    let mut to_sort: Vec<T> = (0..len).map(|_| rng.gen::<T>()).collect();
+    to_sort.extend_from_slice(&[min, max, T::default(), max, min]);
+    let mut expected = to_sort.clone();
+    expected.sort();
+
+    let mut lsd = to_sort.clone();
+    sorting::efficientsorts::radix::sort(&mut lsd);
+    assert_eq!(lsd, expected);
+
+    let mut msd = to_sort.clone();
+    sorting::efficientsorts::radix::sort_msd(&mut msd);
+    assert_eq!(msd, expected);
+}
+
+/// Generate a test of `efficient_radix_full_range()` per integer type.
+macro_rules! efficient_radix_full_range_types {
+    ($($t:ident),*) => {$(
+        /// Validate LSD and MSD sorting over the whole range of the type.
+        #[test]
+        fn $t() {
+            efficient_radix_full_range::<$t>($t::MIN, $t::MAX);
+        }
+    )*}
 }
 
-/// Validate against empty vector (isize).
-#[test]
-fn simple_insertion_empty_vec_isize() {
-    test_empty_vec::<isize>(sorting::simplesorts::insertion::sort);
-}
-/// Validate against empty vector (usize).
-#[test]
-fn simple_insertion_empty_vec_usize() {
-    test_empty_vec::<usize>(sorting::simplesorts::insertion::sort);
-}
+mod efficient_radix_full_range_vec {
+    use super::*;
 
-/// Validate against empty vector (f32).
-#[test]
-fn simple_insertion_empty_vec_f32() {
-    test_empty_vec::<f32>(sorting::simplesorts::insertion::sort);
+    efficient_radix_full_range_types!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
 }
-/// Validate against empty vector (f64).
+
+/// Validate MSD sorting against empty and single element vectors.
 #[test]
-fn simple_insertion_empty_vec_f64() {
-    test_empty_vec::<f64>(sorting::simplesorts::insertion::sort);
+fn efficient_radix_msd_small_vec() {
+    test_empty_vec::<u32>(sorting::efficientsorts::radix::sort_msd);
+    let mut to_sort: Vec<i64> = vec![-5];
+    sorting::efficientsorts::radix::sort_msd(&mut to_sort);
+    assert_eq!(to_sort, vec![-5]);
 }
 
-/// Validate sorting of a const vector (i8).
+/// Validate the operations reported by LSD radix sort: no comparison, one temporary vector and
+/// every element moved once per pass. Replaying them gives the sorted vector.
 #[test]
-fn simple_insertion_vec_i8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I8[..]);
+fn efficient_radix_observed() {
+    let mut rng = rand::thread_rng();
+    let input: Vec<i32> = (0..1000).map(|_| rng.gen::<i32>()).collect();
+    let mut expected = input.clone();
+    expected.sort();
+
+    let mut to_sort = input.clone();
+    let mut counts = instrument::Counts::default();
+    sorting::efficientsorts::radix::sort_observed(&mut to_sort, &mut counts);
+    assert_eq!(to_sort, expected);
+    assert_eq!(counts, instrument::Counts { comparisons: 0, swaps: 0, moves: 4 * 1000,
+                                            allocations: 1 });
 
-    test_sort_vec::<i8>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    let mut to_sort = input.clone();
+    let mut recorder = trace::Recorder::new();
+    sorting::efficientsorts::radix::sort_observed(&mut to_sort, &mut recorder);
+    let mut replayed = input.clone();
+    for event in &recorder.events {
+        event.apply(&mut replayed);
+    }
+    assert_eq!(replayed, expected);
+
+    // A single pass leaves the elements in the temporary vector: they are written back.
+    let mut to_sort: Vec<u8> = vec![3, 1, 2];
+    let mut counts = instrument::Counts::default();
+    sorting::efficientsorts::radix::sort_observed(&mut to_sort, &mut counts);
+    assert_eq!(counts.moves, 2 * 3);
+}
+
+/// Validate the operations reported by MSD radix sort: swaps only, no temporary vector, and the
+/// comparisons of the insertion sort of the small buckets. Replaying them gives the sorted vector.
+#[test]
+fn efficient_radix_msd_observed() {
+    let mut rng = rand::thread_rng();
+    let input: Vec<u16> = (0..2000).map(|_| rng.gen::<u16>()).collect();
+    let mut expected = input.clone();
+    expected.sort();
+
+    let mut to_sort = input.clone();
+    let mut recorder = trace::Recorder::new();
+    sorting::efficientsorts::radix::sort_msd_observed(&mut to_sort, &mut recorder);
+    assert_eq!(to_sort, expected);
+    assert!(recorder.events.iter().any(|event| matches!(*event, Event::Swap(..))));
+    assert!(!recorder.events.iter().any(|event| {
+        matches!(*event, Event::Allocate(..) | Event::MoveToBuffer(..) | Event::Write(..))
+    }));
+    let calls = recorder.events.iter().filter(|event| matches!(*event, Event::Enter(..))).count();
+    let returns = recorder.events.iter().filter(|event| matches!(*event, Event::Exit(..))).count();
+    assert_eq!(calls, returns);
+
+    let mut replayed = input.clone();
+    for event in &recorder.events {
+        event.apply(&mut replayed);
+    }
+    assert_eq!(replayed, expected);
 }
 
-/// Validate sorting of a const vector (i16).
-#[test]
-fn simple_insertion_vec_i16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I16[..]);
 
-    test_sort_vec::<i16>(&mut to_sort, sorting::simplesorts::insertion::sort);
+// ################################################################################################
+// ################################################################################################
+// Efficient sorts: Selection
+
+
+/// Verify `selected` is `input` re-arranged by selecting index `k`: the element at `k` is the
+/// one of the sorted vector, no element before it is larger and no element after it is smaller.
+fn verify_selected<T: Ord + Clone + Debug>(input: &[T], selected: &[T], k: usize) {
+    let mut expected = input.to_vec();
+    expected.sort();
+    assert_eq!(selected[k], expected[k], "k = {}, input = {:?}", k, input);
+    assert!(selected[..k].iter().all(|x| *x <= selected[k]));
+    assert!(selected[k+1..].iter().all(|x| *x >= selected[k]));
+
+    let mut permutation = selected.to_vec();
+    permutation.sort();
+    assert_eq!(permutation, expected);
+}
+
+/// Validate selection of every index of small random vectors, with many duplicates.
+#[test]
+fn efficient_select_every_index() {
+    let mut rng = rand::thread_rng();
+    for n in 1..40 {
+        let input: Vec<i32> = (0..n).map(|_| rng.gen_range(0, 10)).collect();
+        for k in 0..input.len() {
+            let mut selected = input.clone();
+            let value = *sorting::efficientsorts::select::select_nth_unstable(&mut selected, k);
+            assert_eq!(value, selected[k]);
+            verify_selected(&input, &selected, k);
+        }
+    }
 }
 
-/// Validate sorting of a const vector (i32).
+/// Validate selection of the first, middle, last and a random index of vectors of every pattern.
 #[test]
-fn simple_insertion_vec_i32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
-
-    test_sort_vec::<i32>(&mut to_sort, sorting::simplesorts::insertion::sort);
+fn efficient_select_patterns() {
+    let mut rng = rand::thread_rng();
+    for &pattern in patterns::all() {
+        let n = conformance_pattern_max_len(pattern, CONFORMANCE_MAX_LEN);
+        let input: Vec<i64> = patterns::generate(pattern, n, rng.gen::<u64>());
+        for &k in &[0, n / 2, n - 1, rng.gen_range(0, n)] {
+            let mut selected = input.clone();
+            sorting::efficientsorts::select::select_nth_unstable(&mut selected, k);
+            verify_selected(&input, &selected, k);
+        }
+    }
 }
 
-/// Validate sorting of a const vector (i64).
+/// Validate selection stays linear on inputs making quickselect quadratic: the median-of-3 killer
+/// and vectors of equal values.
 #[test]
-fn simple_insertion_vec_i64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I64[..]);
-
-    test_sort_vec::<i64>(&mut to_sort, sorting::simplesorts::insertion::sort);
+fn efficient_select_linear() {
+    let len = 2_000;
+    let killer: Vec<usize> = patterns::generate(Pattern::MedianOf3Killer, len, 0);
+    let equal: Vec<usize> = vec![42; len];
+    for input in &[killer, equal] {
+        for &k in &[0, len / 2, len - 1] {
+            let comparisons = count_comparisons(input, |v, c| {
+                sorting::efficientsorts::select::select_nth_unstable_by(v, k, c);
+            });
+            assert!(comparisons < 50 * len, "{} comparisons to select index {}", comparisons, k);
+        }
+    }
 }
 
-/// Validate sorting of a const vector (u8).
+/// Validate selection with a comparator and a key.
 #[test]
-fn simple_insertion_vec_u8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U8[..]);
+fn efficient_select_by() {
+    let mut to_select: Vec<i32> = From::from(&TO_SORT_I32[..]);
+    let largest = *sorting::efficientsorts::select::select_nth_unstable_by(&mut to_select, 0,
+                                                                            |a, b| b.cmp(a));
+    assert_eq!(largest, 67);
 
-    test_sort_vec::<u8>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    let mut to_select = people();
+    let person = sorting::efficientsorts::select::select_nth_unstable_by_key(&mut to_select, 1,
+                                                                              |p| p.age);
+    assert_eq!(person.name, "Bob");
 }
 
-/// Validate sorting of a const vector (u16).
+/// Validate selecting an index out of the vector panics.
 #[test]
-fn simple_insertion_vec_u16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
+#[should_panic]
+fn efficient_select_out_of_range() {
+    let mut to_select: Vec<i32> = vec![1, 2, 3];
+    sorting::efficientsorts::select::select_nth_unstable(&mut to_select, 3);
+}
 
-    test_sort_vec::<u16>(&mut to_sort, sorting::simplesorts::insertion::sort);
+/// Percentile of sorted values, as computed by NumPy.
+fn sorted_percentile(sorted: &[f64], p: f64) -> f64 {
+    let index = p / 100.0 * (sorted.len() - 1) as f64;
+    let k = index.floor() as usize;
+    let fraction = index - k as f64;
+    if fraction == 0.0 {
+        sorted[k]
+    } else {
+        sorted[k] + (sorted[k+1] - sorted[k]) * fraction
+    }
 }
 
-/// Validate sorting of a const vector (u32).
-#[test]
-fn simple_insertion_vec_u32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U32[..]);
+/// Validate medians and percentiles against the sorted vector.
+#[test]
+fn efficient_select_median_percentile() {
+    let mut data: Vec<f64> = vec![3.0, 1.0, 2.0];
+    assert_eq!(sorting::efficientsorts::select::median(&mut data), 2.0);
+    let mut data: Vec<f32> = vec![4.0, 1.0, 3.0, 2.0];
+    assert_eq!(sorting::efficientsorts::select::median(&mut data), 2.5);
+    assert_eq!(sorting::efficientsorts::select::percentile(&mut data, 0.0), 1.0);
+    assert_eq!(sorting::efficientsorts::select::percentile(&mut data, 100.0), 4.0);
+
+    let mut rng = rand::thread_rng();
+    for n in 1..100 {
+        let input: Vec<f64> = (0..n).map(|_| rng.gen_range(-100.0, 100.0)).collect();
+        let mut sorted = input.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        for &p in &[0.0, 10.0, 25.0, 50.0, 75.0, 99.9, 100.0] {
+            let mut data = input.clone();
+            assert_eq!(sorting::efficientsorts::select::percentile(&mut data, p),
+                       sorted_percentile(&sorted, p));
+        }
+    }
+}
 
-    test_sort_vec::<u32>(&mut to_sort, sorting::simplesorts::insertion::sort);
+/// Validate the median of empty vectors and of vectors containing a NaN is NaN.
+#[test]
+fn efficient_select_median_nan() {
+    let mut data: Vec<f64> = vec![];
+    assert!(sorting::efficientsorts::select::median(&mut data).is_nan());
+    let mut data: Vec<f64> = vec![1.0, f64::NAN, 2.0];
+    assert!(sorting::efficientsorts::select::median(&mut data).is_nan());
+    let mut data: Vec<f32> = vec![f32::NAN];
+    assert!(sorting::efficientsorts::select::percentile(&mut data, 10.0).is_nan());
+}
+
+/// Validate a percentile larger than 100 panics.
+#[test]
+#[should_panic]
+fn efficient_select_percentile_out_of_range() {
+    let mut data: Vec<f64> = vec![1.0, 2.0];
+    sorting::efficientsorts::select::percentile(&mut data, 100.5);
+}
+
+/// Validate the selection, median and percentile exports, and their invalid arguments.
+#[test]
+fn efficient_select_ffi() {
+    let mut to_select: Vec<i32> = From::from(&TO_SORT_I32[..]);
+    let status = sorting::efficientsorts::select::ffi_select_nth_i32(to_select.as_mut_ptr(), 16, 5);
+    assert_eq!(status, ffi::SortStatus::Ok);
+    verify_selected(&TO_SORT_I32, &to_select, 5);
+    let status = sorting::efficientsorts::select::ffi_select_nth_i32(to_select.as_mut_ptr(), 16,
+                                                                     16);
+    assert_eq!(status, ffi::SortStatus::InvalidArgument);
+    let status = sorting::efficientsorts::select::ffi_select_nth_i32(std::ptr::null_mut(), 0, 0);
+    assert_eq!(status, ffi::SortStatus::InvalidArgument);
+    let status = sorting::efficientsorts::select::ffi_select_nth_i32(std::ptr::null_mut(), 16, 0);
+    assert_eq!(status, ffi::SortStatus::NullPointer);
+
+    // NaNs are placed at the end.
+    let mut to_select: Vec<f64> = vec![f64::NAN, 3.0, 1.0, 2.0];
+    let status = sorting::efficientsorts::select::ffi_select_nth_f64(to_select.as_mut_ptr(), 4, 3,
+                                                                     NanPolicy::Last as libc::c_int);
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert!(to_select[3].is_nan());
+
+    let mut data: Vec<f64> = From::from(&TO_SORT_F64[..]);
+    let mut result: f64 = 0.0;
+    let status = sorting::efficientsorts::select::ffi_median_f64(data.as_mut_ptr(), 16,
+                                                                 &mut result);
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert_eq!(result, (5.0 + 6.0) / 2.0);
+
+    let mut data: Vec<f32> = vec![4.0, 1.0, 3.0, 2.0];
+    let status = sorting::efficientsorts::select::ffi_percentile_f32(data.as_mut_ptr(), 4, 100.0,
+                                                                     &mut result);
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert_eq!(result, 4.0);
+    let status = sorting::efficientsorts::select::ffi_percentile_f32(data.as_mut_ptr(), 4, -1.0,
+                                                                     &mut result);
+    assert_eq!(status, ffi::SortStatus::InvalidArgument);
+    let status = sorting::efficientsorts::select::ffi_percentile_f32(data.as_mut_ptr(), 4, 50.0,
+                                                                     std::ptr::null_mut());
+    assert_eq!(status, ffi::SortStatus::NullPointer);
+
+    let status = sorting::efficientsorts::select::ffi_median_f64(std::ptr::null_mut(), 0,
+                                                                 &mut result);
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert!(result.is_nan());
 }
 
-/// Validate sorting of a const vector (u64).
-#[test]
-fn simple_insertion_vec_u64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U64[..]);
 
-    test_sort_vec::<u64>(&mut to_sort, sorting::simplesorts::insertion::sort);
-}
+// ################################################################################################
+// ################################################################################################
+// Efficient sorts: Partial sort
 
 
-/// Validate sorting a vector of single precision values (f32).
-#[test]
-fn simple_insertion_vec_f32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F32[..]);
+/// Verify `partial` is `input` partially sorted up to `k`: its first `k` elements are those of the
+/// sorted vector, truncated, and the others the remaining elements.
+fn verify_partially_sorted<T: Ord + Clone + Debug>(input: &[T], partial: &[T], k: usize) {
+    let mut expected = input.to_vec();
+    expected.sort();
+    let k = k.min(input.len());
+    assert_eq!(partial[..k].to_vec(), expected[..k].to_vec(), "k = {}, input = {:?}", k, input);
 
-    test_sort_vec::<f32>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    let mut permutation = partial.to_vec();
+    permutation.sort();
+    assert_eq!(permutation, expected);
 }
 
-/// Validate sorting a vector of double precision values (f64).
+/// Validate partial sorting of random vectors and of vectors of every pattern against a sort
+/// followed by a truncation.
 #[test]
-fn simple_insertion_vec_f64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F64[..]);
+fn efficient_partial_sort() {
+    let mut rng = rand::thread_rng();
+    for n in 0..30 {
+        let input: Vec<i32> = (0..n).map(|_| rng.gen_range(0, 10)).collect();
+        for k in 0..input.len() + 2 {
+            let mut partial = input.clone();
+            sorting::efficientsorts::partial::partial_sort(&mut partial, k);
+            verify_partially_sorted(&input, &partial, k);
+        }
+    }
 
-    test_sort_vec::<f64>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    for &pattern in patterns::all() {
+        let n = conformance_pattern_max_len(pattern, CONFORMANCE_MAX_LEN);
+        let input: Vec<u32> = patterns::generate(pattern, n, rng.gen::<u64>());
+        for &k in &[1, 10, n / 2, rng.gen_range(0, n + 1)] {
+            let mut partial = input.clone();
+            sorting::efficientsorts::partial::partial_sort(&mut partial, k);
+            verify_partially_sorted(&input, &partial, k);
+        }
+    }
 }
 
-
-
-/// Validate sorting of a sorted vector (isize).
+/// Validate partial sorting with a comparator and a key.
 #[test]
-fn simple_insertion_sortedvec_isize() {
-    let mut to_sort: Vec<isize> = vec![1, 2, 3, 4, 5];
+fn efficient_partial_sort_by() {
+    let mut to_sort: Vec<i32> = From::from(&TO_SORT_I32[..]);
+    sorting::efficientsorts::partial::partial_sort_by(&mut to_sort, 4, |a, b| b.cmp(a));
+    assert_eq!(to_sort[..4].to_vec(), vec![67, 56, 44, 44]);
 
-    test_sort_vec::<isize>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    let mut to_sort = people();
+    sorting::efficientsorts::partial::partial_sort_by_key(&mut to_sort, 2, |p| p.age);
+    let names: Vec<&str> = to_sort[..2].iter().map(|p| p.name).collect();
+    assert_eq!(names, vec!["Dave", "Bob"]);
 }
 
-/// Validate sorting of a sorted vector (usize).
+/// Validate partial sorting does O(N log k) comparisons, even on a reversed vector where every
+/// element enters the heap.
 #[test]
-fn simple_insertion_sortedvec_usize() {
-    let mut to_sort: Vec<usize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<usize>(&mut to_sort, sorting::simplesorts::insertion::sort);
+fn efficient_partial_sort_comparisons() {
+    let len = 10_000;
+    let k = 16;
+    let log2_k = 4;
+    let reversed: Vec<i32> = (0..len as i32).rev().collect();
+    let comparisons = count_comparisons(&reversed, |v, c| {
+        sorting::efficientsorts::partial::partial_sort_by(v, k, c)
+    });
+    assert!(comparisons < 3 * len * (log2_k + 1), "{} comparisons", comparisons);
 }
 
-/// Validate sorting of a sorted vector (i8).
+/// Validate top-k extraction from iterators against a sort followed by a truncation.
 #[test]
-fn simple_insertion_sortedvec_i8() {
-    let mut to_sort: Vec<i8> = vec![1, 2, 3, 4, 5];
+fn efficient_partial_top_k() {
+    let mut rng = rand::thread_rng();
+    for n in 0..30 {
+        let input: Vec<i32> = (0..n).map(|_| rng.gen_range(0, 10)).collect();
+        let mut expected = input.clone();
+        expected.sort();
+        for k in 0..input.len() + 2 {
+            let top = sorting::efficientsorts::partial::top_k(input.iter().cloned(), k);
+            assert_eq!(top, expected[..k.min(n)].to_vec());
+        }
+    }
 
-    test_sort_vec::<i8>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    let largest = sorting::efficientsorts::partial::top_k_by(TO_SORT_F64.iter(), 3,
+                                                             |a, b| b.total_cmp(a));
+    assert_eq!(largest, vec![&67.9, &56.3, &44.2]);
+
+    let youngest = sorting::efficientsorts::partial::top_k_by_key(people(), 1, |p| p.age);
+    assert_eq!(youngest[0].name, "Dave");
 }
 
-/// Validate sorting of a sorted vector (i16).
+/// Validate top-k extraction from a lazily generated stream only keeps `k` elements.
 #[test]
-fn simple_insertion_sortedvec_i16() {
-    let mut to_sort: Vec<i16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i16>(&mut to_sort, sorting::simplesorts::insertion::sort);
+fn efficient_partial_top_k_stream() {
+    let stream = (0..1_000_000u64).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    let top = sorting::efficientsorts::partial::top_k(stream.clone(), 5);
+    let mut expected: Vec<u64> = stream.collect();
+    expected.sort();
+    expected.truncate(5);
+    assert_eq!(top, expected);
+    assert!(top.capacity() < 100);
 }
 
-/// Validate sorting of a sorted vector (i32).
-#[test]
-fn simple_insertion_sortedvec_i32() {
-    let mut to_sort: Vec<i32> = vec![1, 2, 3, 4, 5];
 
-    test_sort_vec::<i32>(&mut to_sort, sorting::simplesorts::insertion::sort);
+// ################################################################################################
+// ################################################################################################
+// Argsort and permutations
+
+
+/// Random permutation of `n` indices.
+fn random_permutation(n: usize) -> Vec<usize> {
+    let mut permutation: Vec<usize> = (0..n).collect();
+    rand::thread_rng().shuffle(&mut permutation);
+    permutation
+}
+
+/// Validate the indices returned by every algorithm sort the vector, which is left untouched, and
+/// keep equal elements in their initial order for the stable algorithms.
+#[test]
+fn argsort_all_algorithms() {
+    let mut rng = rand::thread_rng();
+    for algorithm in registry::all() {
+        for n in 0..50 {
+            let input: Vec<i32> = (0..n).map(|_| rng.gen_range(0, 10)).collect();
+            let copy = input.clone();
+            let indices = algorithm.argsort(&input);
+            assert_eq!(input, copy);
+            assert!(sorting::permutation::is_valid(&indices));
+            assert!(indices.windows(2).all(|w| input[w[0]] <= input[w[1]]), "{}", algorithm);
+            if algorithm.is_stable() {
+                assert!(indices.windows(2).all(|w| input[w[0]] < input[w[1]] || w[0] < w[1]),
+                        "{} is not stable", algorithm);
+            }
+
+            let mut sorted = input.clone();
+            sorting::permutation::apply(&mut sorted, &indices);
+            verify_sorted(&sorted);
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (i64).
-#[test]
-fn simple_insertion_sortedvec_i64() {
-    let mut to_sort: Vec<i64> = vec![1, 2, 3, 4, 5];
+/// The `argsort_by()` function of an algorithm module, for `f64`.
+type ArgsortBy = fn(&[f64], fn(&f64, &f64) -> Ordering) -> Vec<usize>;
+
+/// Validate the argsort functions of the algorithm modules, with a comparator.
+#[test]
+fn argsort_modules() {
+    let expected_descending: Vec<usize> = {
+        let mut indices: Vec<usize> = (0..TO_SORT_F64.len()).collect();
+        indices.sort_by(|&a, &b| TO_SORT_F64[b].total_cmp(&TO_SORT_F64[a]));
+        indices
+    };
+    let argsorts: [ArgsortBy; 3] = [
+        sorting::simplesorts::insertion::argsort_by,
+        sorting::efficientsorts::merge::argsort_by,
+        sorting::efficientsorts::tim::argsort_by,
+    ];
+    for argsort_by in argsorts.iter() {
+        assert_eq!(argsort_by(&TO_SORT_F64, |a, b| b.total_cmp(a)), expected_descending);
+    }
 
-    test_sort_vec::<i64>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    let ages = people();
+    let indices = sorting::efficientsorts::quick::argsort_by(&ages, |a, b| a.age.cmp(&b.age));
+    assert_eq!(indices, vec![2, 3, 0, 1]);
 }
 
-/// Validate sorting of a sorted vector (u8).
+/// Validate applying and inverting random permutations.
 #[test]
-fn simple_insertion_sortedvec_u8() {
-    let mut to_sort: Vec<u8> = vec![1, 2, 3, 4, 5];
+fn permutation_apply_invert() {
+    for n in 0..100 {
+        let permutation = random_permutation(n);
+        let mut values: Vec<String> = (0..n).map(|i| i.to_string()).collect();
+        sorting::permutation::apply(&mut values, &permutation);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(*value, permutation[i].to_string());
+        }
+
+        let mut inverse = permutation.clone();
+        sorting::permutation::invert(&mut inverse);
+        for (i, &index) in permutation.iter().enumerate() {
+            assert_eq!(inverse[index], i);
+        }
+        // Applying the inverse restores the initial order.
+        sorting::permutation::apply(&mut values, &inverse);
+        assert_eq!(values, (0..n).map(|i| i.to_string()).collect::<Vec<String>>());
 
-    test_sort_vec::<u8>(&mut to_sort, sorting::simplesorts::insertion::sort);
+        sorting::permutation::invert(&mut inverse);
+        assert_eq!(inverse, permutation);
+    }
 }
 
-/// Validate sorting of a sorted vector (u16).
+/// Validate the inverse of argsort gives the rank of every element.
 #[test]
-fn simple_insertion_sortedvec_u16() {
-    let mut to_sort: Vec<u16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u16>(&mut to_sort, sorting::simplesorts::insertion::sort);
+fn permutation_ranks() {
+    let input: Vec<u16> = From::from(&TO_SORT_U16[..]);
+    let mut ranks = sorting::efficientsorts::merge::argsort(&input);
+    sorting::permutation::invert(&mut ranks);
+    let mut sorted = input.clone();
+    sorted.sort();
+    for (i, &rank) in ranks.iter().enumerate() {
+        assert_eq!(sorted[rank], input[i]);
+    }
 }
 
-/// Validate sorting of a sorted vector (u32).
+/// Validate invalid permutations are detected, and rejected without touching the vector.
 #[test]
-fn simple_insertion_sortedvec_u32() {
-    let mut to_sort: Vec<u32> = vec![1, 2, 3, 4, 5];
+fn permutation_invalid() {
+    assert!(sorting::permutation::is_valid(&[]));
+    assert!(!sorting::permutation::is_valid(&[0, 0]));
+    assert!(!sorting::permutation::is_valid(&[1, 2]));
 
-    test_sort_vec::<u32>(&mut to_sort, sorting::simplesorts::insertion::sort);
-}
+    let mut values: Vec<i32> = vec![1, 2, 3];
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        sorting::permutation::apply(&mut values, &[2, 0, 0]);
+    }));
+    assert!(result.is_err());
+    assert_eq!(values, vec![1, 2, 3]);
 
-/// Validate sorting of a sorted vector (u64).
-#[test]
-fn simple_insertion_sortedvec_u64() {
-    let mut to_sort: Vec<u64> = vec![1, 2, 3, 4, 5];
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        sorting::permutation::apply(&mut values, &[0, 1]);
+    }));
+    assert!(result.is_err());
 
-    test_sort_vec::<u64>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    let mut permutation: Vec<usize> = vec![1, 3, 0];
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        sorting::permutation::invert(&mut permutation);
+    }));
+    assert!(result.is_err());
+    assert_eq!(permutation, vec![1, 3, 0]);
 }
 
-
-/// Validate sorting a vector of single precision values (f32).
+/// Validate the argsort exports and their invalid arguments.
 #[test]
-fn simple_insertion_sortedvec_f32() {
-    let mut to_sort: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+fn argsort_ffi() {
+    let input: Vec<i32> = vec![30, 10, 20, 10];
+    let mut indices: Vec<libc::size_t> = vec![0; 4];
+    let status = sorting::permutation::ffi_argsort_i32(input.as_ptr(), 4, indices.as_mut_ptr());
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert_eq!(indices, vec![1, 3, 2, 0]);
+    assert_eq!(input, vec![30, 10, 20, 10]);
 
-    test_sort_vec::<f32>(&mut to_sort, sorting::simplesorts::insertion::sort);
-}
-
-/// Validate sorting a vector of double precision values (f64).
-#[test]
-fn simple_insertion_sortedvec_f64() {
-    let mut to_sort: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-
-    test_sort_vec::<f64>(&mut to_sort, sorting::simplesorts::insertion::sort);
+    // NaNs are placed at the end.
+    let input: Vec<f64> = vec![f64::NAN, 2.0, -1.0];
+    let status = sorting::permutation::ffi_argsort_f64(input.as_ptr(), 3, indices.as_mut_ptr(),
+                                                       NanPolicy::Last as libc::c_int);
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert_eq!(indices[..3].to_vec(), vec![2, 1, 0]);
+
+    let status = sorting::permutation::ffi_argsort_u8(std::ptr::null(), 0, std::ptr::null_mut());
+    assert_eq!(status, ffi::SortStatus::Ok);
+    let status = sorting::permutation::ffi_argsort_i32(input.as_ptr() as *const i32, 3,
+                                                       std::ptr::null_mut());
+    assert_eq!(status, ffi::SortStatus::NullPointer);
+    let status = sorting::permutation::ffi_argsort_i32(std::ptr::null(), 3, indices.as_mut_ptr());
+    assert_eq!(status, ffi::SortStatus::NullPointer);
+    let status = sorting::permutation::ffi_argsort_i32(input.as_ptr() as *const i32,
+                                                       libc::size_t::MAX, indices.as_mut_ptr());
+    assert_eq!(status, ffi::SortStatus::InvalidLength);
 }
 
 
 // ################################################################################################
 // ################################################################################################
-// Simple sorts: Selection sort
-
-/// Validate against empty vector (isize).
-#[test]
-fn simple_selection_empty_vec_isize() {
-    test_empty_vec::<isize>(sorting::simplesorts::selection::sort);
-}
-
-/// Validate against empty vector (isize).
-#[test]
-fn simple_selection_empty_vec_usize() {
-    test_empty_vec::<usize>(sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a const vector (i8).
-#[test]
-fn simple_selection_vec_i8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I8[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i16).
-#[test]
-fn simple_selection_vec_i16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I16[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i32).
-#[test]
-fn simple_selection_vec_i32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i64).
-#[test]
-fn simple_selection_vec_i64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I64[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u8).
-#[test]
-fn simple_selection_vec_u8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U8[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u16).
-#[test]
-fn simple_selection_vec_u16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u32).
-#[test]
-fn simple_selection_vec_u32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U32[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u64).
-#[test]
-fn simple_selection_vec_u64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U64[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a const vector (f32).
-#[test]
-fn simple_selection_vec_f32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F32[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (f64).
-#[test]
-fn simple_selection_vec_f64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F64[..]);
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a random vector (i8).
-#[test]
-fn simple_selection_rand_vec_i8() {
-    let len = 50;
-    let mut to_sort: Vec<i8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i8>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (i16).
-#[test]
-fn simple_selection_rand_vec_i16() {
-    let len = 50;
-    let mut to_sort: Vec<i16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i16>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (i32).
-#[test]
-fn simple_selection_rand_vec_i32() {
-    let len = 50;
-    let mut to_sort: Vec<i32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i32>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (i64).
-#[test]
-fn simple_selection_rand_vec_i64() {
-    let len = 50;
-    let mut to_sort: Vec<i64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i64>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u8).
-#[test]
-fn simple_selection_rand_vec_u8() {
-    let len = 50;
-    let mut to_sort: Vec<u8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u8>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u16).
-#[test]
-fn simple_selection_rand_vec_u16() {
-    let len = 50;
-    let mut to_sort: Vec<u16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u16>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u32).
-#[test]
-fn simple_selection_rand_vec_u32() {
-    let len = 50;
-    let mut to_sort: Vec<u32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u32>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u64).
-#[test]
-fn simple_selection_rand_vec_u64() {
-    let len = 50;
-    let mut to_sort: Vec<u64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u64>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a random vector (f32).
-#[test]
-fn simple_selection_rand_vec_f32() {
-    let len = 50;
-    let mut to_sort: Vec<f32> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f32>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (f64).
-#[test]
-fn simple_selection_rand_vec_f64() {
-    let len = 50;
-    let mut to_sort: Vec<f64> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f64>>();
-    sorting::simplesorts::selection::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a sorted vector (isize).
-#[test]
-fn simple_selection_sortedvec_isize() {
-    let mut to_sort: Vec<isize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<isize>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a sorted vector (usize).
-#[test]
-fn simple_selection_sortedvec_usize() {
-    let mut to_sort: Vec<usize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<usize>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a sorted vector (i8).
-#[test]
-fn simple_selection_sortedvec_i8() {
-    let mut to_sort: Vec<i8> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i8>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a sorted vector (i16).
-#[test]
-fn simple_selection_sortedvec_i16() {
-    let mut to_sort: Vec<i16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i16>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a sorted vector (i32).
-#[test]
-fn simple_selection_sortedvec_i32() {
-    let mut to_sort: Vec<i32> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i32>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a sorted vector (i64).
-#[test]
-fn simple_selection_sortedvec_i64() {
-    let mut to_sort: Vec<i64> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i64>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a sorted vector (u8).
-#[test]
-fn simple_selection_sortedvec_u8() {
-    let mut to_sort: Vec<u8> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u8>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting of a sorted vector (u16).
-#[test]
-fn simple_selection_sortedvec_u16() {
-    let mut to_sort: Vec<u16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u16>(&mut to_sort, sorting::simplesorts::selection::sort);
+// Sorting with a payload
+
+
+/// Sort keys along with a payload, using a comparator.
+type SortWithPayloadBy = fn(&mut [i32], &mut Vec<usize>, fn(&i32, &i32) -> Ordering);
+
+/// The `sort_with_payload_by()` functions, with the stability of their algorithm.
+const SORTS_WITH_PAYLOAD: [(SortWithPayloadBy, bool); 3] = [
+    (sorting::efficientsorts::quick::sort_with_payload_by, false),
+    (sorting::efficientsorts::merge::sort_with_payload_by, true),
+    (sorting::efficientsorts::heap::sort_with_payload_by, false),
+];
+
+/// Validate the payload follows the keys: every key is still paired with its initial index.
+#[test]
+fn payload_follows_keys() {
+    let mut rng = rand::thread_rng();
+    for &(sort_with_payload_by, stable) in SORTS_WITH_PAYLOAD.iter() {
+        for &n in &[0, 1, 2, 3, 10, 100, 1000] {
+            let input: Vec<i32> = (0..n).map(|_| rng.gen_range(0, 20)).collect();
+            let mut keys = input.clone();
+            let mut indices: Vec<usize> = (0..n).collect();
+            sort_with_payload_by(&mut keys, &mut indices, |a, b| a.cmp(b));
+
+            verify_sorted(&keys);
+            assert!(sorting::permutation::is_valid(&indices));
+            for (key, &index) in keys.iter().zip(&indices) {
+                assert_eq!(*key, input[index]);
+            }
+            if stable {
+                assert!(indices.windows(2).all(|w| input[w[0]] < input[w[1]] || w[0] < w[1]));
+            }
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (u32).
+/// Validate several payloads of different types follow the keys.
 #[test]
-fn simple_selection_sortedvec_u32() {
-    let mut to_sort: Vec<u32> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u32>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
+fn payload_tuple() {
+    let mut keys: Vec<f64> = From::from(&TO_SORT_F64[..]);
+    let mut labels: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+    let mut bytes: Vec<u8> = (0..keys.len() as u8).collect();
+    sorting::efficientsorts::heap::sort_with_payload(&mut keys, &mut (&mut labels, &mut bytes));
 
-/// Validate sorting of a sorted vector (u64).
-#[test]
-fn simple_selection_sortedvec_u64() {
-    let mut to_sort: Vec<u64> = vec![1, 2, 3, 4, 5];
+    verify_sorted(&keys);
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(labels[i], key.to_string());
+        assert_eq!(TO_SORT_F64[bytes[i] as usize], *key);
+    }
 
-    test_sort_vec::<u64>(&mut to_sort, sorting::simplesorts::selection::sort);
+    let mut keys: Vec<u16> = From::from(&TO_SORT_U16[..]);
+    let mut values: Vec<u16> = keys.iter().map(|key| key * 2).collect();
+    sorting::efficientsorts::merge::sort_with_payload(&mut keys, &mut values[..]);
+    assert_eq!(values, keys.iter().map(|key| key * 2).collect::<Vec<u16>>());
+}
+
+/// Validate payloads whose length differs from the keys' are rejected.
+#[test]
+fn payload_length_mismatch() {
+    let result = panic::catch_unwind(|| {
+        let mut keys: Vec<i32> = vec![3, 2, 1];
+        let mut values: Vec<i32> = vec![1, 2];
+        sorting::efficientsorts::quick::sort_with_payload(&mut keys, &mut values);
+    });
+    assert!(result.is_err());
+
+    let result = panic::catch_unwind(|| {
+        let mut keys: Vec<i32> = vec![3, 2, 1];
+        let mut first: Vec<i32> = vec![1, 2, 3];
+        let mut second: Vec<i32> = vec![1, 2];
+        sorting::efficientsorts::merge::sort_with_payload(&mut keys, &mut (&mut first,
+                                                                             &mut second));
+    });
+    assert!(result.is_err());
+}
+
+/// Validate the keys and the payload are still paired when the comparator panics.
+#[test]
+fn payload_panic_in_comparator() {
+    for &(sort_with_payload_by, _) in SORTS_WITH_PAYLOAD.iter() {
+        let input: Vec<i32> = (0..100).rev().collect();
+        let mut keys = input.clone();
+        let mut indices: Vec<usize> = (0..input.len()).collect();
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            sort_with_payload_by(&mut keys, &mut indices, |a, b| {
+                if *a == 50 || *b == 50 { panic!("comparator"); }
+                a.cmp(b)
+            });
+        }));
+        assert!(result.is_err());
+        for (key, &index) in keys.iter().zip(&indices) {
+            assert_eq!(*key, input[index]);
+        }
+    }
 }
 
+/// Record moved along with its key by the payload exports.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PayloadRecord {
+    key: i64,
+    weight: f32,
+    flag: u8,
+}
+
+/// Validate the payload exports move records of any size, and their invalid arguments.
+#[test]
+fn payload_ffi() {
+    type PayloadExport = extern "C" fn(*mut libc::int64_t, libc::size_t, *mut libc::c_void,
+                                       libc::size_t) -> ffi::SortStatus;
+    let exports: [PayloadExport; 3] = [
+        sorting::efficientsorts::quick::ffi_quicksort_payload_i64,
+        sorting::efficientsorts::merge::ffi_mergesort_payload_i64,
+        sorting::efficientsorts::heap::ffi_heapsort_payload_i64,
+    ];
+    let size = std::mem::size_of::<PayloadRecord>() as libc::size_t;
+    for export in exports.iter() {
+        let mut keys: Vec<i64> = From::from(&TO_SORT_I64[..]);
+        let mut records: Vec<PayloadRecord> = keys.iter().map(|&key| {
+            PayloadRecord { key, weight: key as f32 / 2.0, flag: (key % 7) as u8 }
+        }).collect();
+        let status = export(keys.as_mut_ptr(), 16, records.as_mut_ptr() as *mut libc::c_void,
+                            size);
+        assert_eq!(status, ffi::SortStatus::Ok);
+        verify_sorted(&keys);
+        for (key, record) in keys.iter().zip(&records) {
+            assert_eq!(*record, PayloadRecord { key: *key, weight: *key as f32 / 2.0,
+                                                flag: (key % 7) as u8 });
+        }
 
-/// Validate sorting a vector of single precision values (f32).
-#[test]
-fn simple_selection_sortedvec_f32() {
-    let mut to_sort: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-
-    test_sort_vec::<f32>(&mut to_sort, sorting::simplesorts::selection::sort);
-}
-
-/// Validate sorting a vector of double precision values (f64).
-#[test]
-fn simple_selection_sortedvec_f64() {
-    let mut to_sort: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        // Records of zero bytes need no pointer.
+        let status = export(keys.as_mut_ptr(), 16, std::ptr::null_mut(), 0);
+        assert_eq!(status, ffi::SortStatus::Ok);
+
+        let values = records.as_mut_ptr() as *mut libc::c_void;
+        assert_eq!(export(std::ptr::null_mut(), 0, std::ptr::null_mut(), size),
+                   ffi::SortStatus::Ok);
+        assert_eq!(export(std::ptr::null_mut(), 16, values, size), ffi::SortStatus::NullPointer);
+        assert_eq!(export(keys.as_mut_ptr(), 16, std::ptr::null_mut(), size),
+                   ffi::SortStatus::NullPointer);
+        assert_eq!(export(keys.as_mut_ptr(), 16, values, libc::size_t::MAX),
+                   ffi::SortStatus::InvalidLength);
+    }
 
-    test_sort_vec::<f64>(&mut to_sort, sorting::simplesorts::selection::sort);
+    // NaNs are placed at the end, with their values.
+    let mut keys: Vec<f64> = vec![f64::NAN, 2.0, 1.0];
+    let mut values: Vec<u16> = vec![0, 2, 1];
+    let status = sorting::efficientsorts::quick::ffi_quicksort_payload_f64(
+        keys.as_mut_ptr(), 3, values.as_mut_ptr() as *mut libc::c_void, 2,
+        NanPolicy::Last as libc::c_int);
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert!(keys[2].is_nan());
+    assert_eq!(values, vec![1, 2, 0]);
 }
 
 
 // ################################################################################################
 // ################################################################################################
-// Efficient sorts: Heap sort
-
-/// Validate against empty vector (isize).
-#[test]
-fn efficient_heap_empty_vec_isize() {
-    test_empty_vec::<isize>(sorting::efficientsorts::heap::sort);
-}
-
-/// Validate against empty vector (isize).
-#[test]
-fn efficient_heap_empty_vec_usize() {
-    test_empty_vec::<usize>(sorting::efficientsorts::heap::sort);
-}
-
-/// Validate sorting of a const vector (i8).
-#[test]
-fn efficient_heap_vec_i8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I8[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i16).
-#[test]
-fn efficient_heap_vec_i16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I16[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i32).
-#[test]
-fn efficient_heap_vec_i32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i64).
-#[test]
-fn efficient_heap_vec_i64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I64[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u8).
-#[test]
-fn efficient_heap_vec_u8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U8[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u16).
-#[test]
-fn efficient_heap_vec_u16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u32).
-#[test]
-fn efficient_heap_vec_u32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U32[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u64).
-#[test]
-fn efficient_heap_vec_u64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U64[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a const vector (f32).
-#[test]
-fn efficient_heap_vec_f32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F32[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (f64).
-#[test]
-fn efficient_heap_vec_f64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F64[..]);
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a random vector (i8).
-#[test]
-fn efficient_heap_rand_vec_i8() {
-    let len = 50;
-    let mut to_sort: Vec<i8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i8>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (i16).
-#[test]
-fn efficient_heap_rand_vec_i16() {
-    let len = 50;
-    let mut to_sort: Vec<i16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i16>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (i32).
-#[test]
-fn efficient_heap_rand_vec_i32() {
-    let len = 50;
-    let mut to_sort: Vec<i32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i32>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (i64).
-#[test]
-fn efficient_heap_rand_vec_i64() {
-    let len = 50;
-    let mut to_sort: Vec<i64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i64>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u8).
-#[test]
-fn efficient_heap_rand_vec_u8() {
-    let len = 50;
-    let mut to_sort: Vec<u8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u8>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u16).
-#[test]
-fn efficient_heap_rand_vec_u16() {
-    let len = 50;
-    let mut to_sort: Vec<u16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u16>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u32).
-#[test]
-fn efficient_heap_rand_vec_u32() {
-    let len = 50;
-    let mut to_sort: Vec<u32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u32>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u64).
-#[test]
-fn efficient_heap_rand_vec_u64() {
-    let len = 50;
-    let mut to_sort: Vec<u64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u64>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a random vector (f32).
-#[test]
-fn efficient_heap_rand_vec_f32() {
-    let len = 50;
-    let mut to_sort: Vec<f32> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f32>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (f64).
-#[test]
-fn efficient_heap_rand_vec_f64() {
-    let len = 50;
-    let mut to_sort: Vec<f64> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f64>>();
-    sorting::efficientsorts::heap::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a sorted vector (isize).
-#[test]
-fn efficient_heap_sortedvec_isize() {
-    let mut to_sort: Vec<isize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<isize>(&mut to_sort, sorting::efficientsorts::heap::sort);
-}
-
-/// Validate sorting of a sorted vector (usize).
-#[test]
-fn efficient_heap_sortedvec_usize() {
-    let mut to_sort: Vec<usize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<usize>(&mut to_sort, sorting::efficientsorts::heap::sort);
-}
-
-/// Validate sorting of a sorted vector (i8).
-#[test]
-fn efficient_heap_sortedvec_i8() {
-    let mut to_sort: Vec<i8> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i8>(&mut to_sort, sorting::efficientsorts::heap::sort);
-}
-
-/// Validate sorting of a sorted vector (i16).
-#[test]
-fn efficient_heap_sortedvec_i16() {
-    let mut to_sort: Vec<i16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i16>(&mut to_sort, sorting::efficientsorts::heap::sort);
+// Lexicographic sort
+
+
+/// Validate lexsort against a stable sort of the rows, in every combination of directions.
+#[test]
+fn lexsort_matches_sorted_rows() {
+    let mut rng = rand::thread_rng();
+    for &n in &[0, 1, 2, 3, 10, 100, 1000] {
+        let groups: Vec<u8> = (0..n).map(|_| rng.gen_range(0, 4)).collect();
+        let values: Vec<i64> = (0..n).map(|_| rng.gen_range(-5, 5)).collect();
+        let weights: Vec<f32> = (0..n).map(|_| rng.gen_range(0, 3) as f32 / 2.0).collect();
+        for &(first, second) in &[(Direction::Ascending, Direction::Ascending),
+                                  (Direction::Ascending, Direction::Descending),
+                                  (Direction::Descending, Direction::Ascending),
+                                  (Direction::Descending, Direction::Descending)] {
+            let indices = lexsort(&[SortKey::new(&groups, first), SortKey::new(&values, second),
+                                    SortKey::ascending(&weights[..])]);
+
+            let mut expected: Vec<usize> = (0..n).collect();
+            expected.sort_by(|&i, &j| {
+                let by_group = groups[i].cmp(&groups[j]);
+                let by_value = values[i].cmp(&values[j]);
+                let by_group = if first == Direction::Descending { by_group.reverse() } else {
+                    by_group
+                };
+                let by_value = if second == Direction::Descending { by_value.reverse() } else {
+                    by_value
+                };
+                by_group.then(by_value).then(weights[i].partial_cmp(&weights[j]).unwrap())
+            });
+            assert_eq!(indices, expected);
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (i32).
+/// Validate rows equal on all keys stay in their initial order.
 #[test]
-fn efficient_heap_sortedvec_i32() {
-    let mut to_sort: Vec<i32> = vec![1, 2, 3, 4, 5];
+fn lexsort_stable() {
+    let people = people();
+    let ages: Vec<u32> = people.iter().map(|person| person.age / 20).collect();
+    let indices = lexsort(&[SortKey::descending(&ages)]);
+    assert_eq!(indices, vec![1, 0, 3, 2]);
 
-    test_sort_vec::<i32>(&mut to_sort, sorting::efficientsorts::heap::sort);
+    let constant: Vec<i32> = vec![7; 50];
+    assert_eq!(lexsort(&[SortKey::ascending(&constant), SortKey::descending(&constant)]),
+               (0..50).collect::<Vec<usize>>());
 }
 
-/// Validate sorting of a sorted vector (i64).
+/// Validate NaNs are placed last in ascending order and first in descending order.
 #[test]
-fn efficient_heap_sortedvec_i64() {
-    let mut to_sort: Vec<i64> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i64>(&mut to_sort, sorting::efficientsorts::heap::sort);
+fn lexsort_nan() {
+    let keys: Vec<f64> = vec![2.0, f64::NAN, 1.0, f64::NAN];
+    assert_eq!(lexsort(&[SortKey::ascending(&keys)]), vec![2, 0, 1, 3]);
+    assert_eq!(lexsort(&[SortKey::descending(&keys)]), vec![1, 3, 0, 2]);
 }
 
-/// Validate sorting of a sorted vector (u8).
+/// Validate the permutation re-arranges all the columns of a table.
 #[test]
-fn efficient_heap_sortedvec_u8() {
-    let mut to_sort: Vec<u8> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u8>(&mut to_sort, sorting::efficientsorts::heap::sort);
+fn lexsort_apply_payload() {
+    let mut days: Vec<u16> = vec![3, 1, 2, 1, 3];
+    let mut scores: Vec<f64> = vec![0.5, 0.25, 1.0, 0.75, 0.5];
+    let mut names: Vec<&str> = vec!["a", "b", "c", "d", "e"];
+    let indices = lexsort(&[SortKey::ascending(&days), SortKey::descending(&scores)]);
+    sorting::permutation::apply_payload(&mut (&mut days, &mut scores, &mut names), &indices);
+    assert_eq!(days, vec![1, 1, 2, 3, 3]);
+    assert_eq!(scores, vec![0.75, 0.25, 1.0, 0.5, 0.5]);
+    assert_eq!(names, vec!["d", "b", "c", "a", "e"]);
 }
 
-/// Validate sorting of a sorted vector (u16).
+/// Validate keys whose columns differ in length, and empty keys, are rejected.
 #[test]
-fn efficient_heap_sortedvec_u16() {
-    let mut to_sort: Vec<u16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u16>(&mut to_sort, sorting::efficientsorts::heap::sort);
-}
+fn lexsort_invalid_keys() {
+    let result = panic::catch_unwind(|| {
+        let first: Vec<i32> = vec![1, 2, 3];
+        let second: Vec<i32> = vec![1, 2];
+        lexsort(&[SortKey::ascending(&first), SortKey::ascending(&second)]);
+    });
+    assert!(result.is_err());
 
-/// Validate sorting of a sorted vector (u32).
-#[test]
-fn efficient_heap_sortedvec_u32() {
-    let mut to_sort: Vec<u32> = vec![1, 2, 3, 4, 5];
+    assert!(panic::catch_unwind(|| lexsort(&[])).is_err());
 
-    test_sort_vec::<u32>(&mut to_sort, sorting::efficientsorts::heap::sort);
+    let result = panic::catch_unwind(|| {
+        let mut values: Vec<i32> = vec![1, 2, 3];
+        sorting::permutation::apply_payload(&mut values, &[0, 1]);
+    });
+    assert!(result.is_err());
 }
 
-/// Validate sorting of a sorted vector (u64).
+/// Validate the lexsort export on columns of different types, and its invalid arguments.
 #[test]
-fn efficient_heap_sortedvec_u64() {
-    let mut to_sort: Vec<u64> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u64>(&mut to_sort, sorting::efficientsorts::heap::sort);
-}
+fn lexsort_ffi() {
+    let days: Vec<u32> = vec![2, 1, 2, 1];
+    let scores: Vec<f64> = vec![0.5, 0.2, 0.9, f64::NAN];
+    let columns = [days.as_ptr() as *const libc::c_void, scores.as_ptr() as *const libc::c_void];
+    let types = [ffi::ColumnType::U32 as libc::c_int, ffi::ColumnType::F64 as libc::c_int];
+    let descending: [libc::c_int; 2] = [0, 1];
+    let mut indices: Vec<libc::size_t> = vec![0; 4];
 
+    let status = ffi_lexsort(columns.as_ptr(), types.as_ptr(), descending.as_ptr(), 2, 4,
+                             indices.as_mut_ptr());
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert_eq!(indices, vec![3, 1, 2, 0]);
 
-/// Validate sorting a vector of single precision values (f32).
-#[test]
-fn efficient_heap_sortedvec_f32() {
-    let mut to_sort: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-
-    test_sort_vec::<f32>(&mut to_sort, sorting::efficientsorts::heap::sort);
-}
+    // All the columns are ascending without directions.
+    let status = ffi_lexsort(columns.as_ptr(), types.as_ptr(), std::ptr::null(), 2, 4,
+                             indices.as_mut_ptr());
+    assert_eq!(status, ffi::SortStatus::Ok);
+    assert_eq!(indices, vec![1, 3, 0, 2]);
 
-/// Validate sorting a vector of double precision values (f64).
-#[test]
-fn efficient_heap_sortedvec_f64() {
-    let mut to_sort: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+    let (columns, types, output) = (columns.as_ptr(), types.as_ptr(), indices.as_mut_ptr());
+    let no_columns: *const *const libc::c_void = std::ptr::null();
+    let no_flags: *const libc::c_int = std::ptr::null();
+    assert_eq!(ffi_lexsort(no_columns, std::ptr::null(), no_flags, 0, 0, std::ptr::null_mut()),
+               ffi::SortStatus::Ok);
+    assert_eq!(ffi_lexsort(columns, types, no_flags, 0, 4, output),
+               ffi::SortStatus::InvalidArgument);
+    assert_eq!(ffi_lexsort(no_columns, types, no_flags, 2, 4, output),
+               ffi::SortStatus::NullPointer);
+    assert_eq!(ffi_lexsort(columns, types, no_flags, 2, 4, std::ptr::null_mut()),
+               ffi::SortStatus::NullPointer);
+    assert_eq!(ffi_lexsort(columns, types, no_flags, 2, libc::size_t::MAX, output),
+               ffi::SortStatus::InvalidLength);
 
-    test_sort_vec::<f64>(&mut to_sort, sorting::efficientsorts::heap::sort);
+    let unknown: [libc::c_int; 2] = [ffi::ColumnType::U32 as libc::c_int, 10];
+    assert_eq!(ffi_lexsort(columns, unknown.as_ptr(), no_flags, 2, 4, output),
+               ffi::SortStatus::InvalidArgument);
+    let missing = [days.as_ptr() as *const libc::c_void, std::ptr::null()];
+    assert_eq!(ffi_lexsort(missing.as_ptr(), types, no_flags, 2, 4, output),
+               ffi::SortStatus::NullPointer);
 }
 
 
 // ################################################################################################
 // ################################################################################################
-// Efficient sorts: Merge sort
-
-/// Validate against empty vector (isize).
-#[test]
-fn efficient_merge_empty_vec_isize() {
-    test_empty_vec::<isize>(sorting::efficientsorts::merge::sort);
-}
-
-/// Validate against empty vector (isize).
-#[test]
-fn efficient_merge_empty_vec_usize() {
-    test_empty_vec::<usize>(sorting::efficientsorts::merge::sort);
-}
-
-/// Validate sorting of a const vector (i8).
-#[test]
-fn simple_merge_vec_i8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I8[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i16).
-#[test]
-fn simple_merge_vec_i16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I16[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i32).
-#[test]
-fn simple_merge_vec_i32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (i64).
-#[test]
-fn simple_merge_vec_i64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I64[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u8).
-#[test]
-fn simple_merge_vec_u8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U8[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u16).
-#[test]
-fn simple_merge_vec_u16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u32).
-#[test]
-fn simple_merge_vec_u32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U32[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a const vector (u64).
-#[test]
-fn simple_merge_vec_u64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U64[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+// Custom comparators
+
+/// Validate every algorithm terminates without panicking and keeps the elements when the
+/// comparator is not a total order: random (non-transitive) results or always the same result
+/// (every element is then larger, or smaller, than every other one).
+#[test]
+fn inconsistent_comparator() {
+    let orderings = [Ordering::Less, Ordering::Equal, Ordering::Greater];
+    let mut rng = rand::thread_rng();
+    for &algorithm in registry::all() {
+        for &n in &[3, 10, 100, 1_000] {
+            for constant in 0..4 {
+                let mut to_sort: Vec<usize> = (0..n).collect();
+                algorithm.sort_by(&mut to_sort, |_, _| {
+                    if constant < 3 { orderings[constant] } else { orderings[rng.gen_range(0, 3)] }
+                });
+                to_sort.sort();
+                assert_eq!(to_sort, (0..n).collect::<Vec<usize>>(), "{}", algorithm);
+            }
+        }
+    }
 }
 
 
-/// Validate sorting of a const vector (f32).
-#[test]
-fn simple_merge_vec_f32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F32[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+// ################################################################################################
+// ################################################################################################
+// Sorter trait and registry
 
-/// Validate sorting of a const vector (f64).
-#[test]
-fn simple_merge_vec_f64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F64[..]);
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+/// Sort with any `Sorter`, through static dispatch.
+fn sort_with<S: Sorter, T: PartialOrd>(sorter: S, to_sort: &mut [T]) {
+    sorter.sort(to_sort);
 }
 
-
-/// Validate sorting of a random vector (i8).
+/// Validate every algorithm of the registry can be found by name and sorts.
 #[test]
-fn simple_merge_rand_vec_i8() {
-    let len = 50;
-    let mut to_sort: Vec<i8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i8>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+fn registry_find_and_sort() {
+    assert_eq!(registry::all().len(), 8);
+    for &algorithm in registry::all() {
+        assert_eq!(registry::find(algorithm.name()), Some(algorithm));
+        assert_eq!(format!("{}", algorithm), algorithm.name());
 
-/// Validate sorting of a random vector (i16).
-#[test]
-fn simple_merge_rand_vec_i16() {
-    let len = 50;
-    let mut to_sort: Vec<i16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i16>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+        let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
+        algorithm.sort(&mut to_sort);
+        verify_sorted(&to_sort);
 
-/// Validate sorting of a random vector (i32).
-#[test]
-fn simple_merge_rand_vec_i32() {
-    let len = 50;
-    let mut to_sort: Vec<i32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i32>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+        let mut to_sort: Vec<_> = From::from(&TO_SORT_F64[..]);
+        algorithm.sort_by(&mut to_sort, |a, b| b.partial_cmp(a).unwrap());
+        verify_sorted_by(&to_sort, |a, b| b.partial_cmp(a).unwrap());
 
-/// Validate sorting of a random vector (i64).
-#[test]
-fn simple_merge_rand_vec_i64() {
-    let len = 50;
-    let mut to_sort: Vec<i64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i64>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+        let mut to_sort: Vec<u8> = vec![];
+        algorithm.sort(&mut to_sort);
+        assert!(to_sort.is_empty());
+    }
+    assert_eq!(registry::find("bogosort"), None);
 }
 
-/// Validate sorting of a random vector (u8).
+/// Validate the names match the FFI exports and the algorithms' metadata.
 #[test]
-fn simple_merge_rand_vec_u8() {
-    let len = 50;
-    let mut to_sort: Vec<u8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u8>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+fn registry_metadata() {
+    let names: Vec<_> = registry::all().iter().map(|a| a.name()).collect();
+    assert_eq!(names, vec!["bubblesort", "insertionsort", "selectionsort",
+                           "quicksort", "mergesort", "heapsort", "introsort", "timsort"]);
 
-/// Validate sorting of a random vector (u16).
-#[test]
-fn simple_merge_rand_vec_u16() {
-    let len = 50;
-    let mut to_sort: Vec<u16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u16>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    let stable: Vec<_> = registry::all().iter().filter(|a| a.is_stable()).map(|a| a.name()).collect();
+    assert_eq!(stable, vec!["bubblesort", "insertionsort", "mergesort", "timsort"]);
 
-/// Validate sorting of a random vector (u32).
-#[test]
-fn simple_merge_rand_vec_u32() {
-    let len = 50;
-    let mut to_sort: Vec<u32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u32>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    for &algorithm in registry::all() {
+        let complexity = algorithm.complexity();
+        assert!(complexity.best <= complexity.average);
+        assert!(complexity.average <= complexity.worst);
+        assert_eq!(algorithm.is_in_place(), algorithm.allocation() != Allocation::Linear);
+    }
 
-/// Validate sorting of a random vector (u64).
-#[test]
-fn simple_merge_rand_vec_u64() {
-    let len = 50;
-    let mut to_sort: Vec<u64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u64>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+    assert_eq!(registry::find("heapsort").unwrap().complexity().worst, Order::Linearithmic);
+    assert_eq!(registry::find("quicksort").unwrap().complexity().worst, Order::Quadratic);
+    assert_eq!(format!("{}", Order::Linearithmic), "O(N log N)");
 }
 
-
-/// Validate sorting of a random vector (f32).
+/// Validate the zero-sized types can be used through static dispatch.
 #[test]
-fn simple_merge_rand_vec_f32() {
-    let len = 50;
-    let mut to_sort: Vec<f32> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f32>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
+fn sorter_static_dispatch() {
+    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
+    sort_with(sorting::sorter::Merge, &mut to_sort);
     verify_sorted(&to_sort);
-}
 
-/// Validate sorting of a random vector (f64).
-#[test]
-fn simple_merge_rand_vec_f64() {
-    let len = 50;
-    let mut to_sort: Vec<f64> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f64>>();
-    sorting::efficientsorts::merge::sort(&mut to_sort);
+    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
+    sort_with(sorting::sorter::Tim, &mut to_sort);
     verify_sorted(&to_sort);
 }
 
 
-/// Validate sorting of a sorted vector (isize).
-#[test]
-fn efficient_merge_sortedvec_isize() {
-    let mut to_sort: Vec<isize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<isize>(&mut to_sort, sorting::efficientsorts::merge::sort);
-}
-
-/// Validate sorting of a sorted vector (usize).
-#[test]
-fn efficient_merge_sortedvec_usize() {
-    let mut to_sort: Vec<usize> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<usize>(&mut to_sort, sorting::efficientsorts::merge::sort);
-}
-
-/// Validate sorting of a sorted vector (i8).
-#[test]
-fn efficient_merge_sortedvec_i8() {
-    let mut to_sort: Vec<i8> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i8>(&mut to_sort, sorting::efficientsorts::merge::sort);
-}
-
-/// Validate sorting of a sorted vector (i16).
-#[test]
-fn efficient_merge_sortedvec_i16() {
-    let mut to_sort: Vec<i16> = vec![1, 2, 3, 4, 5];
+// ################################################################################################
+// ################################################################################################
+// Generic C ABI
+
+type GenericSort = extern "C" fn(*mut libc::c_void, libc::size_t, libc::size_t,
+                                 Option<ffi::Comparator>, *mut libc::c_void) -> ffi::SortStatus;
+
+/// The `qsort()`-like export of every algorithm, with its name.
+const GENERIC_SORTS: [(&str, GenericSort); 8] = [
+    ("bubblesort",    sorting::simplesorts::bubble::ffi_bubblesort_generic),
+    ("insertionsort", sorting::simplesorts::insertion::ffi_insertionsort_generic),
+    ("selectionsort", sorting::simplesorts::selection::ffi_selectionsort_generic),
+    ("quicksort",     sorting::efficientsorts::quick::ffi_quicksort_generic),
+    ("mergesort",     sorting::efficientsorts::merge::ffi_mergesort_generic),
+    ("heapsort",      sorting::efficientsorts::heap::ffi_heapsort_generic),
+    ("introsort",     sorting::efficientsorts::intro::ffi_introsort_generic),
+    ("timsort",       sorting::efficientsorts::tim::ffi_timsort_generic),
+];
+
+/// A record as it would be defined in C.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Record {
+    key: u16,
+    id: u32,
+    weight: f64,
+}
+
+/// Build records with few distinct keys, the ids giving their original order.
+fn records(n: usize) -> Vec<Record> {
+    let mut rng = rand::thread_rng();
+    (0..n).map(|i| Record { key: rng.gen_range(0, 8), id: i as u32, weight: rng.gen() }).collect()
+}
+
+/// C comparator on the records' key. The context counts the calls.
+extern "C" fn compare_records(a: *const libc::c_void, b: *const libc::c_void,
+                              ctx: *mut libc::c_void) -> libc::c_int {
+    let (a, b) = unsafe { (&*(a as *const Record), &*(b as *const Record)) };
+    let calls = unsafe { &mut *(ctx as *mut usize) };
+    *calls += 1;
+    a.key as libc::c_int - b.key as libc::c_int
+}
+
+/// C comparator on `i32`, the context giving the direction (1 or -1).
+extern "C" fn compare_i32(a: *const libc::c_void, b: *const libc::c_void,
+                          ctx: *mut libc::c_void) -> libc::c_int {
+    let (a, b) = unsafe { (*(a as *const i32), *(b as *const i32)) };
+    let direction = unsafe { *(ctx as *const libc::c_int) };
+    direction * (a.cmp(&b) as libc::c_int)
+}
+
+/// Validate every algorithm sorts records through the generic export, stable ones keeping ties
+/// in their original order.
+#[test]
+fn generic_records() {
+    for &(name, generic_sort) in GENERIC_SORTS.iter() {
+        let original = records(500);
+        let mut to_sort = original.clone();
+        let mut calls: usize = 0;
+        let status = generic_sort(to_sort.as_mut_ptr() as *mut libc::c_void,
+                                  to_sort.len() as libc::size_t,
+                                  std::mem::size_of::<Record>() as libc::size_t,
+                                  Some(compare_records), &mut calls as *mut usize as *mut libc::c_void);
+        assert_eq!(status, ffi::SortStatus::Ok);
+        assert!(calls > 0);
+        verify_sorted_by(&to_sort, |a, b| a.key.cmp(&b.key));
+
+        // Nothing lost, nothing duplicated.
+        let mut ids: Vec<u32> = to_sort.iter().map(|r| r.id).collect();
+        ids.sort();
+        assert_eq!(ids, (0..500).collect::<Vec<u32>>());
+        for record in &to_sort {
+            assert_eq!(*record, original[record.id as usize]);
+        }
 
-    test_sort_vec::<i16>(&mut to_sort, sorting::efficientsorts::merge::sort);
+        if registry::find(name).unwrap().is_stable() {
+            verify_sorted_by(&to_sort, |a, b| a.key.cmp(&b.key).then(a.id.cmp(&b.id)));
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (i32).
-#[test]
-fn efficient_merge_sortedvec_i32() {
-    let mut to_sort: Vec<i32> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i32>(&mut to_sort, sorting::efficientsorts::merge::sort);
+/// Validate the context pointer is passed to the comparator.
+#[test]
+fn generic_context() {
+    for &(_, generic_sort) in GENERIC_SORTS.iter() {
+        let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
+        let mut direction: libc::c_int = -1;
+        let status = generic_sort(to_sort.as_mut_ptr() as *mut libc::c_void,
+                                  to_sort.len() as libc::size_t,
+                                  std::mem::size_of::<i32>() as libc::size_t, Some(compare_i32),
+                                  &mut direction as *mut libc::c_int as *mut libc::c_void);
+        assert_eq!(status, ffi::SortStatus::Ok);
+        verify_sorted_by(&to_sort, |a, b| b.cmp(a));
+
+        let mut to_sort: Vec<i32> = vec![42];
+        direction = 1;
+        let status = generic_sort(to_sort.as_mut_ptr() as *mut libc::c_void,
+                                  to_sort.len() as libc::size_t,
+                                  std::mem::size_of::<i32>() as libc::size_t, Some(compare_i32),
+                                  &mut direction as *mut libc::c_int as *mut libc::c_void);
+        assert_eq!(status, ffi::SortStatus::Ok);
+        assert_eq!(to_sort, vec![42]);
+    }
 }
 
-/// Validate sorting of a sorted vector (i64).
-#[test]
-fn efficient_merge_sortedvec_i64() {
-    let mut to_sort: Vec<i64> = vec![1, 2, 3, 4, 5];
 
-    test_sort_vec::<i64>(&mut to_sort, sorting::efficientsorts::merge::sort);
-}
+// ################################################################################################
+// ################################################################################################
+// FFI error codes
 
-/// Validate sorting of a sorted vector (u8).
-#[test]
-fn efficient_merge_sortedvec_u8() {
-    let mut to_sort: Vec<u8> = vec![1, 2, 3, 4, 5];
+type TypedSort = extern "C" fn(*mut libc::int32_t, libc::size_t) -> ffi::SortStatus;
 
-    test_sort_vec::<u8>(&mut to_sort, sorting::efficientsorts::merge::sort);
-}
+/// Some of the exports sorting `i32`.
+const TYPED_SORTS: [TypedSort; 4] = [
+    sorting::simplesorts::insertion::ffi_insertionsort_i32,
+    sorting::efficientsorts::quick::ffi_quicksort_i32,
+    sorting::efficientsorts::tim::ffi_timsort_i32,
+    sorting::efficientsorts::radix::ffi_radixsort_i32,
+];
 
-/// Validate sorting of a sorted vector (u16).
+/// Validate empty arrays are a no-op, whatever the pointer.
 #[test]
-fn efficient_merge_sortedvec_u16() {
-    let mut to_sort: Vec<u16> = vec![1, 2, 3, 4, 5];
+fn ffi_empty_array() {
+    for typed_sort in TYPED_SORTS.iter() {
+        assert_eq!(typed_sort(std::ptr::null_mut(), 0), ffi::SortStatus::Ok);
+        let mut to_sort: Vec<i32> = vec![];
+        assert_eq!(typed_sort(to_sort.as_mut_ptr(), 0), ffi::SortStatus::Ok);
+    }
+    let status = sorting::ffi_sort_f64(std::ptr::null_mut(), 0, NanPolicy::Last as libc::c_int);
+    assert_eq!(status, ffi::SortStatus::Ok);
 
-    test_sort_vec::<u16>(&mut to_sort, sorting::efficientsorts::merge::sort);
+    for &(_, generic_sort) in GENERIC_SORTS.iter() {
+        let status = generic_sort(std::ptr::null_mut(), 0, 4, Some(compare_i32),
+                                  std::ptr::null_mut());
+        assert_eq!(status, ffi::SortStatus::Ok);
+    }
 }
 
-/// Validate sorting of a sorted vector (u32).
+/// Validate invalid arguments are reported instead of panicking.
 #[test]
-fn efficient_merge_sortedvec_u32() {
-    let mut to_sort: Vec<u32> = vec![1, 2, 3, 4, 5];
+fn ffi_invalid_arguments() {
+    for typed_sort in TYPED_SORTS.iter() {
+        assert_eq!(typed_sort(std::ptr::null_mut(), 16), ffi::SortStatus::NullPointer);
 
-    test_sort_vec::<u32>(&mut to_sort, sorting::efficientsorts::merge::sort);
-}
+        let mut to_sort: Vec<i32> = vec![3, 2, 1];
+        let status = typed_sort(to_sort.as_mut_ptr(), libc::size_t::MAX);
+        assert_eq!(status, ffi::SortStatus::InvalidLength);
+        assert_eq!(to_sort, vec![3, 2, 1]);
 
-/// Validate sorting of a sorted vector (u64).
-#[test]
-fn efficient_merge_sortedvec_u64() {
-    let mut to_sort: Vec<u64> = vec![1, 2, 3, 4, 5];
+        assert_eq!(typed_sort(to_sort.as_mut_ptr(), 3), ffi::SortStatus::Ok);
+        assert_eq!(to_sort, vec![1, 2, 3]);
+    }
 
-    test_sort_vec::<u64>(&mut to_sort, sorting::efficientsorts::merge::sort);
+    for &(_, generic_sort) in GENERIC_SORTS.iter() {
+        let mut direction: libc::c_int = 1;
+        let ctx = &mut direction as *mut libc::c_int as *mut libc::c_void;
+        let mut to_sort: Vec<i32> = vec![3, 2, 1];
+        let base = to_sort.as_mut_ptr() as *mut libc::c_void;
+
+        let status = generic_sort(std::ptr::null_mut(), 3, 4, Some(compare_i32), ctx);
+        assert_eq!(status, ffi::SortStatus::NullPointer);
+        let status = generic_sort(base, 3, 4, None, ctx);
+        assert_eq!(status, ffi::SortStatus::ComparatorError);
+        let status = generic_sort(base, libc::size_t::MAX / 2, 4, Some(compare_i32), ctx);
+        assert_eq!(status, ffi::SortStatus::InvalidLength);
+        assert_eq!(to_sort, vec![3, 2, 1]);
+
+        let status = generic_sort(base, 3, 4, Some(compare_i32), ctx);
+        assert_eq!(status, ffi::SortStatus::Ok);
+        assert_eq!(to_sort, vec![1, 2, 3]);
+    }
 }
 
 
-/// Validate sorting a vector of single precision values (f32).
-#[test]
-fn efficient_merge_sortedvec_f32() {
-    let mut to_sort: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-
-    test_sort_vec::<f32>(&mut to_sort, sorting::efficientsorts::merge::sort);
+type FloatSort = extern "C" fn(*mut libc::c_double, libc::size_t, libc::c_int) -> ffi::SortStatus;
+
+/// Some of the exports sorting `f64`.
+const FLOAT_SORTS: [FloatSort; 4] = [
+    sorting::ffi_sort_f64,
+    sorting::simplesorts::insertion::ffi_insertionsort_f64,
+    sorting::efficientsorts::quick::ffi_quicksort_f64,
+    sorting::efficientsorts::tim::ffi_timsort_f64,
+];
+
+/// Validate the NaN policy given to the exports sorting floating point values.
+#[test]
+fn ffi_nan_policy() {
+    let nan = f64::NAN;
+    let negative_nan = -f64::NAN;
+    for float_sort in FLOAT_SORTS.iter() {
+        let mut to_sort: Vec<f64> = vec![2.0, nan, -1.0, negative_nan, 0.0];
+        assert_eq!(float_sort(to_sort.as_mut_ptr(), 5, NanPolicy::First as libc::c_int),
+                   ffi::SortStatus::Ok);
+        assert!(to_sort[0].is_nan() && to_sort[1].is_nan());
+        assert_eq!(to_sort[2..].to_vec(), vec![-1.0, 0.0, 2.0]);
+
+        assert_eq!(float_sort(to_sort.as_mut_ptr(), 5, NanPolicy::Last as libc::c_int),
+                   ffi::SortStatus::Ok);
+        assert_eq!(to_sort[..3].to_vec(), vec![-1.0, 0.0, 2.0]);
+        assert!(to_sort[3].is_nan() && to_sort[4].is_nan());
+
+        assert_eq!(float_sort(to_sort.as_mut_ptr(), 5, NanPolicy::TotalOrder as libc::c_int),
+                   ffi::SortStatus::Ok);
+        assert!(to_sort[0].is_nan() && to_sort[0].is_sign_negative());
+        assert_eq!(to_sort[1..4].to_vec(), vec![-1.0, 0.0, 2.0]);
+        assert!(to_sort[4].is_nan() && to_sort[4].is_sign_positive());
+
+        // The array is left untouched when a NaN is found.
+        let mut to_sort: Vec<f64> = vec![2.0, nan, -1.0];
+        assert_eq!(float_sort(to_sort.as_mut_ptr(), 3, NanPolicy::Error as libc::c_int),
+                   ffi::SortStatus::InvalidArgument);
+        assert_eq!(to_sort[0], 2.0);
+        assert!(to_sort[1].is_nan());
+        assert_eq!(to_sort[2], -1.0);
+
+        let mut to_sort: Vec<f64> = vec![2.0, -1.0];
+        assert_eq!(float_sort(to_sort.as_mut_ptr(), 2, NanPolicy::Error as libc::c_int),
+                   ffi::SortStatus::Ok);
+        assert_eq!(to_sort, vec![-1.0, 2.0]);
+
+        assert_eq!(float_sort(to_sort.as_mut_ptr(), 2, 4), ffi::SortStatus::InvalidArgument);
+        assert_eq!(float_sort(to_sort.as_mut_ptr(), 2, -1), ffi::SortStatus::InvalidArgument);
+    }
 }
 
-/// Validate sorting a vector of double precision values (f64).
-#[test]
-fn efficient_merge_sortedvec_f64() {
-    let mut to_sort: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-
-    test_sort_vec::<f64>(&mut to_sort, sorting::efficientsorts::merge::sort);
+/// Validate the selection, argsort and payload exports follow the NaN policy they are given.
+#[test]
+fn ffi_nan_policy_select_argsort_payload() {
+    let nan = f64::NAN;
+    let first = NanPolicy::First as libc::c_int;
+    let error = NanPolicy::Error as libc::c_int;
+
+    let mut to_select: Vec<f64> = vec![2.0, nan, -1.0];
+    assert_eq!(sorting::efficientsorts::select::ffi_select_nth_f64(to_select.as_mut_ptr(), 3, 0,
+                                                                   first),
+               ffi::SortStatus::Ok);
+    assert!(to_select[0].is_nan());
+    assert_eq!(sorting::efficientsorts::select::ffi_select_nth_f64(to_select.as_mut_ptr(), 3, 0,
+                                                                   error),
+               ffi::SortStatus::InvalidArgument);
+    assert!(to_select[0].is_nan());
+    assert_eq!(sorting::efficientsorts::select::ffi_select_nth_f64(to_select.as_mut_ptr(), 3, 0,
+                                                                   4),
+               ffi::SortStatus::InvalidArgument);
+
+    let input: Vec<f64> = vec![2.0, nan, -1.0];
+    let mut indices: Vec<libc::size_t> = vec![7; 3];
+    assert_eq!(sorting::permutation::ffi_argsort_f64(input.as_ptr(), 3, indices.as_mut_ptr(),
+                                                     first),
+               ffi::SortStatus::Ok);
+    assert_eq!(indices, vec![1, 2, 0]);
+    let mut indices: Vec<libc::size_t> = vec![7; 3];
+    assert_eq!(sorting::permutation::ffi_argsort_f64(input.as_ptr(), 3, indices.as_mut_ptr(),
+                                                     error),
+               ffi::SortStatus::InvalidArgument);
+    assert_eq!(indices, vec![7; 3]);
+    assert_eq!(sorting::permutation::ffi_argsort_f64(input.as_ptr(), 3, indices.as_mut_ptr(), -1),
+               ffi::SortStatus::InvalidArgument);
+
+    let mut keys: Vec<f64> = vec![2.0, nan, -1.0];
+    let mut values: Vec<u16> = vec![2, 0, 1];
+    let values_pointer = values.as_mut_ptr() as *mut libc::c_void;
+    assert_eq!(sorting::efficientsorts::merge::ffi_mergesort_payload_f64(keys.as_mut_ptr(), 3,
+                                                                         values_pointer, 2,
+                                                                         error),
+               ffi::SortStatus::InvalidArgument);
+    assert_eq!(values, vec![2, 0, 1]);
+    let values_pointer = values.as_mut_ptr() as *mut libc::c_void;
+    assert_eq!(sorting::efficientsorts::merge::ffi_mergesort_payload_f64(keys.as_mut_ptr(), 3,
+                                                                         values_pointer, 2,
+                                                                         first),
+               ffi::SortStatus::Ok);
+    assert!(keys[0].is_nan());
+    assert_eq!(keys[1..].to_vec(), vec![-1.0, 2.0]);
+    assert_eq!(values, vec![0, 1, 2]);
 }
 
-
 // ################################################################################################
 // ################################################################################################
-// Efficient sorts: Quicksort
-
-/// Validate against empty vector (isize).
-#[test]
-fn efficient_quick_empty_vec_isize() {
-    test_empty_vec::<isize>(sorting::efficientsorts::quick::sort);
-}
+// C interface
+
+/// Validate the generated header, the pkg-config file and the static library from C, as installed
+/// by the `install-c` binary, if a C compiler is available.
+#[test]
+fn c_interface() {
+    let prefix = Path::new(env!("CARGO_TARGET_TMPDIR")).join("c_interface");
+    let installed = Command::new(env!("CARGO_BIN_EXE_install-c")).arg(&prefix).output().unwrap();
+    assert!(installed.status.success(), "{}", String::from_utf8_lossy(&installed.stderr));
+    let program = prefix.join("test_sorting_c");
+
+    // Link the static library as pkg-config would.
+    let pc = std::fs::read_to_string(prefix.join("lib").join("pkgconfig").join("sorting.pc"))
+                 .unwrap();
+    let private_libs: Vec<&str> = pc.lines()
+                                    .filter_map(|line| line.strip_prefix("Libs.private:"))
+                                    .flat_map(|libs| libs.split_whitespace())
+                                    .collect();
+
+    let compiled = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg("tests/c/test_sorting.c")
+        .arg("-std=c99").arg("-Wall").arg("-Werror")
+        .arg("-I").arg(prefix.join("include"))
+        .arg(prefix.join("lib").join("libsorting.a"))
+        .args(&private_libs)
+        .arg("-o").arg(&program)
+        .status();
+    match compiled {
+        Ok(status) => assert!(status.success(), "Compilation of tests/c/test_sorting.c failed"),
+        Err(_) => {
+            println!("No C compiler found, skipping the C interface test");
+            return;
+        },
+    }
 
-/// Validate against empty vector (isize).
-#[test]
-fn efficient_quick_empty_vec_usize() {
-    test_empty_vec::<usize>(sorting::efficientsorts::quick::sort);
+    let output = Command::new(&program).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
 }
 
-/// Validate sorting of a const vector (i8).
-#[test]
-fn efficient_quick_vec_i8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I8[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
 
-/// Validate sorting of a const vector (i16).
-#[test]
-fn efficient_quick_vec_i16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I16[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+// ################################################################################################
+// ################################################################################################
+// Input patterns
+
+/// Verify every algorithm of the registry sorts every pattern.
+fn test_patterns<T: patterns::Element + PartialOrd + Debug>() {
+    for &pattern in patterns::all() {
+        for &n in &[0, 1, 2, 3, 17, 200] {
+            let input: Vec<T> = patterns::generate(pattern, n, 42);
+            assert_eq!(input.len(), n);
+            for &algorithm in registry::all() {
+                let mut to_sort = input.clone();
+                algorithm.sort(&mut to_sort);
+                verify_sorted(&to_sort);
+            }
+        }
+    }
 }
 
-/// Validate sorting of a const vector (i32).
+/// Validate all the algorithms on all the patterns (i8).
 #[test]
-fn efficient_quick_vec_i32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I32[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn patterns_sort_i8() {
+    test_patterns::<i8>();
 }
 
-/// Validate sorting of a const vector (i64).
+/// Validate all the algorithms on all the patterns (u16).
 #[test]
-fn efficient_quick_vec_i64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_I64[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn patterns_sort_u16() {
+    test_patterns::<u16>();
 }
 
-/// Validate sorting of a const vector (u8).
+/// Validate all the algorithms on all the patterns (i64).
 #[test]
-fn efficient_quick_vec_u8() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U8[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn patterns_sort_i64() {
+    test_patterns::<i64>();
 }
 
-/// Validate sorting of a const vector (u16).
+/// Validate all the algorithms on all the patterns (usize).
 #[test]
-fn efficient_quick_vec_u16() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U16[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn patterns_sort_usize() {
+    test_patterns::<usize>();
 }
 
-/// Validate sorting of a const vector (u32).
+/// Validate all the algorithms on all the patterns (f32).
 #[test]
-fn efficient_quick_vec_u32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U32[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn patterns_sort_f32() {
+    test_patterns::<f32>();
 }
 
-/// Validate sorting of a const vector (u64).
+/// Validate the shape of the patterns.
 #[test]
-fn efficient_quick_vec_u64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_U64[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+fn patterns_shape() {
+    let n = 1_000;
+    let sorted: Vec<i32> = patterns::generate(Pattern::Sorted, n, 0);
+    assert_eq!(sorted, (-500..500).collect::<Vec<i32>>());
 
+    let reversed: Vec<u32> = patterns::generate(Pattern::Reversed, n, 0);
+    assert_eq!(reversed, (0..1_000).rev().collect::<Vec<u32>>());
 
-/// Validate sorting of a const vector (f32).
-#[test]
-fn efficient_quick_vec_f32() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F32[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    let organ_pipe: Vec<u32> = patterns::generate(Pattern::OrganPipe, 7, 0);
+    assert_eq!(organ_pipe, vec![0, 1, 2, 3, 2, 1, 0]);
 
-/// Validate sorting of a const vector (f64).
-#[test]
-fn efficient_quick_vec_f64() {
-    let mut to_sort: Vec<_> = From::from(&TO_SORT_F64[..]);
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    let sawtooth: Vec<u32> = patterns::generate(Pattern::Sawtooth, 9, 0);
+    assert_eq!(sawtooth, vec![0, 1, 2, 0, 1, 2, 0, 1, 2]);
 
+    let all_equal: Vec<f64> = patterns::generate(Pattern::AllEqual, n, 0);
+    assert!(all_equal.iter().all(|&x| x == all_equal[0]));
 
-/// Validate sorting of a random vector (i8).
-#[test]
-fn efficient_quick_rand_vec_i8() {
-    let len = 50;
-    let mut to_sort: Vec<i8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i8>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    let mut few_unique: Vec<u64> = patterns::generate(Pattern::FewUnique(5), n, 0);
+    few_unique.sort();
+    few_unique.dedup();
+    assert_eq!(few_unique, vec![0, 1, 2, 3, 4]);
 
-/// Validate sorting of a random vector (i16).
-#[test]
-fn efficient_quick_rand_vec_i16() {
-    let len = 50;
-    let mut to_sort: Vec<i16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i16>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    let perturbed: Vec<u32> = patterns::generate(Pattern::Perturbed(10), n, 0);
+    let moved = perturbed.iter().enumerate().filter(|&(i, &x)| i as u32 != x).count();
+    assert!(moved > 0 && moved <= n / 10);
+    let mut values = perturbed.clone();
+    values.sort();
+    assert_eq!(values, (0..1_000).collect::<Vec<u32>>());
 
-/// Validate sorting of a random vector (i32).
-#[test]
-fn efficient_quick_rand_vec_i32() {
-    let len = 50;
-    let mut to_sort: Vec<i32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i32>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
+    // Zipf: the smallest value is the most frequent, twice as frequent as the second.
+    let zipf: Vec<u32> = patterns::generate(Pattern::Zipf, 100_000, 0);
+    let count = |v| zipf.iter().filter(|&&x| x == v).count() as f64;
+    assert!((count(0) / count(1) - 2.0).abs() < 0.2);
 
-/// Validate sorting of a random vector (i64).
-#[test]
-fn efficient_quick_rand_vec_i64() {
-    let len = 50;
-    let mut to_sort: Vec<i64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49, 51)).collect::<Vec<i64>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+    // Values larger than the type's range are scaled, keeping the order.
+    let sorted: Vec<u8> = patterns::generate(Pattern::Sorted, n, 0);
+    assert_eq!((sorted[0], sorted[n - 1]), (0, 255));
+    verify_sorted(&sorted);
 }
 
-/// Validate sorting of a random vector (u8).
+/// Validate the generation is reproducible and depends on the seed.
 #[test]
-fn efficient_quick_rand_vec_u8() {
-    let len = 50;
-    let mut to_sort: Vec<u8> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u8>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u16).
-#[test]
-fn efficient_quick_rand_vec_u16() {
-    let len = 50;
-    let mut to_sort: Vec<u16> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u16>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u32).
-#[test]
-fn efficient_quick_rand_vec_u32() {
-    let len = 50;
-    let mut to_sort: Vec<u32> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u32>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-/// Validate sorting of a random vector (u64).
-#[test]
-fn efficient_quick_rand_vec_u64() {
-    let len = 50;
-    let mut to_sort: Vec<u64> = vec![0; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(1, 101)).collect::<Vec<u64>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
-}
-
-
-/// Validate sorting of a random vector (f32).
-#[test]
-fn efficient_quick_rand_vec_f32() {
-    let len = 50;
-    let mut to_sort: Vec<f32> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f32>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn patterns_seed() {
+    for &pattern in patterns::all() {
+        let a: Vec<i32> = patterns::generate(pattern, 500, 1);
+        let b: Vec<i32> = patterns::generate(pattern, 500, 1);
+        assert_eq!(a, b);
+    }
+    let a: Vec<i32> = patterns::generate(Pattern::Random, 500, 1);
+    let b: Vec<i32> = patterns::generate(Pattern::Random, 500, 2);
+    assert!(a != b);
 }
 
-/// Validate sorting of a random vector (f64).
+/// Validate the patterns' names.
 #[test]
-fn efficient_quick_rand_vec_f64() {
-    let len = 50;
-    let mut to_sort: Vec<f64> = vec![0.; len];
-    to_sort = to_sort.iter().map(|_| rand::thread_rng().gen_range(-49.0, 51.0)).collect::<Vec<f64>>();
-    sorting::efficientsorts::quick::sort(&mut to_sort);
-    verify_sorted(&to_sort);
+fn patterns_names() {
+    for &pattern in patterns::all() {
+        assert_eq!(pattern.to_string().parse::<Pattern>(), Ok(pattern));
+    }
+    assert_eq!("few-unique".parse::<Pattern>(), Ok(Pattern::FewUnique(10)));
+    assert_eq!("perturbed:25".parse::<Pattern>(), Ok(Pattern::Perturbed(25)));
+    assert!("sorted:3".parse::<Pattern>().is_err());
+    assert!("bogus".parse::<Pattern>().is_err());
 }
 
 
-/// Validate sorting of a sorted vector (isize).
-#[test]
-fn efficient_quick_sortedvec_isize() {
-    let mut to_sort: Vec<isize> = vec![1, 2, 3, 4, 5];
+// ################################################################################################
+// ################################################################################################
+// Instrumentation
 
-    test_sort_vec::<isize>(&mut to_sort, sorting::efficientsorts::quick::sort);
+/// Observer applying the reported swaps and writes to its own copy of the vector.
+struct Replay<T> {
+    array: Vec<T>,
 }
 
-/// Validate sorting of a sorted vector (usize).
-#[test]
-fn efficient_quick_sortedvec_usize() {
-    let mut to_sort: Vec<usize> = vec![1, 2, 3, 4, 5];
+impl<T: Clone> Observer<T> for Replay<T> {
+    fn swap(&mut self, i: usize, j: usize) {
+        self.array.swap(i, j);
+    }
 
-    test_sort_vec::<usize>(&mut to_sort, sorting::efficientsorts::quick::sort);
+    fn write(&mut self, i: usize, value: &T) {
+        self.array[i] = value.clone();
+    }
 }
 
-/// Validate sorting of a sorted vector (i8).
+/// Validate the operations reported by every algorithm, replaying them on a copy of the vector.
 #[test]
-fn efficient_quick_sortedvec_i8() {
-    let mut to_sort: Vec<i8> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i8>(&mut to_sort, sorting::efficientsorts::quick::sort);
+fn instrument_replay() {
+    for &pattern in patterns::all() {
+        for &n in &[0, 1, 2, 3, 17, 100, 1_000] {
+            let input: Vec<i32> = patterns::generate(pattern, n, 7);
+            for &algorithm in registry::all() {
+                let mut to_sort = input.clone();
+                let mut replay = Replay { array: input.clone() };
+                algorithm.sort_by_observed(&mut to_sort, |a, b| a.cmp(b), &mut replay);
+                verify_sorted(&to_sort);
+                assert_eq!(replay.array, to_sort, "{} on {} ({})", algorithm, pattern, n);
+            }
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (i16).
+/// Validate the number of operations on an already sorted vector.
 #[test]
-fn efficient_quick_sortedvec_i16() {
-    let mut to_sort: Vec<i16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i16>(&mut to_sort, sorting::efficientsorts::quick::sort);
+fn instrument_sorted() {
+    let n = 1_000;
+    for &algorithm in &["bubblesort", "insertionsort", "timsort"] {
+        let mut to_sort: Vec<i32> = (0..n as i32).collect();
+        let counts = instrument::count(&registry::find(algorithm).unwrap(), &mut to_sort,
+                                       |a, b| a.cmp(b));
+        assert_eq!(counts.comparisons, n - 1, "{}", algorithm);
+        assert_eq!(counts.swaps, 0, "{}", algorithm);
+        assert_eq!(counts.moves, 0, "{}", algorithm);
+    }
 }
 
-/// Validate sorting of a sorted vector (i32).
+/// Validate the number of operations on a reversed vector.
 #[test]
-fn efficient_quick_sortedvec_i32() {
-    let mut to_sort: Vec<i32> = vec![1, 2, 3, 4, 5];
+fn instrument_reversed() {
+    let n = 1_000;
+    for &algorithm in &["bubblesort", "insertionsort"] {
+        let mut to_sort: Vec<i32> = (0..n as i32).rev().collect();
+        let counts = instrument::count(&registry::find(algorithm).unwrap(), &mut to_sort,
+                                       |a, b| a.cmp(b));
+        assert_eq!(counts.comparisons, n * (n - 1) / 2, "{}", algorithm);
+        assert_eq!(counts.swaps, n * (n - 1) / 2, "{}", algorithm);
+    }
 
-    test_sort_vec::<i32>(&mut to_sort, sorting::efficientsorts::quick::sort);
+    // Timsort finds a single strictly descending run and reverses it.
+    let mut to_sort: Vec<i32> = (0..n as i32).rev().collect();
+    let counts = instrument::count(&Tim, &mut to_sort, |a, b| a.cmp(b));
+    assert_eq!(counts.comparisons, n - 1);
+    assert_eq!(counts.swaps, n / 2);
+
+    // Selection sort does at most N-1 swaps.
+    let mut to_sort: Vec<i32> = patterns::generate(Pattern::Random, n, 0);
+    let counts = instrument::count(&Selection, &mut to_sort, |a, b| a.cmp(b));
+    assert!(counts.swaps < n);
+}
+
+/// Validate the comparisons of the O(N log N) algorithms stay within a small multiple of N log2 N:
+/// on all patterns if that is their worst case, on random inputs otherwise.
+#[test]
+fn instrument_linearithmic() {
+    let n = 10_000;
+    // log2(10_000) is about 13.3. Introsort falling back to heap sort is the worst.
+    let bound = 4 * n * 14;
+    for &algorithm in registry::all() {
+        let complexity = algorithm.complexity();
+        if complexity.average != Order::Linearithmic {
+            continue;
+        }
+        for &pattern in patterns::all() {
+            let random = pattern == Pattern::Random || pattern == Pattern::Perturbed(10);
+            if complexity.worst != Order::Linearithmic && !random {
+                continue;
+            }
+            let mut to_sort: Vec<i32> = patterns::generate(pattern, n, 0);
+            let counts = instrument::count(&algorithm, &mut to_sort, |a, b| a.cmp(b));
+            assert!(counts.comparisons < bound, "{} on {}: {:?}", algorithm, pattern, counts);
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (i64).
+/// Validate the allocations match `Sorter::allocation()`.
 #[test]
-fn efficient_quick_sortedvec_i64() {
-    let mut to_sort: Vec<i64> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<i64>(&mut to_sort, sorting::efficientsorts::quick::sort);
+fn instrument_allocations() {
+    for &algorithm in registry::all() {
+        let mut to_sort: Vec<i32> = patterns::generate(Pattern::Random, 1_000, 0);
+        let counts = instrument::count(&algorithm, &mut to_sort, |a, b| a.cmp(b));
+        match algorithm.allocation() {
+            Allocation::None     => assert_eq!(counts.allocations, 0, "{}", algorithm),
+            Allocation::Constant => assert!(counts.allocations > 0, "{}", algorithm),
+            // A single buffer, allocated once.
+            Allocation::Linear   => assert_eq!(counts.allocations, 1, "{}", algorithm),
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (u8).
-#[test]
-fn efficient_quick_sortedvec_u8() {
-    let mut to_sort: Vec<u8> = vec![1, 2, 3, 4, 5];
 
-    test_sort_vec::<u8>(&mut to_sort, sorting::efficientsorts::quick::sort);
+// ################################################################################################
+// ################################################################################################
+// Trace
+
+/// Validate replaying the recorded events on the initial vector gives the sorted vector.
+#[test]
+fn trace_replay() {
+    for &pattern in patterns::all() {
+        let input: Vec<i32> = patterns::generate(pattern, 300, 3);
+        for &algorithm in registry::all() {
+            let mut to_sort = input.clone();
+            let events = trace::record(&algorithm, &mut to_sort, |a, b| a.cmp(b));
+            let mut replayed = input.clone();
+            for event in &events {
+                event.apply(&mut replayed);
+            }
+            assert_eq!(replayed, to_sort, "{} on {}", algorithm, pattern);
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (u16).
-#[test]
-fn efficient_quick_sortedvec_u16() {
-    let mut to_sort: Vec<u16> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u16>(&mut to_sort, sorting::efficientsorts::quick::sort);
+/// Validate every call to the comparator is recorded, with the indices of the elements compared
+/// (for the algorithms without a temporary buffer).
+#[test]
+fn trace_comparisons() {
+    for &pattern in patterns::all() {
+        let input: Vec<i32> = patterns::generate(pattern, 300, 5);
+        for &algorithm in registry::all() {
+            // Sort the indices of the elements so the comparator knows which ones it compares.
+            let mut indices: Vec<usize> = (0..input.len()).collect();
+            let mut compared = Vec::new();
+            let events = trace::record(&algorithm, &mut indices, |&a, &b| {
+                compared.push((a, b));
+                input[a].cmp(&input[b])
+            });
+
+            // Replay the events to know which element is at every index.
+            let mut replayed: Vec<usize> = (0..input.len()).collect();
+            let mut recorded = Vec::new();
+            for event in &events {
+                match *event {
+                    Event::Compare(i, j) => recorded.push((replayed[i], replayed[j])),
+                    // Elements moved to a buffer stay in the replayed vector until written back.
+                    _                    => event.apply(&mut replayed),
+                }
+            }
+            assert_eq!(recorded.len(), compared.len(), "{} on {}", algorithm, pattern);
+            if algorithm.allocation() == Allocation::Linear {
+                // Elements compared while in the temporary buffer are designated by the index
+                // they were moved from, which may have been overwritten since.
+                continue;
+            }
+            let same = recorded.iter().zip(&compared).filter(|&(r, c)| r == c).count();
+            assert_eq!(same, compared.len(), "{} on {}", algorithm, pattern);
+        }
+    }
 }
 
-/// Validate sorting of a sorted vector (u32).
-#[test]
-fn efficient_quick_sortedvec_u32() {
-    let mut to_sort: Vec<u32> = vec![1, 2, 3, 4, 5];
+/// Validate the recursive calls are properly nested and the partitions are correct.
+#[test]
+fn trace_recursion() {
+    let input: Vec<i32> = patterns::generate(Pattern::Random, 1_000, 11);
+    for &algorithm in registry::all() {
+        let mut to_sort = input.clone();
+        let events = trace::record(&algorithm, &mut to_sort, |a, b| a.cmp(b));
+
+        let mut replayed = input.clone();
+        let mut calls: Vec<(usize, usize)> = Vec::new();
+        let mut nb_partitions = 0;
+        for event in &events {
+            event.apply(&mut replayed);
+            match *event {
+                Event::Enter(start, end) => {
+                    if let Some(&(outer_start, outer_end)) = calls.last() {
+                        assert!(outer_start <= start && end <= outer_end, "{}", algorithm);
+                    }
+                    calls.push((start, end));
+                },
+                Event::Exit(start, end) => assert_eq!(calls.pop(), Some((start, end))),
+                Event::Partition(start, pivot, end) => {
+                    nb_partitions += 1;
+                    assert!(start <= pivot && pivot < end);
+                    assert_eq!(calls.last(), Some(&(start, end)));
+                    let value = replayed[pivot];
+                    assert!(replayed[start..pivot].iter().all(|&x| x < value), "{}", algorithm);
+                    assert!(replayed[pivot+1..end].iter().all(|&x| x >= value), "{}", algorithm);
+                },
+                _ => {},
+            }
+        }
+        assert!(calls.is_empty());
 
-    test_sort_vec::<u32>(&mut to_sort, sorting::efficientsorts::quick::sort);
+        let partitions = algorithm.name() == "quicksort" || algorithm.name() == "introsort";
+        assert_eq!(nb_partitions > 0, partitions, "{}", algorithm);
+    }
 }
 
-/// Validate sorting of a sorted vector (u64).
-#[test]
-fn efficient_quick_sortedvec_u64() {
-    let mut to_sort: Vec<u64> = vec![1, 2, 3, 4, 5];
-
-    test_sort_vec::<u64>(&mut to_sort, sorting::efficientsorts::quick::sort);
+/// Validate the JSON lines written.
+#[test]
+fn trace_json() {
+    let initial: Vec<f64> = vec![2.5, f64::NAN, -1.0];
+    let mut to_sort = initial.clone();
+    let events = trace::record(&registry::find("mergesort").unwrap(), &mut to_sort,
+                               |a, b| floats::compare(a, b, NanPolicy::Last));
+    let mut json = Vec::new();
+    trace::write_json_lines(&mut json, &initial, &events).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    let lines: Vec<&str> = json.lines().collect();
+    assert_eq!(lines.len(), events.len() + 1);
+    assert_eq!(lines[0], r#"{"event":"start","values":[2.5,null,-1]}"#);
+    assert_eq!(lines[1], r#"{"event":"allocate","len":1}"#);
+    assert_eq!(lines[2], r#"{"event":"enter","start":0,"end":3}"#);
+    assert!(lines.contains(&r#"{"event":"write","i":0,"value":-1}"#));
+    assert!(lines.contains(&r#"{"event":"move_to_buffer","start":0,"len":1}"#));
+
+    let initial = vec!["b\"", "a\n"];
+    let mut to_sort = initial.clone();
+    let events = trace::record(&registry::find("bubblesort").unwrap(), &mut to_sort,
+                               |a, b| a.cmp(b));
+    let mut json = Vec::new();
+    trace::write_json_lines(&mut json, &initial, &events).unwrap();
+    assert_eq!(String::from_utf8(json).unwrap(),
+               "{\"event\":\"start\",\"values\":[\"b\\\"\",\"a\\n\"]}\n\
+                {\"event\":\"compare\",\"i\":0,\"j\":1}\n\
+                {\"event\":\"swap\",\"i\":0,\"j\":1}\n");
 }
 
 
-/// Validate sorting a vector of single precision values (f32).
-#[test]
-fn efficient_quick_sortedvec_f32() {
-    let mut to_sort: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-
-    test_sort_vec::<f32>(&mut to_sort, sorting::efficientsorts::quick::sort);
+// ################################################################################################
+// ################################################################################################
+// SVG
+
+/// Validate the frames: one per comparison, swap and write, ending sorted with all bars done.
+#[test]
+fn svg_frames() {
+    let initial: Vec<i32> = patterns::generate(Pattern::Random, 20, 7);
+    for &algorithm in registry::all() {
+        let mut to_sort = initial.clone();
+        let events = trace::record(&algorithm, &mut to_sort, |a, b| a.cmp(b));
+        let frames = svg::frames(&initial, &events);
+
+        let nb_operations = events.iter().filter(|event| {
+            matches!(**event, Event::Compare(..) | Event::Swap(..) | Event::Write(..))
+        }).count();
+        assert_eq!(frames.len(), nb_operations + 1, "{}", algorithm);
+        assert_eq!(frames[0].highlight, Highlight::None);
+
+        let last = frames.last().unwrap();
+        assert!(last.ranks.windows(2).all(|pair| pair[0] <= pair[1]), "{}", algorithm);
+        assert!(last.done.iter().all(|&done| done), "{}", algorithm);
+        for frame in &frames {
+            assert_eq!(frame.ranks.len(), initial.len());
+            assert_eq!(frame.done.len(), initial.len());
+        }
+    }
 }
 
-/// Validate sorting a vector of double precision values (f64).
-#[test]
-fn efficient_quick_sortedvec_f64() {
-    let mut to_sort: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// Validate the highlighted operations and the recursive calls shown.
+#[test]
+fn svg_highlights() {
+    let initial: Vec<i32> = vec![3, 1, 2];
+    let mut to_sort = initial.clone();
+    let events = trace::record(&registry::find("bubblesort").unwrap(), &mut to_sort,
+                               |a, b| a.cmp(b));
+    let frames = svg::frames(&initial, &events);
+    assert_eq!(frames[0].ranks, vec![2, 0, 1]);
+    assert_eq!(frames[1].highlight, Highlight::Compare(0, 1));
+    assert_eq!(frames[2].highlight, Highlight::Swap(0, 1));
+    assert_eq!(frames[2].ranks, vec![0, 2, 1]);
+    assert!(frames.iter().all(|frame| frame.range.is_none()));
+
+    let mut to_sort = initial.clone();
+    let events = trace::record(&registry::find("mergesort").unwrap(), &mut to_sort,
+                               |a, b| a.cmp(b));
+    let frames = svg::frames(&initial, &events);
+    assert_eq!(frames[0].range, None);
+    assert!(frames.iter().any(|frame| frame.range == Some((0, 3))));
+}
+
+/// Validate the rendered images.
+#[test]
+fn svg_render() {
+    let initial: Vec<i32> = patterns::generate(Pattern::Random, 10, 3);
+    let mut to_sort = initial.clone();
+    let events = trace::record(&registry::find("quicksort").unwrap(), &mut to_sort,
+                               |a, b| a.cmp(b));
+    let frames = svg::frames(&initial, &events);
+    let options = svg::Options::default();
+
+    for frame in &frames {
+        let image = svg::render_frame(frame, &options);
+        assert!(image.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(image.ends_with("</svg>\n"));
+        // The background and the bars.
+        let nb_rects = image.matches("<rect").count();
+        assert_eq!(nb_rects, 1 + initial.len() + frame.range.map_or(0, |_| 1));
+    }
 
-    test_sort_vec::<f64>(&mut to_sort, sorting::efficientsorts::quick::sort);
+    let animation = svg::render_animation(&frames, &options);
+    assert!(animation.starts_with("<svg"));
+    assert!(animation.ends_with("</svg>\n"));
+    assert!(animation.contains("<animate attributeName=\"height\""));
+    assert!(animation.contains("repeatCount=\"indefinite\""));
+    assert_eq!(animation.matches("<rect").count(), animation.matches("</rect>").count() + 1);
 }